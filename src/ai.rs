@@ -0,0 +1,119 @@
+use std::collections::VecDeque;
+
+use crate::config::{Direction, Settings};
+use crate::game_map::GameMap;
+use crate::snake::Snake;
+
+/// Directions tried in order, so ties always break the same way.
+const DIRECTIONS: [Direction; 4] = [Direction::North, Direction::East, Direction::South, Direction::West];
+
+/// Pick the next move for `snakes[player]`: the first step of a shortest
+/// path to the nearest food, or any move that survives the next tick when no
+/// food is reachable. None means every move is fatal.
+///
+/// Tails are treated as free, since they move out of the way on the same
+/// tick unless that snake is growing.
+pub fn choose_direction(snakes: &[&Snake], player: usize, map: &GameMap, settings: &Settings) -> Option<Direction> {
+    let snake = snakes[player];
+    let (h, w) = (map.height, map.width);
+    let mut blocked = vec![vec![false; w]; h];
+    for &(r, c) in &map.walls {
+        blocked[r][c] = true;
+    }
+    for s in snakes {
+        for &(r, c) in s.parts.iter().skip(1) {
+            blocked[r][c] = true;
+        }
+    }
+
+    let mut target = vec![vec![false; w]; h];
+    let primary = Some(snake.food);
+    let bonus = map.bonus_food.as_ref().map(|b| b.pos);
+    for (r, c) in primary.into_iter().chain(map.extra_food.iter().copied()).chain(bonus) {
+        if r < h && c < w {
+            target[r][c] = true;
+        }
+    }
+
+    let step = |from: (usize, usize), dir: Direction| {
+        Snake::step(from, dir, settings, map.border_min, map.border_max).filter(|&(r, c)| !blocked[r][c])
+    };
+
+    // Breadth-first search, remembering which first move reached each cell
+    let mut first_move: Vec<Vec<Option<Direction>>> = vec![vec![None; w]; h];
+    let mut queue = VecDeque::new();
+    let mut fallback = None;
+    for dir in DIRECTIONS {
+        if dir == snake.direction.opposite() && !snake.allow_reverse {
+            continue;
+        }
+        let Some((r, c)) = step(snake.head, dir) else {
+            continue;
+        };
+        if first_move[r][c].is_some() {
+            continue;
+        }
+        fallback.get_or_insert(dir);
+        first_move[r][c] = Some(dir);
+        queue.push_back((r, c));
+    }
+
+    while let Some((r, c)) = queue.pop_front() {
+        let first = first_move[r][c];
+        if target[r][c] {
+            return first;
+        }
+        for dir in DIRECTIONS {
+            if let Some((nr, nc)) = step((r, c), dir) {
+                if first_move[nr][nc].is_none() && (nr, nc) != snake.head {
+                    first_move[nr][nc] = first;
+                    queue.push_back((nr, nc));
+                }
+            }
+        }
+    }
+    fallback
+}
+
+/// Cell the AI would move player 1's head into next, for `--coach`.
+pub fn coach_hint(snakes: &[&Snake], map: &GameMap, settings: &Settings) -> Option<(usize, usize)> {
+    let snake = snakes.first()?;
+    let dir = choose_direction(snakes, 0, map, settings)?;
+    Snake::step(snake.head, dir, settings, map.border_min, map.border_max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use crate::config::{COACH_HINT_CHAR, INITIAL_SNAKE_LENGTH};
+
+    #[test]
+    fn test_coach_hint_matches_ai_move() {
+        let mut settings = Settings::parse_from(["test", "--coach"]).resolve();
+        settings.map_width = 20;
+        settings.map_height = 20;
+        let mut map = GameMap::new(20, 20);
+        let mut snake = Snake::new(20, 20);
+        snake.spawn_at((5, 5), Direction::East, INITIAL_SNAKE_LENGTH);
+        assert_eq!(snake.head, (5, 5));
+
+        // Food straight above: turn north
+        snake.food = (2, 5);
+        assert_eq!(choose_direction(&[&snake], 0, &map, &settings), Some(Direction::North));
+        assert_eq!(coach_hint(&[&snake], &map, &settings), Some((4, 5)));
+
+        // A wall in the way sends the shortest path around it
+        map.walls = vec![(4, 5), (4, 6)];
+        let dir = choose_direction(&[&snake], 0, &map, &settings).unwrap();
+        assert_eq!(dir, Direction::East);
+        let hint = coach_hint(&[&snake], &map, &settings).unwrap();
+        assert_eq!(Some(hint), Snake::step(snake.head, dir, &settings, map.border_min, map.border_max));
+
+        // The hint is drawn on the board, distinct from the food glyph
+        let frame = map.render(&[&snake], &settings, false, 0, 0.0);
+        assert_eq!(map.cells()[hint.0][hint.1].ch, COACH_HINT_CHAR);
+        assert_ne!(COACH_HINT_CHAR, settings.food);
+        assert!(frame.contains(COACH_HINT_CHAR));
+    }
+}
@@ -0,0 +1,213 @@
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::bell::{BellEvent, EventSink};
+use crate::config::{Direction, Settings};
+use crate::game_map::GameMap;
+use crate::snake::Snake;
+
+/// Ticks between `--announce` position summaries.
+pub const ANNOUNCE_SUMMARY_TICKS: usize = 40;
+
+/// What lies straight ahead of the head, for the "ahead" warnings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Ahead {
+    Clear,
+    Wall,
+    Body,
+}
+
+/// `--announce`: short plain-text lines describing the game for a screen
+/// reader or TTS, one per line, on stderr or a named pipe, never on the
+/// stdout the board is drawn to. Events are held until the tick is over so
+/// their lines can carry the scores it ended on. Opened once per session.
+pub struct Announcer<W: Write = Box<dyn Write>> {
+    out: W,
+    pending: Vec<BellEvent>,
+    ahead: [Ahead; 2],
+    ticks: usize,
+}
+
+impl Announcer {
+    /// Open the announcement channel: stderr for `-`, else the file or
+    /// named pipe at `target` (opening a pipe waits for its reader).
+    pub fn new(target: &Path) -> io::Result<Self> {
+        let out: Box<dyn Write> = if target == Path::new("-") {
+            Box::new(io::stderr())
+        } else {
+            Box::new(OpenOptions::new().append(true).create(true).open(target)?)
+        };
+        Ok(Announcer::with_writer(out))
+    }
+}
+
+impl<W: Write> Announcer<W> {
+    pub fn with_writer(out: W) -> Self {
+        Announcer { out, pending: Vec::new(), ahead: [Ahead::Clear; 2], ticks: 0 }
+    }
+
+    fn say(&mut self, line: &str) {
+        // A reader that went away shouldn't stop the game
+        let _ = writeln!(self.out, "{line}");
+        let _ = self.out.flush();
+    }
+
+    /// End of a tick: the events it raised, a warning when the cell ahead
+    /// of either player has just become deadly, and now and then where
+    /// things are. Two players' lines start with whose they are.
+    pub fn tick(&mut self, snakes: &[&Snake], map: &GameMap, settings: &Settings) {
+        let scores: Vec<usize> = snakes.iter().map(|s| s.score).collect();
+        for event in std::mem::take(&mut self.pending) {
+            if let Some(line) = event_line(event, &scores) {
+                self.say(&line);
+            }
+        }
+
+        for (i, snake) in snakes.iter().enumerate().take(2) {
+            let ahead = look_ahead(snake, map, settings);
+            if ahead != self.ahead[i] {
+                match ahead {
+                    Ahead::Wall => self.say(&format!("{}wall ahead", player_prefix(i, snakes.len()))),
+                    Ahead::Body => self.say(&format!("{}body ahead", player_prefix(i, snakes.len()))),
+                    Ahead::Clear => {}
+                }
+                self.ahead[i] = ahead;
+            }
+        }
+
+        self.ticks += 1;
+        if self.ticks.is_multiple_of(ANNOUNCE_SUMMARY_TICKS) {
+            for (i, snake) in snakes.iter().enumerate() {
+                self.say(&format!("{}{}", player_prefix(i, snakes.len()), summary(snake)));
+            }
+        }
+    }
+
+    /// The round is over: whatever was still pending, then the final scores.
+    pub fn game_over(&mut self, scores: &[usize]) {
+        for event in std::mem::take(&mut self.pending) {
+            if let Some(line) = event_line(event, scores) {
+                self.say(&line);
+            }
+        }
+        self.say(&format!("game over, {}", scores_text(scores)));
+        self.ahead = [Ahead::Clear; 2];
+        self.ticks = 0;
+    }
+}
+
+impl<W: Write> EventSink for Announcer<W> {
+    fn send(&mut self, event: BellEvent) {
+        self.pending.push(event);
+    }
+}
+
+/// The announcement for `event` once the players' scores are `scores`.
+/// Beats aren't worth saying.
+pub fn event_line(event: BellEvent, scores: &[usize]) -> Option<String> {
+    match event {
+        BellEvent::Food => Some(format!("food eaten, {}", scores_text(scores))),
+        BellEvent::Bonus => Some(format!("bonus eaten, {}", scores_text(scores))),
+        BellEvent::Death => Some("crashed".to_string()),
+        BellEvent::LevelUp => Some("level up".to_string()),
+        BellEvent::Beat => None,
+    }
+}
+
+/// "score 5" for one player, "scores 5 and 3" for two.
+fn scores_text(scores: &[usize]) -> String {
+    match scores {
+        [score] => format!("score {score}"),
+        _ => format!("scores {}", scores.iter().map(|s| s.to_string()).collect::<Vec<_>>().join(" and ")),
+    }
+}
+
+/// "player 2: " ahead of a player's own lines in a two-player game.
+fn player_prefix(player: usize, players: usize) -> String {
+    if players > 1 { format!("player {}: ", player + 1) } else { String::new() }
+}
+
+fn look_ahead(snake: &Snake, map: &GameMap, settings: &Settings) -> Ahead {
+    match Snake::step(snake.head, snake.direction, settings, map.border_min, map.border_max) {
+        None => Ahead::Wall,
+        Some(next) if map.walls.contains(&next) => Ahead::Wall,
+        Some(next) if snake.occupies(next) => Ahead::Body,
+        Some(_) => Ahead::Clear,
+    }
+}
+
+/// Heading, length and the food's offset from the head,
+/// e.g. "heading east, length 5, food 3 down 2 left".
+pub fn summary(snake: &Snake) -> String {
+    let heading = match snake.direction {
+        Direction::North => "north",
+        Direction::South => "south",
+        Direction::East => "east",
+        Direction::West => "west",
+    };
+    let (head, food) = (snake.head, snake.food);
+    let mut offsets = Vec::new();
+    if food.0 != head.0 {
+        offsets.push(format!("{} {}", food.0.abs_diff(head.0), if food.0 < head.0 { "up" } else { "down" }));
+    }
+    if food.1 != head.1 {
+        offsets.push(format!("{} {}", food.1.abs_diff(head.1), if food.1 < head.1 { "left" } else { "right" }));
+    }
+    let food = if offsets.is_empty() { "here".to_string() } else { offsets.join(" ") };
+    format!("heading {heading}, length {}, food {food}", snake.length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::INITIAL_SNAKE_LENGTH;
+    use clap::Parser;
+
+    #[test]
+    fn test_events_produce_announcements() {
+        let settings = Settings::parse_from(["test"]).resolve();
+        let mut snake = Snake::new(20, 20);
+        snake.spawn_at((10, 10), Direction::East, INITIAL_SNAKE_LENGTH);
+        snake.food = (7, 12);
+        snake.score = 5;
+        let map = GameMap::new(20, 20);
+
+        let mut announcer = Announcer::with_writer(Vec::new());
+        announcer.send(BellEvent::Food);
+        announcer.send(BellEvent::Beat);
+        announcer.tick(&[&snake], &map, &settings);
+        // Heading into the east edge
+        snake.spawn_at((10, 19), Direction::East, INITIAL_SNAKE_LENGTH);
+        announcer.tick(&[&snake], &map, &settings);
+        announcer.tick(&[&snake], &map, &settings);
+        announcer.send(BellEvent::Death);
+        announcer.game_over(&[5]);
+
+        let text = String::from_utf8(announcer.out).unwrap();
+        assert_eq!(text.lines().collect::<Vec<_>>(), ["food eaten, score 5", "wall ahead", "crashed", "game over, score 5"]);
+
+        assert_eq!(event_line(BellEvent::Bonus, &[8]).as_deref(), Some("bonus eaten, score 8"));
+        assert_eq!(event_line(BellEvent::Beat, &[8]), None);
+        snake.spawn_at((10, 10), Direction::East, INITIAL_SNAKE_LENGTH);
+        assert_eq!(summary(&snake), "heading east, length 3, food 3 up 2 right");
+    }
+
+    #[test]
+    fn test_both_players_are_announced() {
+        let settings = Settings::parse_from(["test", "--multiplayer"]).resolve();
+        let (mut p1, mut p2) = (Snake::new(20, 20), Snake::new(20, 20));
+        p1.spawn_at((5, 10), Direction::East, INITIAL_SNAKE_LENGTH);
+        p2.spawn_at((15, 19), Direction::East, INITIAL_SNAKE_LENGTH);
+        (p1.score, p2.score) = (4, 2);
+        let map = GameMap::new(20, 20);
+
+        let mut announcer = Announcer::with_writer(Vec::new());
+        announcer.send(BellEvent::Food);
+        announcer.tick(&[&p1, &p2], &map, &settings);
+        announcer.game_over(&[4, 2]);
+
+        let text = String::from_utf8(announcer.out).unwrap();
+        assert_eq!(text.lines().collect::<Vec<_>>(), ["food eaten, scores 4 and 2", "player 2: wall ahead", "game over, scores 4 and 2"]);
+    }
+}
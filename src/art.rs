@@ -0,0 +1,111 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::config::ART_TARGET_SCORE;
+
+/// Picture for `--art-target`: board cells the snake's body should cover.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtTarget {
+    pub cells: Vec<(usize, usize)>,
+}
+
+impl ArtTarget {
+    /// One text line per board row. Any character other than a space or
+    /// `.` marks a target cell.
+    pub fn parse(text: &str) -> ArtTarget {
+        let mut cells = Vec::new();
+        for (r, line) in text.lines().enumerate() {
+            for (c, ch) in line.chars().enumerate() {
+                if ch != ' ' && ch != '.' {
+                    cells.push((r, c));
+                }
+            }
+        }
+        ArtTarget { cells }
+    }
+
+    pub fn load(path: &Path) -> io::Result<ArtTarget> {
+        let art = ArtTarget::parse(&fs::read_to_string(path)?);
+        if art.cells.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "no target cells in pattern"));
+        }
+        Ok(art)
+    }
+
+    pub fn fits(&self, width: usize, height: usize) -> bool {
+        self.cells.iter().all(|&(r, c)| r < height && c < width)
+    }
+}
+
+/// Number of target cells currently under the snake.
+pub fn overlap<'a>(parts: impl IntoIterator<Item = &'a (usize, usize)>, target: &ArtTarget) -> usize {
+    let mut covered: Vec<(usize, usize)> = parts.into_iter().filter(|p| target.cells.contains(p)).copied().collect();
+    covered.sort_unstable();
+    covered.dedup();
+    covered.len()
+}
+
+/// Best coverage a snake has reached so far. Only new records score, so
+/// sliding back and forth over the same cells earns nothing.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ArtProgress {
+    pub best: usize,
+}
+
+impl ArtProgress {
+    /// Points earned for reaching `covered` target cells this tick.
+    pub fn award(&mut self, covered: usize) -> usize {
+        let gained = covered.saturating_sub(self.best);
+        self.best = self.best.max(covered);
+        gained * ART_TARGET_SCORE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Direction, Settings, INITIAL_SNAKE_LENGTH};
+    use crate::snake::Snake;
+    use clap::Parser;
+
+    #[test]
+    fn test_overlap_scoring() {
+        let target = ArtTarget::parse("..##\n..#.\n");
+        assert_eq!(target.cells, vec![(0, 2), (0, 3), (1, 2)]);
+        assert!(target.fits(4, 2));
+        assert!(!target.fits(3, 2));
+
+        let body = [(0, 0), (0, 1), (0, 2)];
+        assert_eq!(overlap(&body, &target), 1);
+        assert_eq!(overlap(&[(0, 2), (0, 3), (1, 2), (1, 1)], &target), target.cells.len());
+        assert_eq!(overlap(&[(1, 0), (1, 1)], &target), 0);
+
+        let mut progress = ArtProgress::default();
+        assert_eq!(progress.award(1), ART_TARGET_SCORE);
+        assert_eq!(progress.award(1), 0);
+        // Dropping back and recovering the same coverage earns nothing
+        assert_eq!(progress.award(0), 0);
+        assert_eq!(progress.award(1), 0);
+        assert_eq!(progress.award(3), 2 * ART_TARGET_SCORE);
+    }
+
+    #[test]
+    fn test_art_award_survives_the_next_tick() {
+        let settings = Settings::parse_from(["test"]).resolve();
+        let target = ArtTarget::parse("..........\n..........\n.....###..\n");
+        let mut snake = Snake::new(10, 5);
+        snake.spawn_at((2, 7), Direction::East, INITIAL_SNAKE_LENGTH);
+        snake.food = (0, 0);
+
+        let mut progress = ArtProgress::default();
+        snake.art_points += progress.award(overlap(&snake.parts, &target));
+        snake.rescore(&settings);
+        assert_eq!(snake.score, 3 * ART_TARGET_SCORE);
+
+        // Rescoring on the next move keeps the bonus
+        snake.update_movement(&settings, &[], (0, 0), (5, 10));
+        assert!(!snake.is_dead);
+        assert_eq!(snake.score, 3 * ART_TARGET_SCORE);
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::VecDeque;
+
+/// Something the terminal bell can announce.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BellEvent {
+    Food,
+    Bonus,
+    Death,
+    LevelUp,
+    Beat,
+}
+
+impl BellEvent {
+    /// Beep (true) or rest (false) for each of the next frames.
+    pub fn pattern(self) -> &'static [bool] {
+        match self {
+            BellEvent::Food | BellEvent::Beat => &[true],
+            BellEvent::Bonus => &[true, true],
+            BellEvent::Death => &[true, false, true, false, true, true],
+            BellEvent::LevelUp => &[true, false, true],
+        }
+    }
+
+    /// Name handed to `--on-event` commands. Beats fire every tick, so
+    /// they aren't passed on.
+    pub fn name(self) -> Option<&'static str> {
+        match self {
+            BellEvent::Food => Some("food"),
+            BellEvent::Bonus => Some("bonus"),
+            BellEvent::Death => Some("death"),
+            BellEvent::LevelUp => Some("level-up"),
+            BellEvent::Beat => None,
+        }
+    }
+}
+
+/// Anything that reacts to game events: the bell, `--on-event` commands.
+pub trait EventSink {
+    fn send(&mut self, event: BellEvent);
+}
+
+impl<S: EventSink> EventSink for Option<S> {
+    fn send(&mut self, event: BellEvent) {
+        if let Some(sink) = self {
+            sink.send(event);
+        }
+    }
+}
+
+/// Spreads bell patterns over the following frames, at most one `\x07`
+/// per frame. Overlapping patterns are merged.
+pub struct Bell {
+    muted: bool,
+    queue: VecDeque<bool>,
+}
+
+impl Bell {
+    pub fn new(muted: bool) -> Self {
+        Bell { muted, queue: VecDeque::new() }
+    }
+
+    pub fn ring(&mut self, event: BellEvent) {
+        if self.muted {
+            return;
+        }
+        for (i, &beep) in event.pattern().iter().enumerate() {
+            match self.queue.get_mut(i) {
+                Some(slot) => *slot |= beep,
+                None => self.queue.push_back(beep),
+            }
+        }
+    }
+
+    /// Bytes to write this frame: a bell or nothing.
+    pub fn tick(&mut self) -> &'static str {
+        if self.queue.pop_front().unwrap_or(false) { "\x07" } else { "" }
+    }
+}
+
+impl EventSink for Bell {
+    fn send(&mut self, event: BellEvent) {
+        self.ring(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(bell: &mut Bell, frames: usize) -> String {
+        (0..frames).map(|_| if bell.tick().is_empty() { '.' } else { 'B' }).collect()
+    }
+
+    #[test]
+    fn test_bell_patterns() {
+        let mut bell = Bell::new(false);
+        for (event, expected) in [
+            (BellEvent::Food, "B......."),
+            (BellEvent::Bonus, "BB......"),
+            (BellEvent::Death, "B.B.BB.."),
+            (BellEvent::LevelUp, "B.B....."),
+            (BellEvent::Beat, "B......."),
+        ] {
+            bell.ring(event);
+            assert_eq!(play(&mut bell, 8), expected, "{event:?}");
+        }
+
+        // Food during a death pattern merges rather than doubling up
+        bell.ring(BellEvent::Death);
+        bell.tick();
+        bell.ring(BellEvent::Food);
+        assert_eq!(play(&mut bell, 6), "BB.BB.");
+
+        let mut silent = Bell::new(true);
+        silent.ring(BellEvent::Death);
+        silent.ring(BellEvent::Bonus);
+        assert_eq!(play(&mut silent, 8), "........");
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Countdown for `--time-attack`: the round ends once the deadline passes.
+pub struct TimeAttack {
+    deadline: Instant,
+}
+
+impl TimeAttack {
+    pub fn new(secs: u64, now: Instant) -> Option<Self> {
+        if secs == 0 {
+            return None;
+        }
+        Some(TimeAttack { deadline: now + Duration::from_secs(secs) })
+    }
+
+    pub fn remaining(&self, now: Instant) -> Duration {
+        self.deadline.saturating_duration_since(now)
+    }
+
+    pub fn is_over(&self, now: Instant) -> bool {
+        now >= self.deadline
+    }
+
+    /// Push the deadline back, e.g. by the time spent paused.
+    pub fn extend(&mut self, by: Duration) {
+        self.deadline += by;
+    }
+
+    pub fn status(&self, now: Instant) -> String {
+        let secs = self.remaining(now).as_secs_f64().ceil() as u64;
+        format!("Time: {}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+/// Fires once something has waited `secs` without input, e.g.
+/// `--gameover-timeout` returning to the menu or `--idle-timeout` ending an
+/// unattended game.
+pub struct IdleTimeout {
+    timeout: Duration,
+    deadline: Instant,
+}
+
+impl IdleTimeout {
+    pub fn new(secs: u64, now: Instant) -> Option<Self> {
+        if secs == 0 {
+            return None;
+        }
+        let timeout = Duration::from_secs(secs);
+        Some(IdleTimeout { timeout, deadline: now + timeout })
+    }
+
+    pub fn expired(&self, now: Instant) -> bool {
+        now >= self.deadline
+    }
+
+    /// Input arrived: start the full wait over.
+    pub fn reset(&mut self, now: Instant) {
+        self.deadline = now + self.timeout;
+    }
+
+    /// Push the deadline back, e.g. by the time spent paused.
+    pub fn extend(&mut self, by: Duration) {
+        self.deadline += by;
+    }
+}
+
+/// Frames of history `SlowDetector` judges the frame rate over.
+pub const SLOW_WINDOW: usize = 20;
+
+/// Spots a terminal that can't keep up (e.g. over slow SSH): frames that
+/// take more than `margin_pct` percent longer than their target. A few
+/// spikes don't count; three quarters of the recent window must be over
+/// budget to flag it, and it only clears once most frames are back on time.
+pub struct SlowDetector {
+    margin_pct: u32,
+    recent: VecDeque<bool>,
+    slow: bool,
+}
+
+impl SlowDetector {
+    /// None when `margin_pct` is 0 (detection off).
+    pub fn new(margin_pct: u32) -> Option<Self> {
+        (margin_pct > 0).then(|| SlowDetector { margin_pct, recent: VecDeque::with_capacity(SLOW_WINDOW), slow: false })
+    }
+
+    /// Note one frame that took `actual` against a `target` delay.
+    pub fn record(&mut self, actual: Duration, target: Duration) {
+        let over = actual.as_secs_f64() > target.as_secs_f64() * (1.0 + self.margin_pct as f64 / 100.0);
+        if self.recent.len() == SLOW_WINDOW {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(over);
+        let over_count = self.recent.iter().filter(|&&o| o).count();
+        if self.recent.len() == SLOW_WINDOW && over_count * 4 >= SLOW_WINDOW * 3 {
+            self.slow = true;
+        } else if over_count * 4 <= SLOW_WINDOW {
+            self.slow = false;
+        }
+    }
+
+    pub fn is_slow(&self) -> bool {
+        self.slow
+    }
+}
+
+/// Movement budget for one snake when the players run at different
+/// speeds (`--p2-speed`). The loop ticks at the faster snake's pace and
+/// each snake banks the tick's time, moving once it has a whole step of
+/// its own period saved up.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StepAccumulator {
+    banked_ms: u64,
+}
+
+impl StepAccumulator {
+    /// Bank a tick of `tick_ms`; true if the snake, which moves every
+    /// `period_ms`, steps this tick. At most one step is ever owed.
+    pub fn advance(&mut self, tick_ms: u64, period_ms: u64) -> bool {
+        self.banked_ms += tick_ms;
+        if self.banked_ms < period_ms {
+            return false;
+        }
+        self.banked_ms = (self.banked_ms - period_ms).min(period_ms);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_attack_deadline() {
+        let start = Instant::now();
+        let mut ta = TimeAttack::new(30, start).unwrap();
+        assert!(!ta.is_over(start));
+        assert!(!ta.is_over(start + Duration::from_millis(29_999)));
+        assert!(ta.is_over(start + Duration::from_secs(30)));
+        assert_eq!(ta.status(start + Duration::from_millis(500)), "Time: 0:30");
+
+        ta.extend(Duration::from_secs(5));
+        assert!(!ta.is_over(start + Duration::from_secs(30)));
+        assert!(ta.is_over(start + Duration::from_secs(35)));
+
+        assert!(TimeAttack::new(0, start).is_none());
+    }
+
+    #[test]
+    fn test_idle_timeout_resets_on_input() {
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+        let mut idle = IdleTimeout::new(10, start).unwrap();
+        assert!(!idle.expired(at(9_999)));
+        assert!(idle.expired(at(10_000)));
+
+        // A move at 8s restarts the full span
+        idle.reset(at(8_000));
+        assert!(!idle.expired(at(17_999)));
+        assert!(idle.expired(at(18_000)));
+
+        // Time paused doesn't count
+        idle.extend(Duration::from_secs(5));
+        assert!(!idle.expired(at(22_999)));
+        assert!(idle.expired(at(23_000)));
+
+        assert!(IdleTimeout::new(0, start).is_none());
+    }
+
+    #[test]
+    fn test_faster_snake_steps_more() {
+        let (fast_ms, slow_ms) = (100, 150);
+        let mut fast = StepAccumulator::default();
+        let mut slow = StepAccumulator::default();
+        let (mut fast_steps, mut slow_steps) = (0, 0);
+        // 3 seconds of ticks at the faster snake's pace
+        for _ in 0..30 {
+            fast_steps += fast.advance(fast_ms, fast_ms) as usize;
+            slow_steps += slow.advance(fast_ms, slow_ms) as usize;
+        }
+        assert_eq!(fast_steps, 30);
+        assert_eq!(slow_steps, 20);
+
+        // Equal speeds step together every tick
+        let mut same = StepAccumulator::default();
+        assert!((0..10).all(|_| same.advance(200, 200)));
+    }
+
+    #[test]
+    fn test_slow_detector_ignores_spikes() {
+        let target = Duration::from_millis(100);
+        let (on_time, over) = (Duration::from_millis(105), Duration::from_millis(160));
+        let mut slow = SlowDetector::new(25).unwrap();
+
+        // A spike every few frames is not sustained slowness
+        for i in 0..100 {
+            slow.record(if i % 4 == 0 { over } else { on_time }, target);
+            assert!(!slow.is_slow(), "frame {i}");
+        }
+        // A short burst isn't either
+        for _ in 0..10 {
+            slow.record(over, target);
+        }
+        assert!(!slow.is_slow());
+
+        // Keep going over budget and it's flagged
+        for _ in 0..10 {
+            slow.record(over, target);
+        }
+        assert!(slow.is_slow());
+        // One good frame doesn't clear it; a recovered run does
+        slow.record(on_time, target);
+        assert!(slow.is_slow());
+        for _ in 0..SLOW_WINDOW {
+            slow.record(on_time, target);
+        }
+        assert!(!slow.is_slow());
+
+        assert!(SlowDetector::new(0).is_none());
+    }
+}
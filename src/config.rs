@@ -1,15 +1,223 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+use crossterm::style::Color;
 use serde::Deserialize;
+use std::fmt;
 use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::art::ArtTarget;
+use crate::input::{KeyBindings, ScriptEnd};
+use crate::mapfile::MapFile;
+use crate::menu::MenuTheme;
+use crate::replay::RecordFrom;
+use crate::rng::{GameRng, RngAlgorithm};
+use crate::runcode::RunCode;
+use crate::score::{ScoreWeights, TieBreaker};
+use crate::screen::supports_alt_screen;
+use crate::skin::{Skin, SkinUnlocks};
+use crate::snake::{spawn_cells, twin_spawn};
+use crate::snapshot::Snapshot;
 
 pub const DEFAULT_MAP_WIDTH: usize = 20;
 pub const DEFAULT_MAP_HEIGHT: usize = 20;
 pub const MAP_CHAR: char = '.';
 pub const WALL_CHAR: char = '#';
+pub const BORDER_CHAR: char = ','; // shrunk-out border and --shape region, told apart from walls
 pub const INITIAL_SNAKE_LENGTH: usize = 3;
 pub const BONUS_FOOD_CHAR: char = '$';
 pub const BONUS_FOOD_SCORE: usize = 3;
+pub const COACH_HINT_CHAR: char = '+';
+pub const WARP_TILE_CHAR: char = '%';
+pub const BOMB_CHAR: char = '!';
+pub const PORTAL_CHAR: char = '&';
+pub const KEY_CHAR: char = 'k';
+pub const GATE_CHAR: char = '=';
+pub const EDGE_GAP_CHAR: char = '-';
+pub const BOMB_SPAWN_ODDS: usize = 50; // 1 in N frames while no bomb is out
+pub const REWIND_CHAR: char = '<';
+pub const REWIND_SPAWN_ODDS: usize = 80; // 1 in N frames while no rewind pickup is out
+pub const REWIND_TICKS: usize = 5; // moves undone by one rewind
+pub const SPLIT_CHAR: char = 'Y';
+pub const SPLIT_SPAWN_ODDS: usize = 120; // 1 in N frames while no split pickup is out
+pub const SPLIT_MIN_LENGTH: usize = 4; // shorter snakes can't split
+pub const FOOD_VALUE_MAX: usize = 5; // --food-values: pellets are worth 1..=this
+pub const FOOD_VALUE_ODDS: usize = 3; // each step up in value is 1 in N as likely
+pub const COIN_CHAR: char = 'c';
+pub const COIN_DROP_ODDS: usize = 8; // 1 in N vacated tail cells
+pub const COIN_MAX_VALUE: usize = 3;
+pub const COIN_LIFETIME: usize = 60; // frames
+pub const HUNGRY_MIN_LENGTH: usize = 2;
+pub const STYLE_POINTS_PER_TICK: usize = 2; // --style-points cap on near misses scored per move
+pub const NEAR_MISS_FLASH_FRAMES: usize = 6;
+pub const GROWING_BOARD_MIN: usize = 10; // smallest starting side for --growing-board
+pub const GROWING_BOARD_STEP: usize = 5; // segments of growth per one-cell expansion
+pub const ART_TARGET_CHAR: char = ':';
+pub const ART_TARGET_SCORE: usize = 1; // per newly covered target cell
 pub const BONUS_FOOD_LIFETIME: usize = 30; // frames
+pub const BONUS_SEQUENCE_LEN: usize = 8; // --deterministic-bonus spots, cycled
+pub const LOOP_REPLAY_PAUSE: Duration = Duration::from_millis(1500); // "Looping..." between --loop-replay runs
+pub const POLL_INTERVAL: Duration = Duration::from_millis(10);
+pub const LOW_POWER_POLL_INTERVAL: Duration = Duration::from_millis(30);
+
+/// Named glyph/color sets for walls and shrunk borders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum WallTheme {
+    Classic,
+    Brick,
+    Stone,
+    Hedge,
+}
+
+impl WallTheme {
+    pub fn glyph(self) -> (char, Color) {
+        match self {
+            WallTheme::Classic => (WALL_CHAR, Color::White),
+            WallTheme::Brick => ('▓', Color::DarkRed),
+            WallTheme::Stone => ('█', Color::Grey),
+            WallTheme::Hedge => ('♣', Color::DarkGreen),
+        }
+    }
+}
+
+/// How `--progressive-speed` ramps the frame delay as the snake grows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SpeedCurve {
+    /// 5ms faster per segment grown
+    Linear,
+    /// 3% faster per segment grown
+    Exponential,
+    /// 25ms faster every 5 segments grown
+    Stepped,
+}
+
+impl SpeedCurve {
+    /// Frame delay for a snake that has grown `growth` segments past its
+    /// starting length, never below `floor`.
+    pub fn delay(self, speed: u64, growth: usize, floor: u64) -> u64 {
+        let delay = match self {
+            SpeedCurve::Linear => speed.saturating_sub(growth as u64 * 5),
+            SpeedCurve::Exponential => (speed as f64 * 0.97f64.powi(growth.min(i32::MAX as usize) as i32)).round() as u64,
+            SpeedCurve::Stepped => speed.saturating_sub((growth / 5) as u64 * 25),
+        };
+        delay.max(floor)
+    }
+}
+
+/// Outline of the playfield for `--shape`; cells outside it are walls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum BoardShape {
+    /// The whole rectangular map
+    Rect,
+    /// The largest ellipse that fits the map
+    Circle,
+    /// The largest diamond that fits the map
+    Diamond,
+}
+
+impl BoardShape {
+    /// Whether `pos` is playable on a `width` x `height` board of this shape.
+    pub fn contains(self, (r, c): (usize, usize), width: usize, height: usize) -> bool {
+        // Distance from the center, in half-widths/half-heights of the board
+        let dy = (r as f64 + 0.5 - height as f64 / 2.0) / (height as f64 / 2.0);
+        let dx = (c as f64 + 0.5 - width as f64 / 2.0) / (width as f64 / 2.0);
+        match self {
+            BoardShape::Rect => r < height && c < width,
+            BoardShape::Circle => dx * dx + dy * dy <= 1.0,
+            BoardShape::Diamond => dx.abs() + dy.abs() <= 1.0,
+        }
+    }
+}
+
+/// How the second head of `--two-headed` is steered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TwinMode {
+    /// Always heads the opposite way to the first head
+    Mirrored,
+    /// Steered on its own with the arrow keys
+    Independent,
+}
+
+/// Named starting spots for `--p1-spawn` / `--p2-spawn`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SpawnPoint {
+    Center,
+    Top,
+    Bottom,
+    Left,
+    Right,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl SpawnPoint {
+    /// Head cell on a `width`x`height` board, a quarter of the way in from
+    /// the named edges.
+    pub fn head(self, width: usize, height: usize) -> (usize, usize) {
+        let (top, mid_r, bottom) = (height / 4, height / 2, height - 1 - height / 4);
+        let (left, mid_c, right) = (width / 4, width / 2, width - 1 - width / 4);
+        match self {
+            SpawnPoint::Center => (mid_r, mid_c),
+            SpawnPoint::Top => (top, mid_c),
+            SpawnPoint::Bottom => (bottom, mid_c),
+            SpawnPoint::Left => (mid_r, left),
+            SpawnPoint::Right => (mid_r, right),
+            SpawnPoint::TopLeft => (top, left),
+            SpawnPoint::TopRight => (top, right),
+            SpawnPoint::BottomLeft => (bottom, left),
+            SpawnPoint::BottomRight => (bottom, right),
+        }
+    }
+
+    /// Default heading: into the board, away from the nearest edge.
+    pub fn facing(self) -> Direction {
+        match self {
+            SpawnPoint::Center | SpawnPoint::Left | SpawnPoint::TopLeft | SpawnPoint::BottomLeft => Direction::East,
+            SpawnPoint::Right | SpawnPoint::TopRight | SpawnPoint::BottomRight => Direction::West,
+            SpawnPoint::Top => Direction::South,
+            SpawnPoint::Bottom => Direction::North,
+        }
+    }
+}
+
+/// Player 1's placement from `--start-pos`: a named spot, a random open
+/// spot drawn from the round's rng, or an explicit `row,col` head cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartPos {
+    Point(SpawnPoint),
+    Random,
+    Cell(usize, usize),
+}
+
+impl FromStr for StartPos {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("random") {
+            return Ok(StartPos::Random);
+        }
+        if let Some((r, c)) = s.split_once(',') {
+            let parse = |v: &str| v.trim().parse::<usize>().map_err(|_| format!("invalid start cell '{s}' (expected row,col)"));
+            return Ok(StartPos::Cell(parse(r)?, parse(c)?));
+        }
+        SpawnPoint::from_str(s, true)
+            .map(StartPos::Point)
+            .map_err(|_| format!("unknown start position '{s}' (expected a spawn point, random or row,col)"))
+    }
+}
+
+impl fmt::Display for StartPos {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StartPos::Point(p) => write!(f, "{}", p.to_possible_value().map_or_else(String::new, |v| v.get_name().to_string())),
+            StartPos::Random => write!(f, "random"),
+            StartPos::Cell(r, c) => write!(f, "{r},{c}"),
+        }
+    }
+}
 
 #[derive(Parser, Debug, Clone)]
 #[command(name = "snake-term", about = "Terminal Snake game written in Rust")]
@@ -18,10 +226,18 @@ pub struct Settings {
     #[arg(long, default_value_t = 200)]
     pub speed: u64,
 
+    /// Movement speed in cells per second, instead of --speed (delay = 1000 / N ms, rounded)
+    #[arg(long, value_name = "N")]
+    pub cells_per_second: Option<f64>,
+
     /// Snake body character
     #[arg(long, default_value_t = '@')]
     pub body: char,
 
+    /// Tail glyph, drawn in a dimmer shade of the body color (default: same as the body)
+    #[arg(long)]
+    pub tail_char: Option<char>,
+
     /// Head glyph when moving west (left)
     #[arg(long, default_value_t = '<')]
     pub head_w: char,
@@ -46,42 +262,346 @@ pub struct Settings {
     #[arg(long, default_value_t = '*')]
     pub food: char,
 
+    /// Number of food pellets kept on the board
+    #[arg(long, default_value_t = 1)]
+    pub food_count: usize,
+
+    /// Pellets on the board at the start (0 = same as --food-count); a surplus drains as it's eaten
+    #[arg(long, default_value_t = 0)]
+    pub start_food: usize,
+
+    /// Keep one pellet per this many playable cells instead of a fixed --food-count, rescaled as the border moves (0 = off)
+    #[arg(long, default_value_t = 0, value_name = "CELLS", conflicts_with = "food_count")]
+    pub auto_food: usize,
+
+    /// Respawned food lands at least this many steps from the pellet just eaten, when the board allows
+    #[arg(long, default_value_t = 0, value_name = "CELLS")]
+    pub food_min_spread: usize,
+
+    /// Pellets are worth 1-5 points, drawn as their value; the higher the rarer
+    #[arg(long)]
+    pub food_values: bool,
+
+    /// Each eaten pellet leaves a permanent wall behind
+    #[arg(long)]
+    pub food_walls: bool,
+
+    /// Bonus food appears at a fixed cycle of spots drawn from the seed, to learn for practice
+    #[arg(long)]
+    pub deterministic_bonus: bool,
+
+    /// Body/head skin, overriding the glyph options; higher skins unlock with the high score
+    #[arg(long, value_enum, default_value_t = Skin::Classic)]
+    pub skin: Skin,
+
+    /// High score each skin unlocks at, as skin=score pairs; skins left out are free
+    #[arg(long, value_name = "LIST", default_value_t = SkinUnlocks::default())]
+    pub skin_unlocks: SkinUnlocks,
+
+    /// Wall theme (classic '#', brick '▓', stone '█', hedge '♣')
+    #[arg(long, value_enum, default_value_t = WallTheme::Classic)]
+    pub wall_theme: WallTheme,
+
+    /// Wall glyph, overriding the theme's character
+    #[arg(long)]
+    pub wall_char: Option<char>,
+
+    /// Glyph for the area outside a shrinking or growing border or the --shape, so it isn't mistaken for walls
+    #[arg(long, default_value_t = BORDER_CHAR)]
+    pub border_char: char,
+
     /// RNG seed (0 = use time)
     #[arg(long, default_value_t = 0)]
     pub seed: u64,
 
+    /// RNG algorithm; the pinned default keeps seeds reproducible across versions
+    #[arg(long, value_enum, default_value_t = RngAlgorithm::Xorshift)]
+    pub rng: RngAlgorithm,
+
+    /// Play the setup from a run code shown at game over (seed, board size, mode flags); other match settings play at their defaults
+    #[arg(long, value_name = "CODE")]
+    pub run_code: Option<String>,
+
+    /// Show the round's seed and a shareable run code on the game-over screen
+    #[arg(long)]
+    pub show_run_code: bool,
+
     /// Hide the score display
     #[arg(long)]
     pub hide_score: bool,
 
+    /// Disable colors (also honored via the NO_COLOR environment variable)
+    #[arg(long)]
+    pub no_color: bool,
+
+    /// Draw inline instead of on the alternate screen (automatic when TERM is dumb or unset)
+    #[arg(long)]
+    pub no_alt_screen: bool,
+
+    /// Leave bracketed paste off; pasted text then arrives as ordinary key presses
+    #[arg(long)]
+    pub no_bracketed_paste: bool,
+
+    /// Render the heads sliding between cells (redraws every input poll)
+    #[arg(long)]
+    pub smooth_move: bool,
+
+    /// No blinking or flashing: steady bonus food, metronome and near-miss line, a static death frame and a still splash
+    #[arg(long)]
+    pub reduced_motion: bool,
+
+    /// End the game after this many seconds without a direction key (0 = never)
+    #[arg(long, default_value_t = 0, value_name = "SECS")]
+    pub idle_timeout: u64,
+
+    /// Return from the game-over screen to the menu after this many idle seconds (0 = wait)
+    #[arg(long, default_value_t = 0, value_name = "SECS")]
+    pub gameover_timeout: u64,
+
+    /// Mark the cell the AI would move into next, for comparing your moves
+    #[arg(long)]
+    pub coach: bool,
+
+    /// Fog of war: only cells within this many cells of a head are shown in full; food beyond is hidden (0 = off)
+    #[arg(long, default_value_t = 0, value_name = "RADIUS")]
+    pub fog: usize,
+
+    /// Silence the terminal bell
+    #[arg(long)]
+    pub no_bell: bool,
+
+    /// Show the animated title splash before the menu
+    #[arg(long)]
+    pub splash: bool,
+
+    /// Flash a beat indicator on every game tick
+    #[arg(long)]
+    pub metronome: bool,
+
+    /// Also ring the terminal bell on every --metronome beat
+    #[arg(long)]
+    pub metronome_bell: bool,
+
+    /// Run this shell command on food, bonus, death and level-up, with the event name as its argument
+    #[arg(long, value_name = "COMMAND")]
+    pub on_event: Option<String>,
+
+    /// Describe events and the snake's surroundings in plain text for a screen reader, on stderr (no value or -) or a named pipe
+    #[arg(long, value_name = "PATH", num_args = 0..=1, default_missing_value = "-")]
+    pub announce: Option<PathBuf>,
+
+    /// Start menu look: classic, retro, minimal (minimal is used automatically on short terminals)
+    #[arg(long, value_enum, default_value_t = MenuTheme::Classic)]
+    pub menu_theme: MenuTheme,
+
+    /// Draw separator lines between the board and the score/status text
+    #[arg(long)]
+    pub hud_borders: bool,
+
+    /// Spaces after each cell glyph: 0 is denser, 2 is wider
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u8).range(0..=2))]
+    pub cell_spacing: u8,
+
+    /// Center the board in the terminal
+    #[arg(long)]
+    pub center_board: bool,
+
     /// Automatically restart on game over
     #[arg(long)]
     pub auto_restart: bool,
 
+    /// Restart every round from the first round's RNG seed, so auto-restart demos repeat exactly
+    #[arg(long)]
+    pub reseed_on_restart: bool,
+
     /// Invert movement controls
     #[arg(long)]
     pub invert_controls: bool,
 
+    /// Pressing the opposite direction performs a U-turn (tail becomes head)
+    #[arg(long)]
+    pub allow_reverse: bool,
+
+    /// A second head sharing the snake's length and score: mirrored (moves opposite) or independent (arrow keys)
+    #[arg(long, value_enum, value_name = "MODE", conflicts_with_all = ["multiplayer", "allow_reverse", "rewind"])]
+    pub two_headed: Option<TwinMode>,
+
     /// Enable wrap-around (pass from edge to opposite)
     #[arg(long)]
     pub disable_borders: bool,
 
+    /// Openings per border side that wrap to the opposite side; the rest of the border stays deadly
+    #[arg(long, default_value_t = 0, value_name = "N", conflicts_with = "disable_borders")]
+    pub edge_gaps: usize,
+
+    /// Lose a segment every N ticks without eating; starve below 2 segments (0 = off)
+    #[arg(long, default_value_t = 0, value_name = "TICKS")]
+    pub hungry: usize,
+
+    /// Stop growing at this many segments; food still scores (0 = no cap)
+    #[arg(long, default_value_t = 0)]
+    pub max_length: usize,
+
+    /// Shade the snake from green to red as it nears its next --hungry segment loss
+    #[arg(long)]
+    pub health_color: bool,
+
+    /// Score lost each time the snake wraps an edge with --disable-borders
+    #[arg(long, default_value_t = 0)]
+    pub wrap_penalty: usize,
+
+    /// The tail now and then drops a coin on the cell it leaves; collect it for points
+    #[arg(long)]
+    pub coins: bool,
+
+    /// Score a bonus point each time the head slips right past the snake's own body
+    #[arg(long)]
+    pub style_points: bool,
+
+    /// Bomb pickups appear; detonating one clears walls up to this many cells from the head (0 = off, singleplayer only)
+    #[arg(long, default_value_t = 0)]
+    pub bomb_radius: usize,
+
+    /// Rewind pickups appear; spending one undoes the snake's last few moves (singleplayer only)
+    #[arg(long)]
+    pub rewind: bool,
+
+    /// Split pickups appear; one divides the snake in two, the tail half steered with the arrow keys, until the halves touch again
+    #[arg(long, conflicts_with_all = ["multiplayer", "two_headed", "allow_reverse", "rewind"])]
+    pub powerups: bool,
+
+    /// Edge wraps allowed per life with --disable-borders; the next one kills (0 = unlimited)
+    #[arg(long, default_value_t = 0)]
+    pub wrap_limit: usize,
+
+    /// Rail assist: turn along a border instead of crashing into it
+    #[arg(long)]
+    pub assist: bool,
+
     /// Number of random obstacles on the map
     #[arg(long, default_value_t = 0)]
     pub obstacles: usize,
 
+    /// Number of warp tiles that teleport the snake somewhere random (each works once)
+    #[arg(long, default_value_t = 0)]
+    pub warp_tiles: usize,
+
+    /// Number of linked portal pairs; entering one end comes out of the other
+    #[arg(long, default_value_t = 0, value_name = "PAIRS")]
+    pub portals: usize,
+
+    /// Keep walls off this many cells straight ahead of each head whenever a snake (re)spawns
+    #[arg(long, default_value_t = 0, value_name = "CELLS")]
+    pub spawn_immunity: usize,
+
+    /// Keys that move player 1 up (comma-separated, e.g. 'w,k')
+    #[arg(long, default_value = "w")]
+    pub keys_up: String,
+
+    /// Keys that move player 1 down
+    #[arg(long, default_value = "s")]
+    pub keys_down: String,
+
+    /// Keys that move player 1 left
+    #[arg(long, default_value = "a")]
+    pub keys_left: String,
+
+    /// Keys that move player 1 right
+    #[arg(long, default_value = "d")]
+    pub keys_right: String,
+
+    /// Keys that pause/resume (e.g. 'p' to free up Space)
+    #[arg(long, default_value = "p,space")]
+    pub pause_keys: String,
+
+    /// Keys that quit the game (Ctrl+C always quits)
+    #[arg(long, default_value = "q,esc")]
+    pub quit_keys: String,
+
+    /// Keys that detonate a collected bomb (see --bomb-radius)
+    #[arg(long, default_value = "b")]
+    pub bomb_keys: String,
+
+    /// Keys that spend a collected rewind (see --rewind)
+    #[arg(long, default_value = "z")]
+    pub rewind_keys: String,
+
     /// Enable multiplayer (player 2 uses arrow keys)
     #[arg(long)]
     pub multiplayer: bool,
 
+    /// Player 1 start: center, top, bottom, left, right, top-left, top-right, bottom-left, bottom-right
+    #[arg(long, value_enum)]
+    pub p1_spawn: Option<SpawnPoint>,
+
+    /// Player 1 start as a spawn point, 'random' (seeded) or 'row,col'
+    #[arg(long, value_name = "POS", conflicts_with = "p1_spawn")]
+    pub start_pos: Option<StartPos>,
+
+    /// Player 1 starting direction: north, south, east, west
+    #[arg(long, value_enum)]
+    pub p1_dir: Option<Direction>,
+
+    /// Player 2 start (multiplayer), same choices as --p1-spawn
+    #[arg(long, value_enum)]
+    pub p2_spawn: Option<SpawnPoint>,
+
+    /// Player 2 frame delay in ms, for handicapping (multiplayer; default: same as --speed)
+    #[arg(long, value_name = "MS")]
+    pub p2_speed: Option<u64>,
+
+    /// Player 2 starting direction (multiplayer)
+    #[arg(long, value_enum)]
+    pub p2_dir: Option<Direction>,
+
+    /// Multiplayer: give each player their own food, mirrored across the board from the same seed
+    #[arg(long)]
+    pub mirror_food: bool,
+
+    /// Multiplayer: eating also takes a point off the opponent, and crossing their body cuts it there
+    #[arg(long)]
+    pub steal: bool,
+
+    /// Multiplayer tie-breaker when scores are equal
+    #[arg(long, value_enum, default_value_t = TieBreaker::Length)]
+    pub tie_breaker: TieBreaker,
+
     /// Enable speed increase as snake grows
     #[arg(long)]
     pub progressive_speed: bool,
 
+    /// How --progressive-speed ramps up: linear, exponential, stepped
+    #[arg(long, value_enum, default_value_t = SpeedCurve::Linear)]
+    pub speed_curve: SpeedCurve,
+
+    /// Fastest frame delay --progressive-speed can reach, in ms
+    #[arg(long, default_value_t = 50, value_name = "MS")]
+    pub speed_floor: u64,
+
+    /// Speed up as the board fills with snake and walls, down to half the delay on a full board
+    #[arg(long, conflicts_with = "progressive_speed")]
+    pub fill_speed: bool,
+
+    /// Time-attack: score as much as possible in N seconds, respawning on death (0 = off)
+    #[arg(long, default_value_t = 0)]
+    pub time_attack: u64,
+
+    /// Pause with a celebration each time the score passes a multiple of N (0 = off)
+    #[arg(long, default_value_t = 0, value_name = "POINTS")]
+    pub milestone_pause: usize,
+
     /// Enable shrinking border mode
     #[arg(long)]
     pub shrinking_border: bool,
 
+    /// Start on a small board in the middle of the map that grows outward as the snake gets longer
+    #[arg(long, conflicts_with = "shrinking_border")]
+    pub growing_board: bool,
+
+    /// Board shape: rect, circle, diamond; cells outside it are walls
+    #[arg(long, value_enum, default_value_t = BoardShape::Rect)]
+    pub shape: BoardShape,
+
     /// Map width (0 = auto-detect from terminal)
     #[arg(long, default_value_t = 0)]
     pub map_width: usize,
@@ -90,43 +610,298 @@ pub struct Settings {
     #[arg(long, default_value_t = 0)]
     pub map_height: usize,
 
+    /// Smallest auto-detected map width
+    #[arg(long, default_value_t = 10, value_name = "COLS")]
+    pub min_auto_width: usize,
+
+    /// Largest auto-detected map width
+    #[arg(long, default_value_t = 40, value_name = "COLS")]
+    pub max_auto_width: usize,
+
+    /// Smallest auto-detected map height
+    #[arg(long, default_value_t = 10, value_name = "ROWS")]
+    pub min_auto_height: usize,
+
+    /// Largest auto-detected map height
+    #[arg(long, default_value_t = 30, value_name = "ROWS")]
+    pub max_auto_height: usize,
+
+    /// Score weights for food, length and survival time (e.g. 'food=1,length=2,time=0')
+    #[arg(long, default_value_t = ScoreWeights::default())]
+    pub score_formula: ScoreWeights,
+
+    /// Score the snake's growth in length instead of food eaten (same as --score-formula length=1)
+    #[arg(long, conflicts_with = "score_formula")]
+    pub score_by_length: bool,
+
+    /// Autosave the running game every N seconds for crash recovery (0 = off)
+    #[arg(long, default_value_t = 0)]
+    pub autosave_interval: u64,
+
     /// Path to TOML config file
     #[arg(long)]
     pub config: Option<PathBuf>,
 
+    /// Run the setup wizard again and save its answers to the default config file
+    #[arg(long)]
+    pub setup: bool,
+
+    /// Start a fresh run on the walls and borders saved in a snapshot file
+    #[arg(long, value_name = "FILE")]
+    pub from_snapshot: Option<PathBuf>,
+
+    /// Play on a map file: '#' walls, '.' floor, 'S' player 1 start, 'F' food
+    #[arg(long, value_name = "FILE", conflicts_with = "from_snapshot")]
+    pub map_file: Option<PathBuf>,
+
+    /// Save each round's starting board (walls, start, food) as a map file at game over
+    #[arg(long, value_name = "FILE")]
+    pub save_map: Option<PathBuf>,
+
+    /// Draw with the snake: score by covering the cells marked in this pattern file
+    #[arg(long, value_name = "FILE")]
+    pub art_target: Option<PathBuf>,
+
+    /// Directory for high scores, stats and saves (also via SNAKE_TERM_DATA)
+    #[arg(long, value_name = "DIR")]
+    pub scores_dir: Option<PathBuf>,
+
+    /// Drive player 1 from a file of moves (N, S, E, W or '.' per frame) instead of the keyboard
+    #[arg(long, value_name = "FILE")]
+    pub input_script: Option<PathBuf>,
+
+    /// What to do when the --input-script runs out: loop, quit
+    #[arg(long, value_enum, default_value_t = ScriptEnd::Quit, requires = "input_script")]
+    pub script_end: ScriptEnd,
+
+    /// Log every key press of the session, menus included, with timings and the seed and settings (for bug reports)
+    #[arg(long, value_name = "FILE")]
+    pub session_log: Option<PathBuf>,
+
+    /// Feed a --session-log back, with its seed and settings, in place of the keyboard, then hand control back
+    #[arg(long, value_name = "FILE", conflicts_with = "session_log")]
+    pub session_play: Option<PathBuf>,
+
     /// Record game to a replay file
     #[arg(long)]
     pub record: Option<PathBuf>,
 
+    /// When recording begins: start, first-move (drop idle frames before the first turn)
+    #[arg(long, value_enum, default_value_t = RecordFrom::Start, requires = "record")]
+    pub record_from: RecordFrom,
+
     /// Play back a recorded replay file
     #[arg(long)]
     pub replay: Option<PathBuf>,
+
+    /// Start the --replay over when it ends, until a key is pressed
+    #[arg(long, requires = "replay")]
+    pub loop_replay: bool,
+
+    /// Add a caption to the --replay file at FRAME instead of playing it
+    #[arg(long, num_args = 2, value_names = ["FRAME", "TEXT"], requires = "replay")]
+    pub annotate: Vec<String>,
+
+    /// Render the --replay file to an animated GIF instead of playing it
+    #[arg(long, value_name = "OUT", requires = "replay")]
+    pub export_gif: Option<PathBuf>,
+
+    /// Write the first --frames rendered frames (ANSI) to this file and exit, for golden-file tests
+    #[arg(long, value_name = "FILE", hide = true)]
+    pub render_to: Option<PathBuf>,
+
+    /// Frames to write with --render-to
+    #[arg(long, default_value_t = 10, requires = "render_to", hide = true)]
+    pub frames: usize,
+
+    /// Run a headless game twice from this seed and check the state hashes match frame for frame, then exit
+    #[arg(long, value_name = "SEED")]
+    pub audit: Option<u64>,
+
+    /// Frames each --audit run plays (fewer if the snake dies)
+    #[arg(long, default_value_t = 1000, requires = "audit")]
+    pub audit_frames: usize,
+
+    /// Delete the saved high score and exit
+    #[arg(long)]
+    pub reset_scores: bool,
+
+    /// Delete the lifetime stats file and exit
+    #[arg(long)]
+    pub reset_stats: bool,
+
+    /// Skip the confirmation prompt for --reset-scores / --reset-stats
+    #[arg(long, short = 'y')]
+    pub yes: bool,
+
+    /// Poll input less often to save CPU, at the cost of input latency
+    #[arg(long)]
+    pub low_power: bool,
+
+    /// Show SLOW when frames keep running this many percent over their target (0 = off)
+    #[arg(long, default_value_t = 25, value_name = "PERCENT")]
+    pub slow_margin: u32,
+
+    /// When running slow, drop colors and smooth movement to lighten each frame
+    #[arg(long)]
+    pub auto_degrade: bool,
+
+    #[arg(skip)]
+    pub bindings: KeyBindings,
+
+    /// Board layout loaded from --from-snapshot or --map-file
+    #[arg(skip)]
+    pub layout: Option<Snapshot>,
+
+    /// Map loaded from --map-file, for its starting food
+    #[arg(skip)]
+    pub map: Option<MapFile>,
+
+    /// Target picture loaded from --art-target
+    #[arg(skip)]
+    pub art: Option<ArtTarget>,
+
+    /// Input poll granularity during play, derived from --low-power
+    #[arg(skip = POLL_INTERVAL)]
+    pub poll_interval: Duration,
+
+    /// No --config was given and none exists at the default location yet
+    #[arg(skip)]
+    pub first_run: bool,
 }
 
 #[derive(Deserialize, Default)]
 pub struct FileConfig {
     pub speed: Option<u64>,
+    pub cells_per_second: Option<f64>,
     pub body: Option<String>,
+    pub tail_char: Option<String>,
     pub head_w: Option<String>,
     pub head_n: Option<String>,
     pub head_e: Option<String>,
     pub head_s: Option<String>,
     pub head: Option<String>,
     pub food: Option<String>,
+    pub food_count: Option<usize>,
+    pub food_min_spread: Option<usize>,
+    pub food_values: Option<bool>,
+    pub start_food: Option<usize>,
+    pub auto_food: Option<usize>,
+    pub food_walls: Option<bool>,
+    pub deterministic_bonus: Option<bool>,
+    pub skin: Option<String>,
+    pub skin_unlocks: Option<String>,
+    pub wall_theme: Option<String>,
+    pub wall_char: Option<String>,
+    pub border_char: Option<String>,
     pub seed: Option<u64>,
+    pub rng: Option<String>,
     pub hide_score: Option<bool>,
+    pub no_color: Option<bool>,
+    pub no_alt_screen: Option<bool>,
+    pub no_bracketed_paste: Option<bool>,
+    pub smooth_move: Option<bool>,
+    pub reduced_motion: Option<bool>,
+    pub idle_timeout: Option<u64>,
+    pub gameover_timeout: Option<u64>,
+    pub coach: Option<bool>,
+    pub fog: Option<usize>,
+    pub no_bell: Option<bool>,
+    pub splash: Option<bool>,
+    pub metronome: Option<bool>,
+    pub metronome_bell: Option<bool>,
+    pub on_event: Option<String>,
+    pub announce: Option<PathBuf>,
+    pub menu_theme: Option<String>,
+    pub hud_borders: Option<bool>,
+    pub cell_spacing: Option<u8>,
+    pub center_board: Option<bool>,
     pub auto_restart: Option<bool>,
+    pub reseed_on_restart: Option<bool>,
     pub invert_controls: Option<bool>,
+    pub keys_up: Option<String>,
+    pub keys_down: Option<String>,
+    pub keys_left: Option<String>,
+    pub keys_right: Option<String>,
+    pub pause_keys: Option<String>,
+    pub quit_keys: Option<String>,
+    pub bomb_keys: Option<String>,
+    pub rewind_keys: Option<String>,
+    pub allow_reverse: Option<bool>,
+    pub two_headed: Option<String>,
     pub disable_borders: Option<bool>,
+    pub edge_gaps: Option<usize>,
+    pub hungry: Option<usize>,
+    pub health_color: Option<bool>,
+    pub max_length: Option<usize>,
+    pub wrap_penalty: Option<usize>,
+    pub wrap_limit: Option<usize>,
+    pub coins: Option<bool>,
+    pub style_points: Option<bool>,
+    pub bomb_radius: Option<usize>,
+    pub rewind: Option<bool>,
+    pub powerups: Option<bool>,
+    pub assist: Option<bool>,
     pub obstacles: Option<usize>,
+    pub spawn_immunity: Option<usize>,
+    pub warp_tiles: Option<usize>,
+    pub portals: Option<usize>,
     pub multiplayer: Option<bool>,
+    pub mirror_food: Option<bool>,
+    pub steal: Option<bool>,
+    pub p1_spawn: Option<String>,
+    pub start_pos: Option<String>,
+    pub p1_dir: Option<String>,
+    pub p2_spawn: Option<String>,
+    pub p2_dir: Option<String>,
+    pub p2_speed: Option<u64>,
+    pub tie_breaker: Option<String>,
     pub progressive_speed: Option<bool>,
+    pub speed_curve: Option<String>,
+    pub speed_floor: Option<u64>,
+    pub fill_speed: Option<bool>,
     pub shrinking_border: Option<bool>,
+    pub growing_board: Option<bool>,
+    pub time_attack: Option<u64>,
+    pub milestone_pause: Option<usize>,
+    pub shape: Option<String>,
     pub map_width: Option<usize>,
     pub map_height: Option<usize>,
+    pub min_auto_width: Option<usize>,
+    pub max_auto_width: Option<usize>,
+    pub min_auto_height: Option<usize>,
+    pub max_auto_height: Option<usize>,
+    pub score_formula: Option<String>,
+    pub score_by_length: Option<bool>,
+    pub autosave_interval: Option<u64>,
+    pub low_power: Option<bool>,
+    pub slow_margin: Option<u32>,
+    pub auto_degrade: Option<bool>,
+    pub show_run_code: Option<bool>,
+    pub scores_dir: Option<PathBuf>,
 }
 
 impl Settings {
+    /// Without --config, fall back to the default config file; no file
+    /// there means this is the first launch. Only the binary calls this, so
+    /// tests never pick up the user's own config.
+    pub fn with_default_config(mut self) -> Self {
+        if self.config.is_none() {
+            match default_config_path() {
+                Some(path) if path.exists() => self.config = Some(path),
+                Some(_) => self.first_run = true,
+                None => {}
+            }
+        }
+        self
+    }
+
+    /// Whether input comes from `--input-script` or `--session-play`
+    /// rather than the keyboard.
+    pub fn scripted_input(&self) -> bool {
+        self.input_script.is_some() || self.session_play.is_some()
+    }
+
     pub fn resolve(mut self) -> Self {
         // Load TOML config file if specified (CLI args override file values)
         if let Some(ref path) = self.config {
@@ -137,6 +912,25 @@ impl Settings {
             }
         }
 
+        if let Some(cps) = self.cells_per_second.filter(|&cps| cps > 0.0) {
+            if self.speed != 200 {
+                eprintln!("Warning: --cells-per-second {cps} overrides --speed {}", self.speed);
+            }
+            self.speed = frame_delay_ms(cps);
+        }
+
+        // A bad code changes nothing here; `validate` reports it
+        if let Some(Ok(code)) = self.run_code.as_deref().map(RunCode::decode) {
+            code.apply(&mut self);
+        }
+
+        if std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+            self.no_color = true;
+        }
+        if !supports_alt_screen(std::env::var("TERM").ok().as_deref()) {
+            self.no_alt_screen = true;
+        }
+
         if let Some(ref h) = self.head {
             let chars: Vec<char> = h.chars().collect();
             if chars.len() >= 4 {
@@ -147,20 +941,30 @@ impl Settings {
             }
         }
 
+        if self.skin != Skin::Classic {
+            self.apply_skin(self.skin);
+        }
+
+        if self.score_by_length {
+            self.score_formula = ScoreWeights::BY_LENGTH;
+        }
+
+        self.poll_interval = if self.low_power { LOW_POWER_POLL_INTERVAL } else { POLL_INTERVAL };
+
+        // Invalid bindings keep the defaults here; `validate` reports them
+        if let Ok(bindings) = KeyBindings::from_settings(&self) {
+            self.bindings = bindings;
+        }
+
         // Auto-detect terminal size if map dimensions are 0
         if self.map_width == 0 || self.map_height == 0 {
             if let Ok((cols, rows)) = crossterm::terminal::size() {
+                let (width, height) = self.auto_map_size(cols as usize, rows as usize);
                 if self.map_width == 0 {
-                    // Each cell is "char space" = 2 columns, leave margin
-                    self.map_width = ((cols as usize).saturating_sub(4) / 2)
-                        .min(40)
-                        .max(10);
+                    self.map_width = width;
                 }
                 if self.map_height == 0 {
-                    // Leave room for score line + game over text
-                    self.map_height = ((rows as usize).saturating_sub(6))
-                        .min(30)
-                        .max(10);
+                    self.map_height = height;
                 }
             } else {
                 if self.map_width == 0 {
@@ -175,9 +979,21 @@ impl Settings {
         self
     }
 
+    /// Board size that fits a `cols`x`rows` terminal, within the
+    /// `--min/max-auto-*` clamps. A min above its max is left for
+    /// `validate` to report rather than panicking here.
+    pub fn auto_map_size(&self, cols: usize, rows: usize) -> (usize, usize) {
+        // Each cell is a glyph plus --cell-spacing columns, leave margin
+        let width = (cols.saturating_sub(4) / self.cell_width()).max(self.min_auto_width).min(self.max_auto_width);
+        // Leave room for score line + game over text
+        let height = rows.saturating_sub(6).max(self.min_auto_height).min(self.max_auto_height);
+        (width, height)
+    }
+
     fn apply_file_config(&mut self, fc: &FileConfig) {
         // File config only applies if CLI didn't override (check defaults)
         if let Some(v) = fc.speed { if self.speed == 200 { self.speed = v; } }
+        self.cells_per_second = self.cells_per_second.or(fc.cells_per_second);
         if let Some(ref v) = fc.body { if self.body == '@' { self.body = v.chars().next().unwrap_or('@'); } }
         if let Some(ref v) = fc.head_w { if self.head_w == '<' { self.head_w = v.chars().next().unwrap_or('<'); } }
         if let Some(ref v) = fc.head_n { if self.head_n == '^' { self.head_n = v.chars().next().unwrap_or('^'); } }
@@ -185,17 +1001,391 @@ impl Settings {
         if let Some(ref v) = fc.head_s { if self.head_s == 'v' { self.head_s = v.chars().next().unwrap_or('v'); } }
         if let Some(ref v) = fc.head { if self.head.is_none() { self.head = Some(v.clone()); } }
         if let Some(ref v) = fc.food { if self.food == '*' { self.food = v.chars().next().unwrap_or('*'); } }
+        if let Some(v) = fc.food_count { if self.food_count == 1 { self.food_count = v; } }
+        if let Some(v) = fc.food_min_spread { if self.food_min_spread == 0 { self.food_min_spread = v; } }
+        if let Some(v) = fc.food_values { if !self.food_values { self.food_values = v; } }
+        if let Some(v) = fc.start_food { if self.start_food == 0 { self.start_food = v; } }
+        if let Some(v) = fc.auto_food { if self.auto_food == 0 { self.auto_food = v; } }
+        if let Some(v) = fc.food_walls { if !self.food_walls { self.food_walls = v; } }
+        if let Some(v) = fc.deterministic_bonus { if !self.deterministic_bonus { self.deterministic_bonus = v; } }
+        if let Some(ref v) = fc.skin {
+            if self.skin == Skin::Classic {
+                if let Ok(s) = Skin::from_str(v, true) { self.skin = s; }
+            }
+        }
+        if let Some(ref v) = fc.skin_unlocks {
+            if self.skin_unlocks == SkinUnlocks::default() {
+                if let Ok(u) = v.parse() { self.skin_unlocks = u; }
+            }
+        }
+        if let Some(ref v) = fc.wall_theme {
+            if self.wall_theme == WallTheme::Classic {
+                if let Ok(t) = WallTheme::from_str(v, true) { self.wall_theme = t; }
+            }
+        }
+        if let Some(ref v) = fc.wall_char { if self.wall_char.is_none() { self.wall_char = v.chars().next(); } }
+        if let Some(ref v) = fc.border_char { if self.border_char == BORDER_CHAR { self.border_char = v.chars().next().unwrap_or(BORDER_CHAR); } }
+        if let Some(ref v) = fc.tail_char { if self.tail_char.is_none() { self.tail_char = v.chars().next(); } }
         if let Some(v) = fc.seed { if self.seed == 0 { self.seed = v; } }
+        if let Some(ref v) = fc.rng {
+            if self.rng == RngAlgorithm::Xorshift {
+                if let Ok(a) = RngAlgorithm::from_str(v, true) { self.rng = a; }
+            }
+        }
         if let Some(v) = fc.hide_score { if !self.hide_score { self.hide_score = v; } }
+        if let Some(v) = fc.no_color { if !self.no_color { self.no_color = v; } }
+        if let Some(v) = fc.no_alt_screen { if !self.no_alt_screen { self.no_alt_screen = v; } }
+        if let Some(v) = fc.no_bracketed_paste { if !self.no_bracketed_paste { self.no_bracketed_paste = v; } }
+        if let Some(v) = fc.smooth_move { if !self.smooth_move { self.smooth_move = v; } }
+        if let Some(v) = fc.reduced_motion { if !self.reduced_motion { self.reduced_motion = v; } }
+        if let Some(v) = fc.idle_timeout { if self.idle_timeout == 0 { self.idle_timeout = v; } }
+        if let Some(v) = fc.gameover_timeout { if self.gameover_timeout == 0 { self.gameover_timeout = v; } }
+        if let Some(v) = fc.coach { if !self.coach { self.coach = v; } }
+        if let Some(v) = fc.fog { if self.fog == 0 { self.fog = v; } }
+        if let Some(v) = fc.no_bell { if !self.no_bell { self.no_bell = v; } }
+        if let Some(v) = fc.splash { if !self.splash { self.splash = v; } }
+        if let Some(v) = fc.metronome { if !self.metronome { self.metronome = v; } }
+        if let Some(v) = fc.metronome_bell { if !self.metronome_bell { self.metronome_bell = v; } }
+        if self.on_event.is_none() { self.on_event = fc.on_event.clone(); }
+        if self.announce.is_none() { self.announce = fc.announce.clone(); }
+        if let Some(ref v) = fc.menu_theme {
+            if self.menu_theme == MenuTheme::Classic {
+                if let Ok(t) = MenuTheme::from_str(v, true) { self.menu_theme = t; }
+            }
+        }
+        if let Some(v) = fc.hud_borders { if !self.hud_borders { self.hud_borders = v; } }
+        if let Some(v) = fc.cell_spacing { if self.cell_spacing == 1 { self.cell_spacing = v; } }
+        if let Some(v) = fc.center_board { if !self.center_board { self.center_board = v; } }
         if let Some(v) = fc.auto_restart { if !self.auto_restart { self.auto_restart = v; } }
+        if let Some(v) = fc.reseed_on_restart { if !self.reseed_on_restart { self.reseed_on_restart = v; } }
         if let Some(v) = fc.invert_controls { if !self.invert_controls { self.invert_controls = v; } }
+        if let Some(ref v) = fc.keys_up { if self.keys_up == "w" { self.keys_up = v.clone(); } }
+        if let Some(ref v) = fc.keys_down { if self.keys_down == "s" { self.keys_down = v.clone(); } }
+        if let Some(ref v) = fc.keys_left { if self.keys_left == "a" { self.keys_left = v.clone(); } }
+        if let Some(ref v) = fc.keys_right { if self.keys_right == "d" { self.keys_right = v.clone(); } }
+        if let Some(ref v) = fc.pause_keys { if self.pause_keys == "p,space" { self.pause_keys = v.clone(); } }
+        if let Some(ref v) = fc.quit_keys { if self.quit_keys == "q,esc" { self.quit_keys = v.clone(); } }
+        if let Some(ref v) = fc.bomb_keys { if self.bomb_keys == "b" { self.bomb_keys = v.clone(); } }
+        if let Some(ref v) = fc.rewind_keys { if self.rewind_keys == "z" { self.rewind_keys = v.clone(); } }
+        if let Some(v) = fc.allow_reverse { if !self.allow_reverse { self.allow_reverse = v; } }
+        self.two_headed = self.two_headed.or(fc.two_headed.as_ref().and_then(|v| TwinMode::from_str(v, true).ok()));
         if let Some(v) = fc.disable_borders { if !self.disable_borders { self.disable_borders = v; } }
+        if let Some(v) = fc.edge_gaps { if self.edge_gaps == 0 { self.edge_gaps = v; } }
+        if let Some(v) = fc.hungry { if self.hungry == 0 { self.hungry = v; } }
+        if let Some(v) = fc.health_color { if !self.health_color { self.health_color = v; } }
+        if let Some(v) = fc.max_length { if self.max_length == 0 { self.max_length = v; } }
+        if let Some(v) = fc.wrap_penalty { if self.wrap_penalty == 0 { self.wrap_penalty = v; } }
+        if let Some(v) = fc.wrap_limit { if self.wrap_limit == 0 { self.wrap_limit = v; } }
+        if let Some(v) = fc.coins { if !self.coins { self.coins = v; } }
+        if let Some(v) = fc.style_points { if !self.style_points { self.style_points = v; } }
+        if let Some(v) = fc.bomb_radius { if self.bomb_radius == 0 { self.bomb_radius = v; } }
+        if let Some(v) = fc.rewind { if !self.rewind { self.rewind = v; } }
+        if let Some(v) = fc.powerups { if !self.powerups { self.powerups = v; } }
+        if let Some(v) = fc.assist { if !self.assist { self.assist = v; } }
         if let Some(v) = fc.obstacles { if self.obstacles == 0 { self.obstacles = v; } }
+        if let Some(v) = fc.spawn_immunity { if self.spawn_immunity == 0 { self.spawn_immunity = v; } }
+        if let Some(v) = fc.warp_tiles { if self.warp_tiles == 0 { self.warp_tiles = v; } }
+        if let Some(v) = fc.portals { if self.portals == 0 { self.portals = v; } }
         if let Some(v) = fc.multiplayer { if !self.multiplayer { self.multiplayer = v; } }
+        if let Some(v) = fc.mirror_food { if !self.mirror_food { self.mirror_food = v; } }
+        if let Some(v) = fc.steal { if !self.steal { self.steal = v; } }
+        let spawn = |v: &Option<String>| v.as_ref().and_then(|v| SpawnPoint::from_str(v, true).ok());
+        let dir = |v: &Option<String>| v.as_ref().and_then(|v| Direction::from_str(v, true).ok());
+        self.p1_spawn = self.p1_spawn.or(spawn(&fc.p1_spawn));
+        if self.start_pos.is_none() && self.p1_spawn.is_none() {
+            self.start_pos = fc.start_pos.as_ref().and_then(|v| v.parse().ok());
+        }
+        self.p1_dir = self.p1_dir.or(dir(&fc.p1_dir));
+        self.p2_spawn = self.p2_spawn.or(spawn(&fc.p2_spawn));
+        self.p2_dir = self.p2_dir.or(dir(&fc.p2_dir));
+        self.p2_speed = self.p2_speed.or(fc.p2_speed);
+        if let Some(ref v) = fc.tie_breaker {
+            if self.tie_breaker == TieBreaker::Length {
+                if let Ok(t) = TieBreaker::from_str(v, true) { self.tie_breaker = t; }
+            }
+        }
         if let Some(v) = fc.progressive_speed { if !self.progressive_speed { self.progressive_speed = v; } }
+        if let Some(ref v) = fc.shape {
+            if self.shape == BoardShape::Rect {
+                if let Ok(s) = BoardShape::from_str(v, true) { self.shape = s; }
+            }
+        }
+        if let Some(ref v) = fc.speed_curve {
+            if self.speed_curve == SpeedCurve::Linear {
+                if let Ok(c) = SpeedCurve::from_str(v, true) { self.speed_curve = c; }
+            }
+        }
+        if let Some(v) = fc.speed_floor { if self.speed_floor == 50 { self.speed_floor = v; } }
+        if let Some(v) = fc.fill_speed { if !self.fill_speed { self.fill_speed = v; } }
         if let Some(v) = fc.shrinking_border { if !self.shrinking_border { self.shrinking_border = v; } }
+        if let Some(v) = fc.growing_board { if !self.growing_board { self.growing_board = v; } }
+        if let Some(v) = fc.time_attack { if self.time_attack == 0 { self.time_attack = v; } }
+        if let Some(v) = fc.milestone_pause { if self.milestone_pause == 0 { self.milestone_pause = v; } }
         if let Some(v) = fc.map_width { if self.map_width == 0 { self.map_width = v; } }
         if let Some(v) = fc.map_height { if self.map_height == 0 { self.map_height = v; } }
+        if let Some(v) = fc.min_auto_width { if self.min_auto_width == 10 { self.min_auto_width = v; } }
+        if let Some(v) = fc.max_auto_width { if self.max_auto_width == 40 { self.max_auto_width = v; } }
+        if let Some(v) = fc.min_auto_height { if self.min_auto_height == 10 { self.min_auto_height = v; } }
+        if let Some(v) = fc.max_auto_height { if self.max_auto_height == 30 { self.max_auto_height = v; } }
+        if let Some(v) = fc.low_power { if !self.low_power { self.low_power = v; } }
+        if let Some(v) = fc.slow_margin { if self.slow_margin == 25 { self.slow_margin = v; } }
+        if let Some(v) = fc.auto_degrade { if !self.auto_degrade { self.auto_degrade = v; } }
+        if let Some(v) = fc.show_run_code { if !self.show_run_code { self.show_run_code = v; } }
+        if self.scores_dir.is_none() { self.scores_dir = fc.scores_dir.clone(); }
+        if let Some(v) = fc.autosave_interval { if self.autosave_interval == 0 { self.autosave_interval = v; } }
+        if let Some(v) = fc.score_by_length { if !self.score_by_length { self.score_by_length = v; } }
+        if let Some(ref v) = fc.score_formula {
+            if self.score_formula == ScoreWeights::default() {
+                if let Ok(w) = v.parse() { self.score_formula = w; }
+            }
+        }
+    }
+
+    /// Check option combinations that can't be expressed through clap alone.
+    pub fn validate(&self) -> Result<(), String> {
+        KeyBindings::from_settings(self)?;
+        if let Some(ref code) = self.run_code {
+            RunCode::decode(code)?;
+        }
+        if self.food_count == 0 {
+            return Err("--food-count must be at least 1".to_string());
+        }
+        if let Some(ch) = self.wall_char {
+            if !is_single_width(ch) {
+                return Err(format!("wall character {ch:?} must be a single-width glyph"));
+            }
+        }
+        if !is_single_width(self.border_char) {
+            return Err(format!("border character {:?} must be a single-width glyph", self.border_char));
+        }
+        if let Some(ch) = self.tail_char {
+            if !is_single_width(ch) {
+                return Err(format!("tail character {ch:?} must be a single-width glyph"));
+            }
+        }
+        if self.cells_per_second.is_some_and(|cps| !(cps > 0.0 && cps <= 1000.0)) {
+            return Err("--cells-per-second must be above 0 and at most 1000".to_string());
+        }
+        if self.cell_spacing > 2 {
+            return Err("cell spacing must be 0, 1 or 2".to_string());
+        }
+        if self.min_auto_width > self.max_auto_width || self.min_auto_height > self.max_auto_height {
+            return Err("--min-auto-width/height can't exceed --max-auto-width/height".to_string());
+        }
+        if self.p2_speed == Some(0) {
+            return Err("--p2-speed must be at least 1 ms".to_string());
+        }
+        if self.speed_floor == 0 {
+            return Err("--speed-floor must be at least 1 ms".to_string());
+        }
+        if self.mirror_food && !self.multiplayer {
+            return Err("--mirror-food needs --multiplayer".to_string());
+        }
+        if self.steal && !self.multiplayer {
+            return Err("--steal needs --multiplayer".to_string());
+        }
+        if self.two_headed.is_some() && (self.multiplayer || self.allow_reverse || self.rewind) {
+            return Err("--two-headed can't be combined with --multiplayer, --allow-reverse or --rewind".to_string());
+        }
+        if self.powerups && (self.multiplayer || self.two_headed.is_some() || self.allow_reverse || self.rewind) {
+            return Err("--powerups can't be combined with --multiplayer, --two-headed, --allow-reverse or --rewind".to_string());
+        }
+        // Only player 1 collects and spends pickups
+        if self.multiplayer && (self.bomb_radius > 0 || self.rewind) {
+            return Err("--bomb-radius and --rewind can't be combined with --multiplayer".to_string());
+        }
+        // Playback re-simulates the round, which it can't do for a wall-clock countdown
+        if self.record.is_some() && self.time_attack > 0 {
+            return Err("--record can't be combined with --time-attack".to_string());
+        }
+        if self.two_headed.is_some() && self.start_pos == Some(StartPos::Random) {
+            return Err("--start-pos random can't be combined with --two-headed".to_string());
+        }
+        if self.growing_board && self.start_pos == Some(StartPos::Random) {
+            return Err("--start-pos random can't be combined with --growing-board".to_string());
+        }
+        let players = if self.multiplayer { 2 } else { 1 };
+        let mut taken = Vec::new();
+        for player in 0..players {
+            let (head, dir) = self.spawn(player, self.map_width, self.map_height);
+            let cells = spawn_cells(head, dir, INITIAL_SNAKE_LENGTH, self.map_width, self.map_height)
+                .ok_or_else(|| format!("player {} doesn't fit on the board at its spawn point", player + 1))?;
+            if cells.iter().any(|c| taken.contains(c)) {
+                return Err("player spawn points overlap".to_string());
+            }
+            if !cells.iter().all(|&c| self.shape.contains(c, self.map_width, self.map_height)) {
+                return Err(format!("player {} spawns outside the --shape", player + 1));
+            }
+            if !self.fits_layout(&cells) {
+                return Err(format!("player {} spawns on a wall or outside the border of the loaded board", player + 1));
+            }
+            if self.growing_board {
+                let (min, max) = growing_board_start(self.map_width, self.map_height);
+                if cells.iter().any(|&(r, c)| r < min.0 || r >= max.0 || c < min.1 || c >= max.1) {
+                    return Err(format!("player {} spawns outside the --growing-board starting area", player + 1));
+                }
+            }
+            taken.extend(cells);
+        }
+        if self.two_headed.is_some() {
+            let (head, dir) = self.spawn(0, self.map_width, self.map_height);
+            let (twin_head, twin_dir) = twin_spawn(head, dir, self.map_width, self.map_height);
+            let cells = spawn_cells(twin_head, twin_dir, INITIAL_SNAKE_LENGTH, self.map_width, self.map_height)
+                .ok_or("the second head doesn't fit on the board")?;
+            if cells.iter().any(|c| taken.contains(c)) || !cells.iter().all(|&c| self.shape.contains(c, self.map_width, self.map_height)) {
+                return Err("the second head has no room opposite player 1's spawn".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Head cell and heading for `player` (0 or 1) at the start of a round.
+    /// Without spawn flags, singleplayer starts mid-board and multiplayer
+    /// puts P1 on the upper third and P2 on the lower third facing apart.
+    pub fn spawn(&self, player: usize, width: usize, height: usize) -> ((usize, usize), Direction) {
+        let len = INITIAL_SNAKE_LENGTH;
+        let (point, dir) = if player == 0 { (self.p1_spawn, self.p1_dir) } else { (self.p2_spawn, self.p2_dir) };
+        let (default_head, default_dir) = match (player, self.multiplayer) {
+            (0, false) => ((height / 2, width / 2 - len / 2 + len - 1), Direction::East),
+            (0, true) => ((height / 3, width / 2 - len / 2 + len - 1), Direction::East),
+            _ => ((2 * height / 3, (width / 2 + len / 2 + 1).saturating_sub(len)), Direction::West),
+        };
+        let start = if player == 0 { self.start_pos } else { None };
+        let head = match (start, point) {
+            (Some(StartPos::Point(p)), _) | (None, Some(p)) => p.head(width, height),
+            (Some(StartPos::Cell(r, c)), _) => (r, c),
+            _ => default_head,
+        };
+        let facing = match start {
+            Some(StartPos::Point(p)) => Some(p.facing()),
+            _ => point.map(SpawnPoint::facing),
+        };
+        (head, dir.or(facing).unwrap_or(default_dir))
+    }
+
+    /// Whether `cells` all sit inside the border of the `--map-file` or
+    /// `--from-snapshot` board, off its walls. Always true without one.
+    fn fits_layout(&self, cells: &[(usize, usize)]) -> bool {
+        self.layout.as_ref().is_none_or(|layout| {
+            let (min, max) = (layout.border_min, layout.border_max);
+            cells.iter().all(|&(r, c)| {
+                r >= min.0 && r < max.0 && c >= min.1 && c < max.1 && !layout.walls.contains(&(r, c))
+            })
+        })
+    }
+
+    /// `spawn`, except that `--start-pos random` draws player 1's head from
+    /// `rng` among the cells where the whole starting body fits, clear of
+    /// player 2's spawn and of a loaded board's walls and border.
+    pub fn round_spawn(&self, player: usize, width: usize, height: usize, rng: &mut GameRng) -> ((usize, usize), Direction) {
+        let (head, dir) = self.spawn(player, width, height);
+        if player != 0 || self.start_pos != Some(StartPos::Random) {
+            return (head, dir);
+        }
+        let len = INITIAL_SNAKE_LENGTH;
+        let taken = if self.multiplayer {
+            let (head2, dir2) = self.spawn(1, width, height);
+            spawn_cells(head2, dir2, len, width, height).unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        let open: Vec<(usize, usize)> = (0..height)
+            .flat_map(|r| (0..width).map(move |c| (r, c)))
+            .filter(|&h| {
+                spawn_cells(h, dir, len, width, height).is_some_and(|cells| {
+                    !cells.iter().any(|c| taken.contains(c))
+                        && cells.iter().all(|&c| self.shape.contains(c, width, height))
+                        && self.fits_layout(&cells)
+                })
+            })
+            .collect();
+        if open.is_empty() {
+            return (head, dir);
+        }
+        (open[rng.gen_range(0..open.len())], dir)
+    }
+
+    /// Terminal columns one board cell takes up: the glyph plus spacing.
+    pub fn cell_width(&self) -> usize {
+        1 + self.cell_spacing as usize
+    }
+
+    /// Wait between menu redraws; menus are idle most of the time, so they
+    /// poll ten times less often than the game loop.
+    pub fn menu_poll_interval(&self) -> Duration {
+        self.poll_interval * 10
+    }
+
+    /// Whether to play the title splash before the menu.
+    pub fn splash_enabled(&self) -> bool {
+        self.splash
+    }
+
+    /// Pellets to seed a new round with.
+    pub fn initial_food(&self) -> usize {
+        if self.start_food == 0 {
+            self.food_count
+        } else {
+            self.start_food
+        }
+    }
+
+    /// Play on `map`: its size, layout and, unless the command line picked
+    /// another start, its start cell and direction.
+    pub fn use_map(&mut self, map: MapFile) {
+        self.map_width = map.width;
+        self.map_height = map.height;
+        if let (Some((r, c)), None, None) = (map.start, self.start_pos, self.p1_spawn) {
+            self.start_pos = Some(StartPos::Cell(r, c));
+            self.p1_dir = self.p1_dir.or(Some(map.start_dir));
+        }
+        self.layout = Some(map.layout());
+        self.map = Some(map);
+    }
+
+    /// Play on `snap`'s board (`--from-snapshot`): its size and layout.
+    pub fn use_snapshot(&mut self, snap: Snapshot) {
+        self.map_width = snap.width;
+        self.map_height = snap.height;
+        self.layout = Some(snap);
+    }
+
+    /// Switch to `skin`'s body and head glyphs.
+    pub fn apply_skin(&mut self, skin: Skin) {
+        let (body, [w, n, e, s]) = skin.glyphs();
+        self.skin = skin;
+        self.body = body;
+        (self.head_w, self.head_n, self.head_e, self.head_s) = (w, n, e, s);
+    }
+
+    /// Glyph and color used for walls.
+    pub fn wall_glyph(&self) -> (char, Color) {
+        let (ch, color) = self.wall_theme.glyph();
+        (self.wall_char.unwrap_or(ch), color)
+    }
+
+    /// Seed to restart the RNG from for the next round, or None to keep
+    /// the running stream. `--reseed-on-restart` goes back to `first_seed`;
+    /// recorded rounds otherwise draw a fresh seed from `rng`, since a
+    /// replay is rebuilt from its seed alone.
+    pub fn next_round_seed(&self, first_seed: u64, rng: &mut GameRng) -> Option<u64> {
+        if self.reseed_on_restart {
+            Some(first_seed)
+        } else if self.record.is_some() {
+            Some(rng.next_u64().max(1))
+        } else {
+            None
+        }
+    }
+
+    /// Seed for a game: `--seed`, or a random one when that is 0. Nonzero,
+    /// since replays treat 0 as "unset".
+    pub fn round_seed(&self) -> u64 {
+        if self.seed != 0 {
+            self.seed
+        } else {
+            rand::random::<u64>().max(1)
+        }
     }
 
     pub fn head_char(&self, dir: Direction) -> char {
@@ -207,17 +1397,137 @@ impl Settings {
         }
     }
 
-    pub fn effective_speed(&self, snake_length: usize) -> u64 {
+    /// Frame delay in ms for a snake of `snake_length` on a board that is
+    /// `fill` (0.0-1.0) taken up by snakes and walls. `resolve` has already
+    /// turned any `--cells-per-second` into `speed`.
+    pub fn effective_speed(&self, snake_length: usize, fill: f64) -> u64 {
+        self.progressive(self.speed, snake_length, fill)
+    }
+
+    fn progressive(&self, speed: u64, snake_length: usize, fill: f64) -> u64 {
         if self.progressive_speed {
-            let reduction = ((snake_length.saturating_sub(INITIAL_SNAKE_LENGTH)) as u64) * 5;
-            self.speed.saturating_sub(reduction).max(50)
+            let growth = snake_length.saturating_sub(INITIAL_SNAKE_LENGTH);
+            self.speed_curve.delay(speed, growth, self.speed_floor)
+        } else if self.fill_speed {
+            ((speed as f64 * (1.0 - fill.clamp(0.0, 1.0) / 2.0)).round() as u64).max(1)
         } else {
-            self.speed
+            speed
         }
     }
+
+    /// Step period in ms for each player. Without `--p2-speed` player 2
+    /// keeps player 1's pace, so both move every tick.
+    pub fn player_speeds(&self, p1_length: usize, p2_length: Option<usize>, fill: f64) -> [u64; 2] {
+        let p1 = self.effective_speed(p1_length, fill);
+        let p2 = match (self.p2_speed, p2_length) {
+            (Some(speed), Some(len)) => self.progressive(speed, len, fill),
+            _ => p1,
+        };
+        [p1, p2]
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Play area a `--growing-board` round starts on: half the map each way
+/// (at least `GROWING_BOARD_MIN`), centered, as (border_min, border_max).
+pub fn growing_board_start(width: usize, height: usize) -> ((usize, usize), (usize, usize)) {
+    let w = (width / 2).max(GROWING_BOARD_MIN).min(width);
+    let h = (height / 2).max(GROWING_BOARD_MIN).min(height);
+    let min = ((height - h) / 2, (width - w) / 2);
+    (min, (min.0 + h, min.1 + w))
+}
+
+/// Share of the play area between `border_min` and `border_max` taken up
+/// by `occupied` cells, for `--fill-speed`.
+pub fn board_fill(occupied: usize, border_min: (usize, usize), border_max: (usize, usize)) -> f64 {
+    let area = border_max.0.saturating_sub(border_min.0) * border_max.1.saturating_sub(border_min.1);
+    if area == 0 {
+        return 1.0;
+    }
+    (occupied as f64 / area as f64).min(1.0)
+}
+
+/// Frame delay for `--cells-per-second`: the snake moves one cell per
+/// frame, so the delay is 1000 / `cps` ms, rounded to the nearest
+/// millisecond and never below 1.
+pub fn frame_delay_ms(cps: f64) -> u64 {
+    ((1000.0 / cps).round() as u64).max(1)
+}
+
+/// Config file read when `--config` isn't given, and written by the setup
+/// wizard: `snake-term/config.toml` in the platform's config dir.
+pub fn default_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join("snake-term").join("config.toml"))
+}
+
+/// Answers collected by the setup wizard. A map size of 0 keeps
+/// auto-detection from the terminal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetupAnswers {
+    pub speed: u64,
+    pub map_width: usize,
+    pub map_height: usize,
+    pub body: char,
+    pub food: char,
+}
+
+impl SetupAnswers {
+    /// Start from the values saved in config file text `existing`, or the
+    /// defaults, never from this session's CLI flags, so saving only writes
+    /// what the wizard showed.
+    pub fn from_config(existing: &str) -> Self {
+        let mut base = Settings::parse_from(["snake-term"]);
+        if let Ok(fc) = toml::from_str::<FileConfig>(existing) {
+            base.apply_file_config(&fc);
+        }
+        SetupAnswers { speed: base.speed, map_width: base.map_width, map_height: base.map_height, body: base.body, food: base.food }
+    }
+
+    /// Config file text with these answers set on top of `existing`, so
+    /// re-running the wizard keeps any other keys already in the file.
+    /// Unreadable `existing` text is replaced.
+    pub fn to_config(&self, existing: &str) -> String {
+        let mut table = existing.parse::<toml::Table>().unwrap_or_default();
+        table.insert("speed".into(), toml::Value::Integer(self.speed as i64));
+        table.insert("map_width".into(), toml::Value::Integer(self.map_width as i64));
+        table.insert("map_height".into(), toml::Value::Integer(self.map_height as i64));
+        table.insert("body".into(), toml::Value::String(self.body.to_string()));
+        table.insert("food".into(), toml::Value::String(self.food.to_string()));
+        table.to_string()
+    }
+
+    /// Carry the answers over to `settings` for the session already running.
+    pub fn apply(&self, settings: &mut Settings) {
+        settings.speed = self.speed;
+        if self.map_width > 0 {
+            settings.map_width = self.map_width;
+        }
+        if self.map_height > 0 {
+            settings.map_height = self.map_height;
+        }
+        settings.body = self.body;
+        settings.food = self.food;
+    }
+}
+
+/// Whether a glyph occupies exactly one terminal column. Rejects control and
+/// combining characters and the common wide (CJK, fullwidth, emoji) ranges.
+pub fn is_single_width(ch: char) -> bool {
+    let c = ch as u32;
+    let control = ch.is_control();
+    let combining = (0x0300..=0x036F).contains(&c) || (0x200B..=0x200F).contains(&c) || c == 0xFE0F;
+    let wide = (0x1100..=0x115F).contains(&c)
+        || (0x2E80..=0xA4CF).contains(&c)
+        || (0xAC00..=0xD7A3).contains(&c)
+        || (0xF900..=0xFAFF).contains(&c)
+        || (0xFE30..=0xFE4F).contains(&c)
+        || (0xFF00..=0xFF60).contains(&c)
+        || (0xFFE0..=0xFFE6).contains(&c)
+        || (0x1F300..=0x1FAFF).contains(&c)
+        || (0x20000..=0x3FFFD).contains(&c);
+    !(control || combining || wide)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
 pub enum Direction {
     West,
     North,
@@ -235,6 +1545,25 @@ impl Direction {
         }
     }
 
+    pub fn as_char(self) -> char {
+        match self {
+            Direction::North => 'N',
+            Direction::South => 'S',
+            Direction::East => 'E',
+            Direction::West => 'W',
+        }
+    }
+
+    pub fn from_char(ch: char) -> Option<Self> {
+        match ch {
+            'N' => Some(Direction::North),
+            'S' => Some(Direction::South),
+            'E' => Some(Direction::East),
+            'W' => Some(Direction::West),
+            _ => None,
+        }
+    }
+
     pub fn delta(self) -> (i32, i32) {
         match self {
             Direction::West => (0, -1),
@@ -244,3 +1573,164 @@ impl Direction {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cells_per_second_frame_delay() {
+        assert_eq!(frame_delay_ms(5.0), 200);
+        assert_eq!(frame_delay_ms(8.0), 125);
+        assert_eq!(frame_delay_ms(3.0), 333);
+        assert_eq!(frame_delay_ms(0.5), 2000);
+        assert_eq!(frame_delay_ms(1000.0), 1);
+
+        let settings = Settings::parse_from(["test", "--cells-per-second", "10"]).resolve();
+        assert_eq!(settings.speed, 100);
+        // Cells per second wins over an explicit --speed
+        let both = Settings::parse_from(["test", "--speed", "50", "--cells-per-second", "4"]).resolve();
+        assert_eq!(both.speed, 250);
+        assert_eq!(both.effective_speed(INITIAL_SNAKE_LENGTH, 0.0), 250);
+
+        let zero = Settings::parse_from(["test", "--cells-per-second", "0", "--map-width", "20", "--map-height", "20"]).resolve();
+        assert!(zero.validate().is_err());
+    }
+
+    #[test]
+    fn test_speed_curves_never_slow_down() {
+        for curve in [SpeedCurve::Linear, SpeedCurve::Exponential, SpeedCurve::Stepped] {
+            for floor in [50, 120] {
+                let mut last = curve.delay(200, 0, floor);
+                assert_eq!(last, 200, "{curve:?} starts at --speed");
+                for growth in 1..200 {
+                    let delay = curve.delay(200, growth, floor);
+                    assert!(delay <= last, "{curve:?} slowed down at {growth}");
+                    assert!(delay >= floor, "{curve:?} went under the floor at {growth}");
+                    last = delay;
+                }
+                assert_eq!(last, floor, "{curve:?} bottoms out at the floor");
+            }
+        }
+        assert_eq!(SpeedCurve::Linear.delay(200, 10, 50), 150);
+        assert_eq!(SpeedCurve::Stepped.delay(200, 9, 50), 175);
+        assert_eq!(SpeedCurve::Exponential.delay(200, 1, 50), 194);
+
+        let settings = Settings::parse_from(["test", "--progressive-speed", "--speed-curve", "stepped", "--speed-floor", "80"]).resolve();
+        assert_eq!(settings.effective_speed(INITIAL_SNAKE_LENGTH + 10, 0.0), 150);
+        assert_eq!(settings.effective_speed(500, 0.0), 80);
+    }
+
+    #[test]
+    fn test_fill_speed_quickens_as_board_fills() {
+        let settings = Settings::parse_from(["test", "--speed", "200", "--fill-speed"]).resolve();
+        let mut last = u64::MAX;
+        for occupied in [0, 50, 100, 200, 300, 400] {
+            let fill = board_fill(occupied, (0, 0), (20, 20));
+            let speed = settings.effective_speed(INITIAL_SNAKE_LENGTH, fill);
+            assert!(speed < last || occupied == 0, "{occupied} cells: {speed}ms after {last}ms");
+            last = speed;
+        }
+        assert_eq!(settings.effective_speed(INITIAL_SNAKE_LENGTH, 0.0), 200);
+        assert_eq!(settings.effective_speed(INITIAL_SNAKE_LENGTH, 1.0), 100);
+        // Length alone doesn't change the pace
+        assert_eq!(settings.effective_speed(40, 0.0), 200);
+
+        // Off by default
+        let plain = Settings::parse_from(["test", "--speed", "200"]).resolve();
+        assert_eq!(plain.effective_speed(INITIAL_SNAKE_LENGTH, 0.9), 200);
+        // A shrunk border counts against the smaller area
+        assert_eq!(board_fill(25, (5, 5), (15, 15)), 0.25);
+    }
+
+    #[test]
+    fn test_splash_is_opt_in() {
+        let parse = |args: &[&str]| Settings::parse_from(std::iter::once("test").chain(args.iter().copied())).resolve();
+        assert!(!parse(&[]).splash_enabled());
+        assert!(parse(&["--splash"]).splash_enabled());
+        assert!(Settings::try_parse_from(["test", "--no-splash"]).is_err());
+
+        let path = std::env::temp_dir().join(format!("snake-term-splash-{}.toml", std::process::id()));
+        std::fs::write(&path, "splash = true\n").unwrap();
+        let from_file = parse(&["--config", path.to_str().unwrap()]);
+        std::fs::write(&path, "splash = false\n").unwrap();
+        let off_in_file = parse(&["--config", path.to_str().unwrap()]);
+        let overridden = parse(&["--config", path.to_str().unwrap(), "--splash"]);
+        let _ = std::fs::remove_file(&path);
+        assert!(from_file.splash_enabled());
+        assert!(!off_in_file.splash_enabled());
+        assert!(overridden.splash_enabled());
+    }
+
+    #[test]
+    fn test_skin_unlocks_from_flag_and_file() {
+        let parse = |args: &[&str]| Settings::parse_from(["test"].iter().chain(args)).resolve();
+        assert_eq!(parse(&[]).skin_unlocks, SkinUnlocks::default());
+        assert_eq!(parse(&["--skin-unlocks", "rope=5"]).skin_unlocks.0, [(Skin::Rope, 5)]);
+        assert!(Settings::try_parse_from(["test", "--skin-unlocks", "rope=many"]).is_err());
+
+        let path = std::env::temp_dir().join(format!("snake-term-unlocks-{}.toml", std::process::id()));
+        std::fs::write(&path, "skin_unlocks = \"beads=1,phantom=2\"\n").unwrap();
+        let config = path.to_str().unwrap();
+        let from_file = parse(&["--config", config]);
+        let overridden = parse(&["--config", config, "--skin-unlocks", "arrows=3"]);
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(from_file.skin_unlocks.0, [(Skin::Beads, 1), (Skin::Phantom, 2)]);
+        assert_eq!(overridden.skin_unlocks.0, [(Skin::Arrows, 3)]);
+    }
+
+    #[test]
+    fn test_setup_answers_round_trip_through_config() {
+        let answers = SetupAnswers { speed: 120, map_width: 30, map_height: 0, body: 'o', food: '♦' };
+        // Keys the wizard doesn't ask about survive a re-run
+        let text = answers.to_config("splash = false\nspeed = 300\n");
+
+        let path = std::env::temp_dir().join(format!("snake-term-setup-{}.toml", std::process::id()));
+        std::fs::write(&path, &text).unwrap();
+        let settings = Settings::parse_from(["test", "--config", path.to_str().unwrap()]).resolve();
+        let _ = std::fs::remove_file(&path);
+
+        assert!(!settings.first_run);
+        assert_eq!((settings.speed, settings.map_width), (120, 30));
+        assert_eq!((settings.body, settings.food), ('o', '♦'));
+        assert!(!settings.splash_enabled());
+        // 0 leaves the height to auto-detection
+        assert!(settings.map_height > 0);
+        assert!(settings.validate().is_ok());
+
+        // The wizard starts from the file, not from this session's flags
+        assert_eq!(SetupAnswers::from_config(&text), answers);
+        let fresh = SetupAnswers::from_config("");
+        assert_eq!((fresh.speed, fresh.map_width, fresh.body), (200, 0, '@'));
+        assert!(!Settings::parse_from(["test", "--speed", "50"]).resolve().first_run);
+    }
+
+    #[test]
+    fn test_auto_size_clamps() {
+        let defaults = Settings::parse_from(["test"]);
+        // 200x60 terminal: today's clamps cap the board at 40x30
+        assert_eq!(defaults.auto_map_size(200, 60), (40, 30));
+        assert_eq!(defaults.auto_map_size(20, 10), (10, 10));
+
+        let wide = Settings::parse_from(["test", "--max-auto-width", "120", "--max-auto-height", "50"]);
+        assert_eq!(wide.auto_map_size(200, 60), (98, 50));
+        let spaced = Settings::parse_from(["test", "--max-auto-width", "120", "--cell-spacing", "2"]);
+        assert_eq!(spaced.auto_map_size(200, 60).0, 65);
+
+        let roomy = Settings::parse_from(["test", "--min-auto-width", "30", "--min-auto-height", "20"]);
+        assert_eq!(roomy.auto_map_size(20, 10), (30, 20));
+
+        let inverted = Settings::parse_from(["test", "--min-auto-width", "50", "--map-width", "20", "--map-height", "20"]);
+        assert!(inverted.validate().is_err());
+    }
+
+    #[test]
+    fn test_pickups_are_singleplayer() {
+        let board = ["test", "--map-width", "20", "--map-height", "20"];
+        let parse = |args: &[&str]| Settings::parse_from(board.iter().chain(args)).resolve();
+        assert!(parse(&["--bomb-radius", "2"]).validate().is_ok());
+        assert!(parse(&["--rewind"]).validate().is_ok());
+        assert!(parse(&["--bomb-radius", "2", "--multiplayer"]).validate().is_err());
+        assert!(parse(&["--rewind", "--multiplayer"]).validate().is_err());
+    }
+}
@@ -1,8 +1,16 @@
+use std::collections::{HashMap, HashSet};
+
 use crossterm::style::{Color, StyledContent, Stylize};
-use rand::Rng;
+use crossterm::{cursor, terminal, Command};
 
+use crate::ai;
 use crate::config::*;
-use crate::snake::Snake;
+use crate::rng::{GameRng, RngAlgorithm};
+use crate::snake::{is_edge_gap, spawn_cells, Snake};
+
+/// Where a snake's food is parked while it steps, so a pellet the other
+/// player already ate this tick isn't eaten twice.
+const OFF_BOARD: (usize, usize) = (usize::MAX, usize::MAX);
 
 #[derive(Clone)]
 pub struct Cell {
@@ -14,8 +22,14 @@ impl Cell {
     fn empty() -> Self {
         Cell { ch: MAP_CHAR, color: Color::DarkGrey }
     }
-    fn wall() -> Self {
-        Cell { ch: WALL_CHAR, color: Color::White }
+    fn wall(settings: &Settings) -> Self {
+        let (ch, color) = settings.wall_glyph();
+        Cell { ch, color }
+    }
+    /// Outside the current border: as deadly as a wall, but drawn apart
+    /// from the walls placed on the board.
+    fn border(settings: &Settings) -> Self {
+        Cell { ch: settings.border_char, color: Color::DarkGrey }
     }
 }
 
@@ -24,15 +38,60 @@ pub struct BonusFood {
     pub lifetime: usize, // frames remaining
 }
 
+/// A `--coins` pickup dropped by a snake's tail.
+pub struct Coin {
+    pub pos: (usize, usize),
+    pub value: usize,
+    pub lifetime: usize, // frames remaining
+}
+
+/// What `GameMap::resolve_food` did this tick.
+pub struct FoodTick {
+    pub eaten: usize,
+    pub board_full: bool, // no open cell left for the next pellet
+}
+
+/// Per-player pellet streams for `--mirror-food`. Both start from the same
+/// seed; player 1's pellets land in the top half and player 2's are the same
+/// draws reflected through the board center.
+pub struct MirrorFood {
+    rngs: [GameRng; 2],
+    waiting: [bool; 2], // ate with a full half; retried each tick until there's room
+}
+
+impl MirrorFood {
+    pub fn new(algorithm: RngAlgorithm, seed: u64) -> Self {
+        MirrorFood { rngs: [GameRng::seeded(algorithm, seed), GameRng::seeded(algorithm, seed)], waiting: [false; 2] }
+    }
+}
+
 pub struct GameMap {
     pub width: usize,
     pub height: usize,
     grid: Vec<Vec<Cell>>,
     pub walls: Vec<(usize, usize)>,
+    pub outside: Vec<(usize, usize)>, // --shape cells, kept among the walls
+    pub extra_food: Vec<(usize, usize)>, // pellets beyond the shared primary `Snake::food`
+    pub warp_tiles: Vec<(usize, usize)>,
+    pub portals: Vec<[(usize, usize); 2]>, // linked --portals ends, by pair index
     pub bonus_food: Option<BonusFood>,
+    pub bomb: Option<(usize, usize)>, // uncollected --bomb-radius pickup
+    pub rewind: Option<(usize, usize)>, // uncollected --rewind pickup
+    pub split: Option<(usize, usize)>,  // uncollected --powerups split pickup
+    pub coins: Vec<Coin>,
+    pub key: Option<(usize, usize)>,  // map-file key that opens the gate
+    pub gate: Option<(usize, usize)>, // locked gate, deadly without the key
+    pub reserved: HashSet<(usize, usize)>, // map-file cells nothing spawns on
     pub border_min: (usize, usize),
     pub border_max: (usize, usize),
     pub shrink_timer: usize,
+    pub status: String, // extra HUD text shown after the score
+    pub milestone: Option<usize>, // --milestone-pause score shown in place of PAUSED
+    pub running_slow: bool,
+    pub mirror_food: Option<MirrorFood>,
+    pub bonus_spots: Vec<(usize, usize)>, // --deterministic-bonus cycle, empty when off
+    bonus_next: usize, // index into `bonus_spots` of the next spot to try
+    pub spare_values: HashMap<(usize, usize), usize>, // spares worth more than 1
 }
 
 impl GameMap {
@@ -42,45 +101,552 @@ impl GameMap {
             height,
             grid: vec![vec![Cell::empty(); width]; height],
             walls: Vec::new(),
+            outside: Vec::new(),
+            extra_food: Vec::new(),
+            warp_tiles: Vec::new(),
+            portals: Vec::new(),
             bonus_food: None,
+            bomb: None,
+            rewind: None,
+            split: None,
+            coins: Vec::new(),
+            key: None,
+            gate: None,
+            reserved: HashSet::new(),
             border_min: (0, 0),
             border_max: (height, width),
             shrink_timer: 0,
+            status: String::new(),
+            milestone: None,
+            running_slow: false,
+            mirror_food: None,
+            bonus_spots: Vec::new(),
+            bonus_next: 0,
+            spare_values: HashMap::new(),
         }
     }
 
-    pub fn place_walls<R: Rng>(&mut self, count: usize, snake: &Snake, rng: &mut R) {
-        self.walls.clear();
+    /// Wall off every cell outside `shape`. The cells are remembered so that
+    /// re-placing obstacles or a bomb blast never opens them up.
+    pub fn apply_shape(&mut self, shape: BoardShape) {
+        let (width, height) = (self.width, self.height);
+        self.walls.retain(|&pos| shape.contains(pos, width, height));
+        self.outside = (0..height)
+            .flat_map(|r| (0..width).map(move |c| (r, c)))
+            .filter(|&pos| !shape.contains(pos, width, height))
+            .collect();
+        self.walls.extend(&self.outside);
+    }
+
+    /// Scatter `count` walls over open cells, keeping them off the `--spawn-immunity`
+    /// cells ahead of each head. Gives up early when the board is too full.
+    pub fn place_walls(&mut self, count: usize, snakes: &[&Snake], settings: &Settings, rng: &mut GameRng) {
+        let outside = &self.outside;
+        self.walls.retain(|pos| outside.contains(pos));
+        let ahead = self.spawn_paths(snakes, settings);
+        let mut open: Vec<(usize, usize)> = (0..self.height)
+            .flat_map(|r| (0..self.width).map(move |c| (r, c)))
+            .filter(|&pos| self.is_open(pos, snakes) && !ahead.contains(&pos))
+            .collect();
         for _ in 0..count {
-            loop {
-                let r = rng.gen_range(0..self.height);
-                let c = rng.gen_range(0..self.width);
-                if !snake.parts.contains(&(r, c))
-                    && (r, c) != snake.food
-                    && !self.walls.contains(&(r, c))
-                {
-                    self.walls.push((r, c));
-                    break;
-                }
+            if open.is_empty() {
+                return;
             }
+            self.walls.push(open.swap_remove(rng.gen_range(0..open.len())));
         }
     }
 
-    pub fn place_food<R: Rng>(&self, snake: &mut Snake, rng: &mut R) {
+    /// `--spawn-immunity`: clear walls left from earlier (obstacles,
+    /// `--food-walls`) off the cells ahead of snakes that just respawned.
+    /// The `--shape` outline stays.
+    pub fn clear_spawn_paths(&mut self, snakes: &[&Snake], settings: &Settings) {
+        let ahead = self.spawn_paths(snakes, settings);
+        let outside = &self.outside;
+        self.walls.retain(|pos| outside.contains(pos) || !ahead.contains(pos));
+    }
+
+    /// The `--spawn-immunity` cells ahead of every head.
+    fn spawn_paths(&self, snakes: &[&Snake], settings: &Settings) -> Vec<(usize, usize)> {
+        snakes.iter().flat_map(|s| s.path_ahead(settings.spawn_immunity, settings, self.border_min, self.border_max)).collect()
+    }
+
+    /// Place one pellet shared by every snake, avoiding all of their bodies.
+    /// With `--food-min-spread` it also lands at least that many steps from
+    /// the pellet it replaces (and never on it) whenever some open cell
+    /// allows. Returns false, leaving the food untouched, when no open cell
+    /// is left.
+    pub fn place_shared_food(&self, snakes: &mut [&mut Snake], settings: &Settings, rng: &mut GameRng) -> bool {
         let (bmin_r, bmin_c) = self.border_min;
         let (bmax_r, bmax_c) = self.border_max;
+        let open = |pos: (usize, usize)| {
+            snakes.iter().all(|s| !s.occupies(pos))
+                && !self.walls.contains(&pos)
+                && !self.extra_food.contains(&pos)
+                && !self.reserved.contains(&pos)
+                && self.gate != Some(pos)
+                && self.key != Some(pos)
+        };
+        if !(bmin_r..bmax_r).any(|r| (bmin_c..bmax_c).any(|c| open((r, c)))) {
+            return false;
+        }
+        // Far enough if possible, else anywhere but the old cell, else anywhere
+        let previous = snakes.first().map(|s| s.food).filter(|_| settings.food_min_spread > 0);
+        let steps = |(r, c): (usize, usize)| previous.map_or(usize::MAX, |(pr, pc)| r.abs_diff(pr) + c.abs_diff(pc));
+        let min_steps = [settings.food_min_spread, 1]
+            .into_iter()
+            .find(|&min| (bmin_r..bmax_r).any(|r| (bmin_c..bmax_c).any(|c| open((r, c)) && steps((r, c)) >= min)))
+            .unwrap_or(0);
         loop {
-            let r = rng.gen_range(bmin_r..bmax_r);
-            let c = rng.gen_range(bmin_c..bmax_c);
-            if !snake.parts.contains(&(r, c)) && !self.walls.contains(&(r, c)) {
-                snake.food = (r, c);
-                snake.food_eaten = false;
+            let pos = (rng.gen_range(bmin_r..bmax_r), rng.gen_range(bmin_c..bmax_c));
+            if open(pos) && steps(pos) >= min_steps {
+                let value = draw_food_value(settings, rng);
+                for snake in snakes.iter_mut() {
+                    snake.food = pos;
+                    snake.food_value = value;
+                    snake.food_eaten = false;
+                }
+                return true;
+            }
+        }
+    }
+
+    /// Scatter `count` fresh warp tiles over open cells, replacing any left
+    /// from the last round. Gives up early when the board is too full.
+    pub fn place_warp_tiles(&mut self, count: usize, snakes: &[&Snake], rng: &mut GameRng) {
+        self.warp_tiles.clear();
+        for _ in 0..count {
+            let open: Vec<(usize, usize)> = (0..self.height)
+                .flat_map(|r| (0..self.width).map(move |c| (r, c)))
+                .filter(|&pos| self.is_open(pos, snakes))
+                .collect();
+            if open.is_empty() {
+                return;
+            }
+            self.warp_tiles.push(open[rng.gen_range(0..open.len())]);
+        }
+    }
+
+    /// Place `pairs` fresh portal pairs on open cells, replacing last
+    /// round's. Stops early when the board is too full for another pair.
+    pub fn place_portals(&mut self, pairs: usize, snakes: &[&Snake], rng: &mut GameRng) {
+        self.portals.clear();
+        for _ in 0..pairs {
+            let mut open: Vec<(usize, usize)> = (0..self.height)
+                .flat_map(|r| (0..self.width).map(move |c| (r, c)))
+                .filter(|&pos| self.is_open(pos, snakes))
+                .collect();
+            if open.len() < 2 {
+                return;
+            }
+            let entrance = open.swap_remove(rng.gen_range(0..open.len()));
+            let exit = open[rng.gen_range(0..open.len())];
+            self.portals.push([entrance, exit]);
+        }
+    }
+
+    /// Move each snake whose clock ticked (`moves`, in player order) and
+    /// settle the tiles its head lands on; the rest hold still. Returns the
+    /// turns actually applied this tick, which is what recordings store.
+    pub fn step_snakes(&mut self, snakes: &mut [&mut Snake], moves: [bool; 2], settings: &Settings) -> [Option<Direction>; 2] {
+        let mut applied = [None, None];
+        let walls = self.walls.clone();
+        let (border_min, border_max) = (self.border_min, self.border_max);
+        let mut eaten = None;
+        for (i, snake) in snakes.iter_mut().enumerate() {
+            if moves[i] {
+                applied[i] = snake.apply_queued_input();
+                // A --two-headed snake is always alone, so its second head takes player 2's slot
+                if let Some(turn) = snake.apply_twin_input() {
+                    applied[1] = Some(turn);
+                }
+                // Both heads reaching the shared pellet in one tick: player 1 gets it
+                let taken = eaten.filter(|&pos| pos == snake.food);
+                if taken.is_some() {
+                    snake.food = OFF_BOARD;
+                }
+                snake.update_movement(settings, &walls, border_min, border_max);
+                if let Some(pos) = taken {
+                    snake.food = pos;
+                } else if snake.food_eaten {
+                    eaten = Some(snake.food);
+                }
+                self.take_portal(snake);
+                self.cross_gate(snake);
+                self.collect_key(snake);
+            } else {
+                snake.rest();
+            }
+        }
+        applied
+    }
+
+    /// Send a snake whose head just moved onto a portal out of the other
+    /// end. Returns whether it went through.
+    pub fn take_portal(&self, snake: &mut Snake) -> bool {
+        if snake.is_dead {
+            return false;
+        }
+        let exit_from = |pos: (usize, usize)| {
+            self.portals.iter().find_map(|&[a, b]| match pos {
+                head if head == a => Some(b),
+                head if head == b => Some(a),
+                _ => None,
+            })
+        };
+        let mut went = false;
+        if let Some(exit) = exit_from(snake.head) {
+            snake.teleport_head(exit);
+            went = true;
+        }
+        // A second head goes through on its own
+        if let Some(exit) = snake.twin_head().and_then(exit_from).filter(|_| !snake.is_dead) {
+            snake.teleport_twin(exit);
+            went = true;
+        }
+        went
+    }
+
+    /// A head on the key picks it up.
+    pub fn collect_key(&mut self, snake: &mut Snake) -> bool {
+        if !self.key.is_some_and(|key| snake.heads().any(|head| head == key)) {
+            return false;
+        }
+        self.key = None;
+        snake.has_key = true;
+        true
+    }
+
+    /// A head on the gate spends the key to pass, or dies without one.
+    /// The gate stays locked behind it.
+    pub fn cross_gate(&self, snake: &mut Snake) {
+        let at_gate = snake.heads().filter(|&head| self.gate == Some(head)).count();
+        for _ in 0..at_gate {
+            if snake.has_key {
+                snake.has_key = false;
+            } else {
+                snake.is_dead = true;
+            }
+        }
+    }
+
+    /// Inside the border, not reserved, and free of walls, snakes, food and
+    /// warp tiles.
+    fn is_open(&self, pos: (usize, usize), snakes: &[&Snake]) -> bool {
+        pos.0 >= self.border_min.0 && pos.0 < self.border_max.0
+            && pos.1 >= self.border_min.1 && pos.1 < self.border_max.1
+            && !self.reserved.contains(&pos)
+            && !self.walls.contains(&pos)
+            && !self.extra_food.contains(&pos)
+            && !self.warp_tiles.contains(&pos)
+            && !self.portals.iter().any(|pair| pair.contains(&pos))
+            && self.bonus_food.as_ref().is_none_or(|b| b.pos != pos)
+            && self.bomb != Some(pos)
+            && self.rewind != Some(pos)
+            && self.split != Some(pos)
+            && !self.coins.iter().any(|coin| coin.pos == pos)
+            && self.key != Some(pos)
+            && self.gate != Some(pos)
+            && snakes.iter().all(|s| s.food != pos && !s.occupies(pos))
+    }
+
+    /// How much of the play area snakes and walls take up (0.0-1.0).
+    pub fn fill_fraction(&self, snakes: &[&Snake]) -> f64 {
+        let (bmin, bmax) = (self.border_min, self.border_max);
+        let inside = |&&(r, c): &&(usize, usize)| r >= bmin.0 && r < bmax.0 && c >= bmin.1 && c < bmax.1;
+        let walls = self.walls.iter().filter(inside).count();
+        let bodies: usize = snakes.iter().map(|s| s.parts.len()).sum();
+        board_fill(walls + bodies, bmin, bmax)
+    }
+
+    /// Teleport every snake whose head landed on a warp tile. The tile is
+    /// used up and the body snaps into a straight line behind a random
+    /// open head cell, still facing the same way; the snake stays put if
+    /// there is no room anywhere. Returns how many snakes warped.
+    pub fn warp_snakes(&mut self, snakes: &mut [&mut Snake], rng: &mut GameRng) -> usize {
+        let mut warped = 0;
+        for i in 0..snakes.len() {
+            let Some(tile) = self.warp_tiles.iter().position(|&t| t == snakes[i].head) else {
+                continue;
+            };
+            self.warp_tiles.remove(tile);
+            let (dir, len) = (snakes[i].direction, snakes[i].parts.len());
+            let others: Vec<&Snake> = snakes.iter().enumerate().filter(|&(j, _)| j != i).map(|(_, s)| &**s).collect();
+            // The snake's own old body is about to move, so only its food counts
+            let mut fits = self.open_bodies(dir, len, &others, snakes[i].food);
+            if fits.is_empty() {
+                continue;
+            }
+            let cells = fits.swap_remove(rng.gen_range(0..fits.len()));
+            snakes[i].set_body(cells);
+            warped += 1;
+        }
+        warped
+    }
+
+    /// Every straight `len`-cell body facing `dir` (tail first) that lies
+    /// on open cells, clear of `food` too.
+    fn open_bodies(&self, dir: Direction, len: usize, others: &[&Snake], food: (usize, usize)) -> Vec<Vec<(usize, usize)>> {
+        (0..self.height)
+            .flat_map(|r| (0..self.width).map(move |c| (r, c)))
+            .filter_map(|head| spawn_cells(head, dir, len, self.width, self.height))
+            .filter(|cells| cells.iter().all(|&pos| pos != food && self.is_open(pos, others)))
+            .collect()
+    }
+
+    /// Where a player comes back after a `--time-attack` death: their usual
+    /// `head`, or with walls, pickups or `others` in the way, the head of a
+    /// random open stretch facing `dir`. Stays at `head` if there is no
+    /// room anywhere.
+    pub fn open_spawn(&self, head: (usize, usize), dir: Direction, others: &[&Snake], food: (usize, usize), rng: &mut GameRng) -> (usize, usize) {
+        let len = INITIAL_SNAKE_LENGTH;
+        let blocked = spawn_cells(head, dir, len, self.width, self.height)
+            .is_none_or(|cells| cells.iter().any(|&pos| pos == food || !self.is_open(pos, others)));
+        if !blocked {
+            return head;
+        }
+        let fits = self.open_bodies(dir, len, others, food);
+        if fits.is_empty() {
+            return head;
+        }
+        let cells = &fits[rng.gen_range(0..fits.len())];
+        cells[cells.len() - 1]
+    }
+
+    /// Seed a new round: the primary pellet (one per player with
+    /// `--mirror-food`) plus `--start-food` spares, and the
+    /// `--deterministic-bonus` spots.
+    pub fn seed_food(&mut self, snakes: &mut [&mut Snake], settings: &Settings, rng: &mut GameRng) {
+        self.extra_food.clear();
+        self.bonus_spots.clear();
+        self.bonus_next = 0;
+        if settings.deterministic_bonus {
+            // Their own stream, so nothing drawn later in the round can
+            // shift them
+            let mut spots = GameRng::seeded(settings.rng, rng.next_u64());
+            self.bonus_spots = (0..BONUS_SEQUENCE_LEN)
+                .map(|_| (spots.gen_range(0..self.height), spots.gen_range(0..self.width)))
+                .collect();
+        }
+        self.spare_values.clear();
+        if settings.mirror_food {
+            // Fresh identical streams each round so neither player starts ahead
+            self.mirror_food = Some(MirrorFood::new(settings.rng, rng.next_u64()));
+        }
+        self.seed_primary_food(snakes, settings, rng);
+        let snakes: Vec<&Snake> = snakes.iter().map(|s| &**s).collect();
+        let start = if settings.start_food == 0 { self.food_target(settings) } else { settings.initial_food() };
+        self.top_up_food(&snakes, start, settings, rng);
+    }
+
+    /// Place the primary pellet(s) for a new round: one shared pellet, or
+    /// one per player with `--mirror-food`.
+    pub fn seed_primary_food(&mut self, snakes: &mut [&mut Snake], settings: &Settings, rng: &mut GameRng) -> bool {
+        if self.mirror_food.is_none() {
+            return self.place_shared_food(snakes, settings, rng);
+        }
+        let mut placed = true;
+        for player in 0..snakes.len().min(2) {
+            placed &= self.place_mirrored_food(snakes, player, settings);
+        }
+        placed
+    }
+
+    /// Mirror image of `pos` through the center of the board.
+    pub fn mirror(&self, (r, c): (usize, usize)) -> (usize, usize) {
+        (self.height - 1 - r, self.width - 1 - c)
+    }
+
+    /// Draw `player`'s next pellet from its `--mirror-food` stream. Returns
+    /// false when its half of the board has no open cell left.
+    fn place_mirrored_food(&mut self, snakes: &mut [&mut Snake], player: usize, settings: &Settings) -> bool {
+        let Some(mut mirror) = self.mirror_food.take() else {
+            return false;
+        };
+        let (bmin_r, bmin_c) = self.border_min;
+        let bmax_c = self.border_max.1;
+        let half = bmin_r..(self.height / 2).max(bmin_r);
+        let cell = |draw: (usize, usize)| if player == 0 { draw } else { self.mirror(draw) };
+        let view: Vec<&Snake> = snakes.iter().map(|s| &**s).collect();
+        let open = |pos: (usize, usize)| self.is_open(pos, &view);
+        let placed = half.clone().any(|r| (bmin_c..bmax_c).any(|c| open(cell((r, c)))));
+        if placed {
+            let rng = &mut mirror.rngs[player];
+            let pos = loop {
+                let pos = cell((rng.gen_range(half.clone()), rng.gen_range(bmin_c..bmax_c)));
+                if open(pos) {
+                    break pos;
+                }
+            };
+            // From the player's own stream, so both see the same values
+            snakes[player].food_value = draw_food_value(settings, rng);
+            snakes[player].food = pos;
+            snakes[player].food_eaten = false;
+        }
+        mirror.waiting[player] = !placed;
+        self.mirror_food = Some(mirror);
+        placed
+    }
+
+    /// Replace an eaten primary pellet, promoting a spare one when any are
+    /// left so a surplus from `--start-food` drains instead of respawning.
+    pub fn replace_shared_food(&mut self, snakes: &mut [&mut Snake], settings: &Settings, rng: &mut GameRng) -> bool {
+        match self.extra_food.pop() {
+            Some(pos) => {
+                let value = self.spare_values.remove(&pos).unwrap_or(1);
+                for snake in snakes.iter_mut() {
+                    snake.food = pos;
+                    snake.food_value = value;
+                    snake.food_eaten = false;
+                }
+                true
+            }
+            None => self.place_shared_food(snakes, settings, rng),
+        }
+    }
+
+    /// Add spare pellets until `total` (the primary included) are on the
+    /// board. Never removes any, and gives up when the board is too full.
+    pub fn top_up_food(&mut self, snakes: &[&Snake], total: usize, settings: &Settings, rng: &mut GameRng) {
+        let (bmin_r, bmin_c) = self.border_min;
+        let (bmax_r, bmax_c) = self.border_max;
+        while 1 + self.extra_food.len() < total {
+            let free = (0..100)
+                .map(|_| (rng.gen_range(bmin_r..bmax_r), rng.gen_range(bmin_c..bmax_c)))
+                .find(|&pos| self.is_open(pos, snakes));
+            let Some(pos) = free else {
                 return;
+            };
+            self.extra_food.push(pos);
+            let value = draw_food_value(settings, rng);
+            if value > 1 {
+                self.spare_values.insert(pos, value);
+            }
+        }
+    }
+
+    /// Pellets to keep on the board: `--food-count`, or with `--auto-food`
+    /// one per that many cells inside the current border, so a shrinking
+    /// border asks for fewer. Never less than the primary pellet.
+    pub fn food_target(&self, settings: &Settings) -> usize {
+        if settings.auto_food == 0 {
+            return settings.food_count;
+        }
+        let eff_h = self.border_max.0 - self.border_min.0;
+        let eff_w = self.border_max.1 - self.border_min.1;
+        (eff_w * eff_h / settings.auto_food).max(1)
+    }
+
+    /// Settle the pellets eaten this tick: score spares, turn eaten cells
+    /// into walls with `--food-walls`, then replace the primary pellet and
+    /// top up the spares.
+    pub fn resolve_food(&mut self, snakes: &mut [&mut Snake], settings: &Settings, rng: &mut GameRng) -> FoodTick {
+        let mut eaten: Vec<(usize, usize)> = snakes.iter().filter(|s| s.food_eaten).map(|s| s.food).collect();
+        let primary_eaten = !eaten.is_empty();
+        let mut ate: Vec<bool> = snakes.iter().map(|s| s.food_eaten).collect();
+        for (i, snake) in snakes.iter_mut().enumerate() {
+            let spares = self.check_extra_food_eaten(snake, settings);
+            ate[i] |= !spares.is_empty();
+            eaten.extend(spares);
+        }
+        if settings.steal && snakes.len() == 2 {
+            for (eater, victim) in [(0, 1), (1, 0)] {
+                if ate[eater] {
+                    snakes[victim].lose_point();
+                }
+            }
+        }
+        if settings.food_walls {
+            self.walls.extend(eaten.iter().copied());
+        }
+
+        let mut board_full = false;
+        if let Some(waiting) = self.mirror_food.as_ref().map(|m| m.waiting) {
+            // Each player only ever eats, and replaces, their own pellet. A
+            // full half waits for room; the round ends once both are full
+            let players = snakes.len().min(2);
+            for player in 0..players {
+                if snakes[player].food_eaten || waiting[player] {
+                    self.place_mirrored_food(snakes, player, settings);
+                }
+            }
+            board_full = self.mirror_food.as_ref().is_some_and(|m| m.waiting[..players].iter().all(|&w| w));
+        } else if primary_eaten {
+            board_full = !self.replace_shared_food(snakes, settings, rng);
+        }
+        if !eaten.is_empty() {
+            let snakes: Vec<&Snake> = snakes.iter().map(|s| &**s).collect();
+            self.top_up_food(&snakes, self.food_target(settings), settings, rng);
+        }
+        FoodTick { eaten: eaten.len(), board_full }
+    }
+
+    /// Move pellets a border shrink left outside the play area back inside
+    /// the current border; stranded bonus food just disappears.
+    pub fn rehome_food(&mut self, snakes: &mut [&mut Snake], settings: &Settings, rng: &mut GameRng) {
+        let (bmin, bmax) = (self.border_min, self.border_max);
+        let inside = |(r, c): (usize, usize)| r >= bmin.0 && r < bmax.0 && c >= bmin.1 && c < bmax.1;
+
+        let before = self.extra_food.len();
+        self.extra_food.retain(|&pos| inside(pos));
+        self.spare_values.retain(|&pos, _| inside(pos));
+        let spares_lost = self.extra_food.len() < before;
+        if self.bonus_food.as_ref().is_some_and(|b| !inside(b.pos)) {
+            self.bonus_food = None;
+        }
+        if self.bomb.is_some_and(|pos| !inside(pos)) {
+            self.bomb = None;
+        }
+        if self.rewind.is_some_and(|pos| !inside(pos)) {
+            self.rewind = None;
+        }
+        if self.split.is_some_and(|pos| !inside(pos)) {
+            self.split = None;
+        }
+        self.coins.retain(|coin| inside(coin.pos));
+        self.portals.retain(|pair| pair.iter().all(|&pos| inside(pos)));
+
+        if self.mirror_food.is_some() {
+            for player in 0..snakes.len().min(2) {
+                if !inside(snakes[player].food) {
+                    self.place_mirrored_food(snakes, player, settings);
+                }
             }
+        } else if snakes.first().is_some_and(|s| !inside(s.food)) {
+            self.place_shared_food(snakes, settings, rng);
         }
+        // An --auto-food target may have moved with the border; a surplus
+        // just drains as it's eaten
+        if spares_lost || settings.auto_food > 0 {
+            let snakes: Vec<&Snake> = snakes.iter().map(|s| &**s).collect();
+            self.top_up_food(&snakes, self.food_target(settings), settings, rng);
+        }
+    }
+
+    /// Eat the spare pellets under the snake's heads, scoring like regular
+    /// food. Returns where they were.
+    pub fn check_extra_food_eaten(&mut self, snake: &mut Snake, settings: &Settings) -> Vec<(usize, usize)> {
+        let mut eaten = Vec::new();
+        for (n, head) in snake.heads().collect::<Vec<_>>().into_iter().enumerate() {
+            let Some(i) = self.extra_food.iter().position(|&pos| pos == head) else {
+                continue;
+            };
+            let pos = self.extra_food.swap_remove(i);
+            snake.ticks_since_food = 0;
+            snake.points += self.spare_values.remove(&pos).unwrap_or(1);
+            if n == 0 {
+                snake.grow(settings.max_length);
+            } else {
+                snake.grow_twin(settings.max_length);
+            }
+            eaten.push(pos);
+        }
+        if !eaten.is_empty() {
+            snake.rescore(settings);
+        }
+        eaten
     }
 
-    pub fn maybe_spawn_bonus<R: Rng>(&mut self, snake: &Snake, rng: &mut R) {
+    pub fn maybe_spawn_bonus(&mut self, snakes: &[&Snake], rng: &mut GameRng) {
         if self.bonus_food.is_some() {
             return;
         }
@@ -90,13 +656,21 @@ impl GameMap {
         }
         let (bmin_r, bmin_c) = self.border_min;
         let (bmax_r, bmax_c) = self.border_max;
+        let open = |pos: (usize, usize)| self.is_open(pos, snakes);
+        if !self.bonus_spots.is_empty() {
+            // Next spot in the cycle, passing over any that are blocked
+            let count = self.bonus_spots.len();
+            let next = (0..count).map(|i| (self.bonus_next + i) % count).find(|&i| open(self.bonus_spots[i]));
+            if let Some(i) = next {
+                self.bonus_food = Some(BonusFood { pos: self.bonus_spots[i], lifetime: BONUS_FOOD_LIFETIME });
+                self.bonus_next = (i + 1) % count;
+            }
+            return;
+        }
         for _ in 0..50 {
             let r = rng.gen_range(bmin_r..bmax_r);
             let c = rng.gen_range(bmin_c..bmax_c);
-            if !snake.parts.contains(&(r, c))
-                && !self.walls.contains(&(r, c))
-                && (r, c) != snake.food
-            {
+            if open((r, c)) {
                 self.bonus_food = Some(BonusFood {
                     pos: (r, c),
                     lifetime: BONUS_FOOD_LIFETIME,
@@ -106,6 +680,132 @@ impl GameMap {
         }
     }
 
+    /// With `--bomb-radius`, now and then drop a bomb pickup on an open
+    /// cell while none is out.
+    pub fn maybe_spawn_bomb(&mut self, snakes: &[&Snake], settings: &Settings, rng: &mut GameRng) {
+        if settings.bomb_radius == 0 || self.bomb.is_some() || rng.gen_range(0..BOMB_SPAWN_ODDS) != 0 {
+            return;
+        }
+        let open: Vec<(usize, usize)> = (0..self.height)
+            .flat_map(|r| (0..self.width).map(move |c| (r, c)))
+            .filter(|&pos| self.is_open(pos, snakes))
+            .collect();
+        if !open.is_empty() {
+            self.bomb = Some(open[rng.gen_range(0..open.len())]);
+        }
+    }
+
+    /// A head on the bomb pickup banks one charge. Returns whether it did.
+    pub fn check_bomb_collected(&mut self, snake: &mut Snake) -> bool {
+        if self.bomb != Some(snake.head) {
+            return false;
+        }
+        self.bomb = None;
+        snake.bomb_charges += 1;
+        true
+    }
+
+    /// With `--rewind`, now and then drop a rewind pickup on an open cell
+    /// while none is out.
+    pub fn maybe_spawn_rewind(&mut self, snakes: &[&Snake], settings: &Settings, rng: &mut GameRng) {
+        if !settings.rewind || self.rewind.is_some() || rng.gen_range(0..REWIND_SPAWN_ODDS) != 0 {
+            return;
+        }
+        let open: Vec<(usize, usize)> = (0..self.height)
+            .flat_map(|r| (0..self.width).map(move |c| (r, c)))
+            .filter(|&pos| self.is_open(pos, snakes))
+            .collect();
+        if !open.is_empty() {
+            self.rewind = Some(open[rng.gen_range(0..open.len())]);
+        }
+    }
+
+    /// A head on the rewind pickup banks one charge. Returns whether it did.
+    pub fn check_rewind_collected(&mut self, snake: &mut Snake) -> bool {
+        if self.rewind != Some(snake.head) {
+            return false;
+        }
+        self.rewind = None;
+        snake.rewind_charges += 1;
+        true
+    }
+
+    /// With `--powerups`, now and then drop a split pickup on an open cell
+    /// while none is out and no snake is already split.
+    pub fn maybe_spawn_split(&mut self, snakes: &[&Snake], settings: &Settings, rng: &mut GameRng) {
+        if !settings.powerups || self.split.is_some() || snakes.iter().any(|s| s.twin.is_some()) {
+            return;
+        }
+        if rng.gen_range(0..SPLIT_SPAWN_ODDS) != 0 {
+            return;
+        }
+        let open: Vec<(usize, usize)> = (0..self.height)
+            .flat_map(|r| (0..self.width).map(move |c| (r, c)))
+            .filter(|&pos| self.is_open(pos, snakes))
+            .collect();
+        if !open.is_empty() {
+            self.split = Some(open[rng.gen_range(0..open.len())]);
+        }
+    }
+
+    /// A head on the split pickup divides the snake in two. A snake too
+    /// short to split leaves the pickup where it is. Returns whether it split.
+    pub fn check_split_collected(&mut self, snake: &mut Snake) -> bool {
+        if self.split != Some(snake.head) || !snake.split() {
+            return false;
+        }
+        self.split = None;
+        true
+    }
+
+    /// Spend one of `snake`'s bomb charges, clearing every wall within
+    /// `radius` cells of the head (a square blast, diagonals included).
+    /// Does nothing without a charge. Returns the walls destroyed.
+    pub fn detonate(&mut self, snake: &mut Snake, radius: usize) -> usize {
+        if snake.bomb_charges == 0 {
+            return 0;
+        }
+        snake.bomb_charges -= 1;
+        let (hr, hc) = snake.head;
+        let before = self.walls.len();
+        let outside = &self.outside;
+        self.walls.retain(|&(r, c)| r.abs_diff(hr) > radius || c.abs_diff(hc) > radius || outside.contains(&(r, c)));
+        before - self.walls.len()
+    }
+
+    /// With `--coins`, each cell a tail just left has a 1 in
+    /// `COIN_DROP_ODDS` chance of getting a coin worth 1 to `COIN_MAX_VALUE`.
+    pub fn drop_coins(&mut self, snakes: &[&Snake], settings: &Settings, rng: &mut GameRng) {
+        if !settings.coins {
+            return;
+        }
+        for pos in snakes.iter().filter_map(|s| s.vacated) {
+            if rng.gen_range(0..COIN_DROP_ODDS) == 0 && self.is_open(pos, snakes) {
+                let value = rng.gen_range(1..COIN_MAX_VALUE + 1);
+                self.coins.push(Coin { pos, value, lifetime: COIN_LIFETIME });
+            }
+        }
+    }
+
+    /// Age the coins, removing any that have run out of time.
+    pub fn tick_coins(&mut self) {
+        for coin in &mut self.coins {
+            coin.lifetime = coin.lifetime.saturating_sub(1);
+        }
+        self.coins.retain(|coin| coin.lifetime > 0);
+    }
+
+    /// A head on a coin picks it up. Returns the value collected.
+    pub fn collect_coins(&mut self, snake: &mut Snake, settings: &Settings) -> usize {
+        let Some(idx) = self.coins.iter().position(|coin| coin.pos == snake.head) else {
+            return 0;
+        };
+        let value = self.coins.swap_remove(idx).value;
+        snake.coin_points += value;
+        snake.rescore(settings);
+        value
+    }
+
     pub fn tick_bonus(&mut self) {
         if let Some(ref mut bonus) = self.bonus_food {
             bonus.lifetime = bonus.lifetime.saturating_sub(1);
@@ -115,11 +815,13 @@ impl GameMap {
         }
     }
 
-    pub fn check_bonus_eaten(&mut self, snake: &mut Snake) -> bool {
+    pub fn check_bonus_eaten(&mut self, snake: &mut Snake, settings: &Settings) -> bool {
         if let Some(ref bonus) = self.bonus_food {
             if snake.head == bonus.pos {
-                snake.score += BONUS_FOOD_SCORE;
-                snake.length += 1;
+                snake.ticks_since_food = 0;
+                snake.points += BONUS_FOOD_SCORE;
+                snake.grow(settings.max_length);
+                snake.rescore(settings);
                 self.bonus_food = None;
                 return true;
             }
@@ -130,7 +832,7 @@ impl GameMap {
     pub fn update_shrinking_border(&mut self, snake: &Snake) {
         self.shrink_timer += 1;
         // Shrink every 50 frames
-        if self.shrink_timer % 50 != 0 {
+        if !self.shrink_timer.is_multiple_of(50) {
             return;
         }
         let (min_r, min_c) = self.border_min;
@@ -158,29 +860,176 @@ impl GameMap {
         let _ = snake; // snake position checked elsewhere
     }
 
-    pub fn render(
-        &mut self,
-        snakes: &[&Snake],
-        settings: &Settings,
-        paused: bool,
-        frame_count: usize,
-    ) -> String {
-        // Clear grid
+    /// Set up a round: spawn the snakes, put the board back to its starting
+    /// walls, clear last round's pickups, then lay the food, warp tiles,
+    /// portals and `--obstacles`. The first round, every restart and replay
+    /// playback all come through here, so a round seed always builds the
+    /// same board. Fails if a `--two-headed` second head has no room.
+    pub fn start_round(&mut self, snakes: &mut [&mut Snake], settings: &Settings, rng: &mut GameRng) -> Result<(), String> {
+        for (player, snake) in snakes.iter_mut().enumerate() {
+            let (head, dir) = settings.round_spawn(player, snake.map_width, snake.map_height, rng);
+            snake.spawn_fresh(head, dir, settings)?;
+        }
+        self.reset_board(settings);
+        self.coins.clear();
+        self.bonus_food = None;
+        self.bomb = None;
+        self.rewind = None;
+        self.split = None;
+        if let Some(ref map) = settings.map {
+            map.place_reserved(self);
+        }
+        self.seed_food(snakes, settings, rng);
+        if let Some(ref map) = settings.map {
+            map.place_food(self, snakes);
+            map.place_lock(self);
+        }
+        let snakes: Vec<&Snake> = snakes.iter().map(|s| &**s).collect();
+        self.place_warp_tiles(settings.warp_tiles, &snakes, rng);
+        self.place_portals(settings.portals, &snakes, rng);
+        // A --from-snapshot layout brings its own walls
+        if settings.layout.is_none() && settings.obstacles > 0 {
+            self.place_walls(settings.obstacles, &snakes, settings, rng);
+        }
+        Ok(())
+    }
+
+    /// Put the board back to its starting borders and walls for a restart:
+    /// the `--from-snapshot` layout or an open board, then `--shape`. Walls
+    /// the last round added (obstacles, `--food-walls`) go with it.
+    pub fn reset_board(&mut self, settings: &Settings) {
+        match settings.layout {
+            Some(ref layout) => layout.restore_layout(self),
+            None => {
+                self.walls.clear();
+                self.border_min = (0, 0);
+                self.border_max = (self.height, self.width);
+                self.shrink_timer = 0;
+            }
+        }
+        self.apply_shape(settings.shape);
+        if settings.growing_board {
+            self.start_growing_board();
+        }
+    }
+
+    /// Shrink the play area to where a `--growing-board` round starts.
+    pub fn start_growing_board(&mut self) {
+        (self.border_min, self.border_max) = growing_board_start(self.width, self.height);
+    }
+
+    /// `--growing-board`: push every side of the border out one cell per
+    /// `GROWING_BOARD_STEP` segments the longest snake has grown, up to the
+    /// full map. Food placement draws from inside the border, so new cells
+    /// take pellets straight away. Returns whether the border moved.
+    pub fn update_growing_board(&mut self, snakes: &[&Snake]) -> bool {
+        let longest = snakes.iter().map(|s| s.length).max().unwrap_or(0);
+        let level = longest.saturating_sub(INITIAL_SNAKE_LENGTH) / GROWING_BOARD_STEP;
+        let (start_min, start_max) = growing_board_start(self.width, self.height);
+        let min = (start_min.0.saturating_sub(level), start_min.1.saturating_sub(level));
+        let max = ((start_max.0 + level).min(self.height), (start_max.1 + level).min(self.width));
+        // The board never closes back up
+        let min = (min.0.min(self.border_min.0), min.1.min(self.border_min.1));
+        let max = (max.0.max(self.border_max.0), max.1.max(self.border_max.1));
+        if (min, max) == (self.border_min, self.border_max) {
+            return false;
+        }
+        self.border_min = min;
+        self.border_max = max;
+        true
+    }
+
+    fn clear_grid(&mut self, settings: &Settings) {
+        let (bmin_r, bmin_c) = self.border_min;
+        let (bmax_r, bmax_c) = self.border_max;
         for r in 0..self.height {
             for c in 0..self.width {
-                let (bmin_r, bmin_c) = self.border_min;
-                let (bmax_r, bmax_c) = self.border_max;
                 if r < bmin_r || r >= bmax_r || c < bmin_c || c >= bmax_c {
-                    self.grid[r][c] = Cell::wall();
+                    self.grid[r][c] = Cell::border(settings);
                 } else {
                     self.grid[r][c] = Cell::empty();
                 }
             }
         }
 
-        // Draw walls
         for &(r, c) in &self.walls {
-            self.grid[r][c] = Cell::wall();
+            self.grid[r][c] = Cell::wall(settings);
+        }
+        for &(r, c) in &self.outside {
+            self.grid[r][c] = Cell::border(settings);
+        }
+    }
+
+    /// Faintly mark the `--art-target` cells on open floor; snakes and
+    /// food drawn afterwards cover them.
+    fn draw_art_target(&mut self, settings: &Settings) {
+        let Some(ref art) = settings.art else {
+            return;
+        };
+        for &(r, c) in &art.cells {
+            if r < self.height && c < self.width && self.grid[r][c].ch == MAP_CHAR {
+                self.grid[r][c] = Cell { ch: ART_TARGET_CHAR, color: Color::DarkGrey };
+            }
+        }
+    }
+
+    /// Draw the primary pellet(s) and any spares. A pellet worth more than
+    /// one point shows its value instead of the food glyph.
+    fn draw_food(&mut self, snakes: &[&Snake], settings: &Settings) {
+        let primary = snakes.iter().map(|s| (s.food, s.food_value));
+        let spares = self.extra_food.iter().map(|&pos| (pos, self.spare_values.get(&pos).copied().unwrap_or(1)));
+        let pellets: Vec<_> = primary.chain(spares).collect();
+        for ((r, c), value) in pellets {
+            if r < self.height && c < self.width {
+                self.grid[r][c] = match char::from_digit(value as u32, 10) {
+                    Some(digit) if value > 1 => Cell { ch: digit, color: Color::Yellow },
+                    _ => Cell { ch: settings.food, color: Color::Red },
+                };
+            }
+        }
+    }
+
+    /// Border cells with an `--edge-gaps` opening on their outer side,
+    /// following the border as it shrinks or grows.
+    pub fn edge_gap_cells(&self, gaps: usize) -> Vec<(usize, usize)> {
+        let (bmin_r, bmin_c) = self.border_min;
+        let (bmax_r, bmax_c) = self.border_max;
+        let (h, w) = (bmax_r - bmin_r, bmax_c - bmin_c);
+        let mut cells = Vec::new();
+        if gaps == 0 || h == 0 || w == 0 {
+            return cells;
+        }
+        for c in (0..w).filter(|&c| is_edge_gap(c, w, gaps)) {
+            cells.extend([(bmin_r, bmin_c + c), (bmax_r - 1, bmin_c + c)]);
+        }
+        for r in (0..h).filter(|&r| is_edge_gap(r, h, gaps)) {
+            cells.extend([(bmin_r + r, bmin_c), (bmin_r + r, bmax_c - 1)]);
+        }
+        cells
+    }
+
+    /// Compose the board into the cell grid without producing any output.
+    pub fn render_cells(&mut self, snakes: &[&Snake], settings: &Settings, frame_count: usize) {
+        self.clear_grid(settings);
+        self.draw_art_target(settings);
+        for (r, c) in self.edge_gap_cells(settings.edge_gaps) {
+            if !self.walls.contains(&(r, c)) {
+                self.grid[r][c] = Cell { ch: EDGE_GAP_CHAR, color: Color::Green };
+            }
+        }
+        for &(r, c) in &self.warp_tiles {
+            self.grid[r][c] = Cell { ch: WARP_TILE_CHAR, color: Color::Blue };
+        }
+        for (pair, ends) in self.portals.iter().enumerate() {
+            for &(r, c) in ends {
+                self.grid[r][c] = Cell { ch: PORTAL_CHAR, color: portal_color(pair) };
+            }
+        }
+        if let Some((r, c)) = self.gate {
+            self.grid[r][c] = Cell { ch: GATE_CHAR, color: Color::DarkYellow };
+        }
+        if let Some((r, c)) = self.key {
+            self.grid[r][c] = Cell { ch: KEY_CHAR, color: Color::Yellow };
         }
 
         // Draw snake(s)
@@ -188,14 +1037,25 @@ impl GameMap {
         let head_colors = [Color::Yellow, Color::Magenta];
 
         for (idx, snake) in snakes.iter().enumerate() {
-            let body_color = snake_colors[idx % snake_colors.len()];
+            let health = snake.health(settings.hungry).filter(|_| settings.health_color);
+            let body_color = health.map_or(snake_colors[idx % snake_colors.len()], health_color);
             let hd_color = head_colors[idx % head_colors.len()];
 
-            for &(r, c) in &snake.parts {
+            for &(r, c) in snake.parts.iter().chain(snake.twin.iter().flat_map(|t| &t.parts)) {
                 if r < self.height && c < self.width {
                     self.grid[r][c] = Cell { ch: settings.body, color: body_color };
                 }
             }
+            if let Some(ref twin) = snake.twin {
+                let (r, c) = twin.head;
+                self.grid[r][c] = Cell { ch: settings.head_char(twin.direction), color: hd_color };
+            }
+            // Tail, dimmed so the direction of travel reads at a glance
+            if let (Some(ch), Some(&(r, c)), true) = (settings.tail_char, snake.parts.front(), snake.parts.len() > 1) {
+                if r < self.height && c < self.width {
+                    self.grid[r][c] = Cell { ch, color: dim_color(body_color) };
+                }
+            }
             // Head
             if snake.head.0 < self.height && snake.head.1 < self.width {
                 self.grid[snake.head.0][snake.head.1] = Cell {
@@ -205,91 +1065,159 @@ impl GameMap {
             }
         }
 
-        // Draw food (from first snake)
-        if let Some(s) = snakes.first() {
-            if s.food.0 < self.height && s.food.1 < self.width {
-                self.grid[s.food.0][s.food.1] = Cell { ch: settings.food, color: Color::Red };
-            }
+        self.draw_food(snakes, settings);
+
+        if let Some((r, c)) = self.bomb {
+            self.grid[r][c] = Cell { ch: BOMB_CHAR, color: Color::Red };
+        }
+        if let Some((r, c)) = self.rewind {
+            self.grid[r][c] = Cell { ch: REWIND_CHAR, color: Color::Cyan };
+        }
+        if let Some((r, c)) = self.split {
+            self.grid[r][c] = Cell { ch: SPLIT_CHAR, color: Color::Green };
+        }
+        for coin in &self.coins {
+            let (r, c) = coin.pos;
+            self.grid[r][c] = Cell { ch: COIN_CHAR, color: Color::Yellow };
         }
 
         // Draw bonus food
         if let Some(ref bonus) = self.bonus_food {
             let (r, c) = bonus.pos;
             if r < self.height && c < self.width {
-                // Blink effect: alternate color every few frames
-                let blink_color = if (frame_count / 3) % 2 == 0 { Color::Magenta } else { Color::Yellow };
+                // Blink effect: alternate color every few frames, unless --reduced-motion
+                let blink_color = if settings.reduced_motion || (frame_count / 3).is_multiple_of(2) {
+                    Color::Magenta
+                } else {
+                    Color::Yellow
+                };
                 self.grid[r][c] = Cell { ch: BONUS_FOOD_CHAR, color: blink_color };
             }
         }
+    }
 
-        // Build output string with ANSI colors
-        let mut buf = String::with_capacity((self.height + 4) * (self.width * 2 + 20));
-
-        // Score line
-        if !settings.hide_score {
-            let score_text = if snakes.len() > 1 {
-                format!("P1: {}  P2: {}", snakes[0].score, snakes[1].score)
-            } else {
-                format!("Score: {}", snakes[0].score)
+    /// Draw a partial head in each snake's next cell. Purely cosmetic:
+    /// collisions still happen on whole ticks.
+    fn draw_interpolation(&mut self, snakes: &[&Snake], settings: &Settings, fraction: f32) {
+        let head_colors = [Color::Yellow, Color::Magenta];
+        for (idx, snake) in snakes.iter().enumerate() {
+            let Some(glyph) = interpolation_glyph(snake.direction, fraction) else {
+                continue;
             };
-            let map_display_width = self.width * 2;
-            let padding = if score_text.len() < map_display_width {
-                (map_display_width - score_text.len()) / 2
-            } else {
-                0
+            let Some((r, c)) = Snake::step(snake.head, snake.direction, settings, self.border_min, self.border_max) else {
+                continue;
             };
-            buf.push_str(&" ".repeat(padding));
-            let styled: StyledContent<&str> = score_text.as_str().with(Color::White);
-            buf.push_str(&format!("{styled}"));
-            buf.push_str("\r\n");
+            // Only lean into open floor or food, never over walls or bodies
+            let target = &self.grid[r][c];
+            if target.ch == MAP_CHAR || target.ch == settings.food {
+                self.grid[r][c] = Cell { ch: glyph, color: dim_color(head_colors[idx % head_colors.len()]) };
+            }
         }
+    }
 
-        // Map rows
-        for row in &self.grid {
-            for cell in row.iter() {
-                let styled: StyledContent<String> = cell.ch.to_string().with(cell.color);
-                buf.push_str(&format!("{styled} "));
+    /// Faint marker on the open cell the AI would move player 1 into next.
+    fn draw_coach_hint(&mut self, snakes: &[&Snake], settings: &Settings) {
+        if let Some((r, c)) = ai::coach_hint(snakes, self, settings) {
+            if self.grid[r][c].ch == MAP_CHAR {
+                self.grid[r][c] = Cell { ch: COACH_HINT_CHAR, color: Color::DarkCyan };
             }
-            buf.push_str("\r\n");
         }
+    }
 
-        if paused {
-            let pause_msg = "  ** PAUSED — press P or Space to resume **";
-            let styled: StyledContent<&str> = pause_msg.with(Color::Yellow);
-            buf.push_str(&format!("{styled}\r\n"));
+    /// `--fog`: dim every cell farther than `radius` from all heads, and
+    /// hide the pickups out there entirely.
+    fn apply_fog(&mut self, snakes: &[&Snake], radius: usize) {
+        let mut pickups: Vec<(usize, usize)> = snakes.iter().map(|s| s.food).collect();
+        pickups.extend(&self.extra_food);
+        pickups.extend(self.bonus_food.as_ref().map(|b| b.pos));
+        pickups.extend(self.bomb);
+        pickups.extend(self.rewind);
+        pickups.extend(self.split);
+        pickups.extend(self.coins.iter().map(|coin| coin.pos));
+
+        let in_sight = |(r, c): (usize, usize)| {
+            snakes.iter().any(|s| {
+                let (dr, dc) = (r.abs_diff(s.head.0), c.abs_diff(s.head.1));
+                dr * dr + dc * dc <= radius * radius
+            })
+        };
+        for (r, row) in self.grid.iter_mut().enumerate() {
+            for (c, cell) in row.iter_mut().enumerate() {
+                if in_sight((r, c)) {
+                    continue;
+                }
+                if pickups.contains(&(r, c)) {
+                    *cell = Cell::empty();
+                }
+                cell.color = dim_color(cell.color);
+            }
         }
+    }
 
-        buf
+    /// The composed grid from the last `render_cells` call.
+    #[cfg_attr(not(feature = "gif"), allow(dead_code))]
+    pub fn cells(&self) -> &[Vec<Cell>] {
+        &self.grid
     }
 
-    pub fn render_death_animation(
+    /// Render a frame. `fraction` is how far (0.0-1.0) we are into the
+    /// current tick; with `--smooth-move` the heads lean into their next cell.
+    pub fn render(
         &mut self,
         snakes: &[&Snake],
         settings: &Settings,
-        frame: usize,
+        paused: bool,
+        frame_count: usize,
+        fraction: f32,
     ) -> String {
-        // Flash snake between red and dark on alternating frames
-        // Clear grid
-        for r in 0..self.height {
-            for c in 0..self.width {
-                let (bmin_r, bmin_c) = self.border_min;
-                let (bmax_r, bmax_c) = self.border_max;
-                if r < bmin_r || r >= bmax_r || c < bmin_c || c >= bmax_c {
-                    self.grid[r][c] = Cell::wall();
-                } else {
-                    self.grid[r][c] = Cell::empty();
-                }
+        self.render_cells(snakes, settings, frame_count);
+        if settings.smooth_move && !paused {
+            self.draw_interpolation(snakes, settings, fraction);
+        }
+        if settings.coach {
+            self.draw_coach_hint(snakes, settings);
+        }
+        if settings.fog > 0 {
+            self.apply_fog(snakes, settings.fog);
+        }
+        if paused {
+            // Dim the frozen board so the pause overlay stands out
+            for cell in self.grid.iter_mut().flatten() {
+                cell.color = dim_color(cell.color);
             }
         }
 
-        for &(r, c) in &self.walls {
-            self.grid[r][c] = Cell::wall();
+        let mut buf = String::with_capacity((self.height + 4) * (self.width * settings.cell_width() + 20));
+        self.push_score_line(&mut buf, snakes, settings, Some(frame_count));
+        self.push_separator(&mut buf, settings);
+        self.push_rows(&mut buf, settings);
+        self.push_separator(&mut buf, settings);
+
+        if let (true, Some(points)) = (paused, self.milestone) {
+            let text = format!("  ** {points} POINTS! Well done — press any key to go on **");
+            push_styled(&mut buf, &text, Color::Green, settings);
+            buf.push_str("\r\n");
+        } else if paused {
+            push_styled(&mut buf, "  ** PAUSED — press P or Space to resume **", Color::Yellow, settings);
+            buf.push_str("\r\n");
         }
 
-        let flash_color = if frame % 2 == 0 { Color::Red } else { Color::DarkRed };
+        self.center(buf, settings)
+    }
+
+    pub fn render_death_animation(
+        &mut self,
+        snakes: &[&Snake],
+        settings: &Settings,
+        frame: usize,
+    ) -> String {
+        // Flash snake between red and dark on alternating frames (steady red with --reduced-motion)
+        self.clear_grid(settings);
+
+        let flash_color = if settings.reduced_motion || frame.is_multiple_of(2) { Color::Red } else { Color::DarkRed };
 
         for snake in snakes {
-            for &(r, c) in &snake.parts {
+            for &(r, c) in snake.parts.iter().chain(snake.twin.iter().flat_map(|t| &t.parts)) {
                 if r < self.height && c < self.width {
                     self.grid[r][c] = Cell { ch: settings.body, color: flash_color };
                 }
@@ -302,41 +1230,1456 @@ impl GameMap {
             }
         }
 
-        // Food
-        if let Some(s) = snakes.first() {
-            if s.food.0 < self.height && s.food.1 < self.width {
-                self.grid[s.food.0][s.food.1] = Cell { ch: settings.food, color: Color::Red };
-            }
+        self.draw_food(snakes, settings);
+
+        let mut buf = String::with_capacity((self.height + 4) * (self.width * settings.cell_width() + 20));
+        self.push_score_line(&mut buf, snakes, settings, None);
+        self.push_separator(&mut buf, settings);
+        self.push_rows(&mut buf, settings);
+        self.push_separator(&mut buf, settings);
+        self.center(buf, settings)
+    }
+
+    /// With `--center-board`, pad the frame so the board sits in the middle
+    /// of the terminal. The size is re-read every frame to follow resizes.
+    fn center(&self, buf: String, settings: &Settings) -> String {
+        if !settings.center_board {
+            return buf;
+        }
+        let Ok((cols, rows)) = crossterm::terminal::size() else {
+            return buf;
+        };
+        // Score line above the grid and a status line below it
+        let separators = if settings.hud_borders { 2 } else { 0 };
+        let board = (self.width * settings.cell_width(), self.height + 2 + separators);
+        let (left, top) = board_padding((cols as usize, rows as usize), board);
+        let indent = " ".repeat(left);
+        let mut out = "\r\n".repeat(top);
+        for line in buf.split_inclusive("\r\n") {
+            out.push_str(&indent);
+            out.push_str(line);
         }
+        out
+    }
 
-        let mut buf = String::with_capacity((self.height + 4) * (self.width * 2 + 20));
+    /// `tick` is the logical frame, for the `--metronome` beat; None hides it.
+    fn push_score_line(&self, buf: &mut String, snakes: &[&Snake], settings: &Settings, tick: Option<usize>) {
+        let mut score_text = if settings.hide_score {
+            String::new()
+        } else if snakes.len() > 1 {
+            format!("P1: {}  P2: {}", snakes[0].score, snakes[1].score)
+        } else {
+            format!("Score: {}", snakes[0].score)
+        };
+        if let Some(tick) = tick.filter(|_| settings.metronome) {
+            if !score_text.is_empty() {
+                score_text.insert(0, ' ');
+            }
+            // --reduced-motion keeps the beat's dot from pulsing
+            score_text.insert(0, if settings.reduced_motion { metronome_beat(0) } else { metronome_beat(tick) });
+        }
+        if settings.hungry > 0 && snakes.iter().any(|s| s.starving()) {
+            if !score_text.is_empty() {
+                score_text.push_str("  ");
+            }
+            score_text.push_str("STARVING!");
+        }
+        if settings.disable_borders && settings.wrap_limit > 0 {
+            let left: Vec<String> = snakes.iter().filter_map(|s| s.wraps_left(settings.wrap_limit)).map(|n| n.to_string()).collect();
+            if !score_text.is_empty() {
+                score_text.push_str("  ");
+            }
+            score_text.push_str(&format!("WRAPS: {}", left.join("/")));
+        }
+        if settings.bomb_radius > 0 && snakes[0].bomb_charges > 0 {
+            if !score_text.is_empty() {
+                score_text.push_str("  ");
+            }
+            score_text.push_str(&format!("BOMBS: {}", snakes[0].bomb_charges));
+        }
+        if settings.rewind && snakes[0].rewind_charges > 0 {
+            if !score_text.is_empty() {
+                score_text.push_str("  ");
+            }
+            score_text.push_str(&format!("REWIND: {}", snakes[0].rewind_charges));
+        }
+        if snakes.iter().any(|s| s.has_key) {
+            if !score_text.is_empty() {
+                score_text.push_str("  ");
+            }
+            score_text.push_str("KEY");
+        }
+        if snakes.iter().any(|s| s.at_length_cap(settings.max_length)) {
+            if !score_text.is_empty() {
+                score_text.push_str("  ");
+            }
+            score_text.push_str("MAX LENGTH");
+        }
+        if !self.status.is_empty() {
+            if !score_text.is_empty() {
+                score_text.push_str("  ");
+            }
+            score_text.push_str(&self.status);
+        }
+        if self.running_slow {
+            if !score_text.is_empty() {
+                score_text.push_str("  ");
+            }
+            score_text.push_str("SLOW");
+        }
+        // The whole line flashes for a moment after a near miss, or just
+        // says so with --reduced-motion
+        let near_miss = snakes.iter().any(|s| s.near_miss_flash > 0);
+        if near_miss {
+            if !score_text.is_empty() {
+                score_text.push_str("  ");
+            }
+            score_text.push_str("NEAR MISS!");
+        }
+        if score_text.is_empty() {
+            return;
+        }
+        let map_display_width = self.width * settings.cell_width();
+        let padding = map_display_width.saturating_sub(score_text.chars().count()) / 2;
+        buf.push_str(&" ".repeat(padding));
+        let flash = near_miss && !settings.reduced_motion;
+        push_styled(buf, &score_text, if flash { Color::Magenta } else { Color::White }, settings);
+        buf.push_str("\r\n");
+    }
 
-        if !settings.hide_score {
-            let score_text = if snakes.len() > 1 {
-                format!("P1: {}  P2: {}", snakes[0].score, snakes[1].score)
-            } else {
-                format!("Score: {}", snakes[0].score)
-            };
-            let map_display_width = self.width * 2;
-            let padding = if score_text.len() < map_display_width {
-                (map_display_width - score_text.len()) / 2
-            } else {
-                0
-            };
-            buf.push_str(&" ".repeat(padding));
-            let styled: StyledContent<&str> = score_text.as_str().with(Color::White);
-            buf.push_str(&format!("{styled}"));
+    /// With `--hud-borders`, a rule as wide as the board between it and the HUD.
+    fn push_separator(&self, buf: &mut String, settings: &Settings) {
+        if settings.hud_borders {
+            push_styled(buf, &hud_separator(self.width, settings.cell_width()), Color::DarkGrey, settings);
             buf.push_str("\r\n");
         }
+    }
+
+    fn push_rows(&self, buf: &mut String, settings: &Settings) {
+        let spacing = " ".repeat(settings.cell_spacing as usize);
+        if settings.no_color {
+            // Fast path: plain glyphs, no escape codes at all
+            for row in &self.grid {
+                for cell in row.iter() {
+                    buf.push(cell.ch);
+                    buf.push_str(&spacing);
+                }
+                buf.push_str("\r\n");
+            }
+            return;
+        }
 
         for row in &self.grid {
             for cell in row.iter() {
                 let styled: StyledContent<String> = cell.ch.to_string().with(cell.color);
-                buf.push_str(&format!("{styled} "));
+                buf.push_str(&format!("{styled}{spacing}"));
             }
             buf.push_str("\r\n");
         }
+    }
+}
+
+/// Points a new pellet is worth: 1, or with `--food-values` up to
+/// `FOOD_VALUE_MAX`, each value `FOOD_VALUE_ODDS` times rarer than the one
+/// below it. Draws nothing without the flag.
+fn draw_food_value(settings: &Settings, rng: &mut GameRng) -> usize {
+    let mut value = 1;
+    while settings.food_values && value < FOOD_VALUE_MAX && rng.gen_range(0..FOOD_VALUE_ODDS) == 0 {
+        value += 1;
+    }
+    value
+}
+
+/// Colors told apart from the board, walls and warp tiles, handed out to
+/// portal pairs in turn.
+const PORTAL_COLORS: [Color; 5] = [Color::Cyan, Color::Magenta, Color::Green, Color::DarkYellow, Color::DarkCyan];
+
+/// Color shared by both ends of portal pair `pair`.
+pub fn portal_color(pair: usize) -> Color {
+    PORTAL_COLORS[pair % PORTAL_COLORS.len()]
+}
+
+/// A whole frame ready to go out in one write: cursor home, a clear of the
+/// screen when `clear` is set, then the rendered board and HUD. In-between
+/// `--smooth-move` redraws skip the clear since they cover the same cells.
+pub fn compose_frame(frame: &str, clear: bool) -> String {
+    let mut out = String::with_capacity(frame.len() + 16);
+    let _ = cursor::MoveTo(0, 0).write_ansi(&mut out);
+    if clear {
+        let _ = terminal::Clear(terminal::ClearType::All).write_ansi(&mut out);
+    }
+    out.push_str(frame);
+    out
+}
+
+/// Separator line matching the display width of a board `width` cells
+/// wide, each cell taking `cell_width` columns.
+pub fn hud_separator(width: usize, cell_width: usize) -> String {
+    "─".repeat(width * cell_width)
+}
+
+/// Metronome indicator for logical tick `tick`. It depends only on the
+/// tick, so redraws between ticks (smooth movement, pause) never flip it.
+pub fn metronome_beat(tick: usize) -> char {
+    if tick.is_multiple_of(2) { '●' } else { '○' }
+}
+
+/// Glyph for a head that is `fraction` of the way into its next cell:
+/// nothing at first, then a half block on the side it enters from, then a
+/// full block just before the tick lands.
+pub fn interpolation_glyph(dir: Direction, fraction: f32) -> Option<char> {
+    if fraction < 0.25 {
+        None
+    } else if fraction < 0.75 {
+        Some(match dir {
+            Direction::East => '▌',
+            Direction::West => '▐',
+            Direction::North => '▄',
+            Direction::South => '▀',
+        })
+    } else {
+        Some('█')
+    }
+}
+
+/// Left and top padding that centers a `board` of (columns, rows) in a
+/// terminal of `term` (columns, rows); zero when the board doesn't fit.
+pub fn board_padding(term: (usize, usize), board: (usize, usize)) -> (usize, usize) {
+    (term.0.saturating_sub(board.0) / 2, term.1.saturating_sub(board.1) / 2)
+}
+
+/// Body color for a `--health-color` snake: green when healthy, through
+/// yellow at half health, to red as it runs out.
+pub fn health_color(health: f32) -> Color {
+    let health = health.clamp(0.0, 1.0);
+    let r = ((1.0 - health) * 2.0).min(1.0) * 255.0;
+    let g = (health * 2.0).min(1.0) * 255.0;
+    Color::Rgb { r: r.round() as u8, g: g.round() as u8, b: 0 }
+}
+
+/// Darker variant of a color, used to dim the board while paused.
+pub fn dim_color(color: Color) -> Color {
+    match color {
+        Color::Red => Color::DarkRed,
+        Color::Green => Color::DarkGreen,
+        Color::Yellow => Color::DarkYellow,
+        Color::Blue => Color::DarkBlue,
+        Color::Magenta => Color::DarkMagenta,
+        Color::Cyan => Color::DarkCyan,
+        Color::White => Color::Grey,
+        Color::Rgb { r, g, b } => Color::Rgb { r: r / 2, g: g / 2, b: b / 2 },
+        _ => Color::DarkGrey,
+    }
+}
+
+fn push_styled(buf: &mut String, text: &str, color: Color, settings: &Settings) {
+    if settings.no_color {
+        buf.push_str(text);
+    } else {
+        let styled: StyledContent<&str> = text.with(color);
+        buf.push_str(&format!("{styled}"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use crate::rng::RngAlgorithm;
+
+    fn test_settings(args: &[&str]) -> Settings {
+        let mut settings = Settings::parse_from(std::iter::once("test").chain(args.iter().copied())).resolve();
+        settings.map_width = 20;
+        settings.map_height = 20;
+        settings
+    }
+
+    fn strip_ansi(s: &str) -> String {
+        let mut out = String::with_capacity(s.len());
+        let mut chars = s.chars();
+        while let Some(ch) = chars.next() {
+            if ch == '\x1b' {
+                // Skip CSI sequence up to and including its final byte
+                for c in chars.by_ref() {
+                    if c.is_ascii_alphabetic() {
+                        break;
+                    }
+                }
+            } else {
+                out.push(ch);
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn test_player_two_eats_shared_food() {
+        let settings = test_settings(&["--multiplayer"]);
+        let mut p1 = Snake::new(20, 20);
+        let mut p2 = Snake::new(20, 20);
+        p1.spawn_at((6, 11), Direction::East, INITIAL_SNAKE_LENGTH);
+        p2.spawn_at((13, 9), Direction::West, INITIAL_SNAKE_LENGTH);
+        let map = GameMap::new(20, 20);
+        let mut rng = GameRng::seeded(RngAlgorithm::Xorshift, 7);
+
+        let food = (p2.head.0, p2.head.1 - 1);
+        p1.food = food;
+        p2.food = food;
+        p1.update_movement(&settings, &[], (0, 0), (20, 20));
+        p2.update_movement(&settings, &[], (0, 0), (20, 20));
+
+        assert!(!p1.food_eaten);
+        assert!(p2.food_eaten);
+        assert_eq!(p2.score, 1);
+        assert_eq!(p1.score, 0);
+
+        map.place_shared_food(&mut [&mut p1, &mut p2], &settings, &mut rng);
+        assert_ne!(p2.food, food);
+        assert_eq!(p1.food, p2.food);
+        assert!(!p1.parts.contains(&p1.food) && !p2.parts.contains(&p2.food));
+        assert!(!p2.food_eaten);
+    }
+
+    #[test]
+    fn test_shared_pellet_reached_by_both_heads_is_eaten_once() {
+        let settings = test_settings(&["--multiplayer"]);
+        let mut p1 = Snake::new(20, 20);
+        let mut p2 = Snake::new(20, 20);
+        p1.spawn_at((6, 11), Direction::East, INITIAL_SNAKE_LENGTH);
+        p2.spawn_at((6, 13), Direction::West, INITIAL_SNAKE_LENGTH);
+        let mut map = GameMap::new(20, 20);
+        let mut rng = GameRng::seeded(RngAlgorithm::Xorshift, 7);
+        p1.food = (6, 12);
+        p2.food = (6, 12);
+
+        map.step_snakes(&mut [&mut p1, &mut p2], [true, true], &settings);
+        assert!(p1.food_eaten && !p2.food_eaten);
+        assert_eq!((p1.points, p2.points), (1, 0));
+        assert_eq!((p1.length, p2.length), (INITIAL_SNAKE_LENGTH + 1, INITIAL_SNAKE_LENGTH));
+        assert_eq!(p2.food, (6, 12));
+
+        let food = map.resolve_food(&mut [&mut p1, &mut p2], &settings, &mut rng);
+        assert_eq!(food.eaten, 1);
+        assert_ne!(p1.food, (6, 12));
+        assert_eq!(p1.food, p2.food);
+    }
+
+    #[test]
+    fn test_steal_takes_a_point_from_the_opponent() {
+        let settings = test_settings(&["--multiplayer", "--steal"]);
+        let mut p1 = Snake::new(20, 20);
+        let mut p2 = Snake::new(20, 20);
+        p1.spawn_at((6, 11), Direction::East, INITIAL_SNAKE_LENGTH);
+        p2.spawn_at((13, 9), Direction::West, INITIAL_SNAKE_LENGTH);
+        let mut map = GameMap::new(20, 20);
+        let mut rng = GameRng::seeded(RngAlgorithm::Xorshift, 7);
+
+        // Player 2 eats twice: up one, then down to nothing
+        p2.points = 1;
+        p2.rescore(&settings);
+        for _ in 0..2 {
+            let food = (p1.head.0, p1.head.1 + 1);
+            p1.food = food;
+            p2.food = food;
+            p1.update_movement(&settings, &[], (0, 0), (20, 20));
+            p2.update_movement(&settings, &[], (0, 0), (20, 20));
+            map.resolve_food(&mut [&mut p1, &mut p2], &settings, &mut rng);
+        }
+        assert_eq!(p1.score, 2);
+        assert_eq!(p2.score, 0);
+
+        // Spare pellets count too
+        p2.points = 3;
+        p2.rescore(&settings);
+        map.extra_food.push((p2.head.0, p2.head.1 - 1));
+        p2.update_movement(&settings, &[], (0, 0), (20, 20));
+        p1.food_eaten = false;
+        map.resolve_food(&mut [&mut p1, &mut p2], &settings, &mut rng);
+        assert_eq!((p1.score, p2.score), (1, 3));
+
+        // Without --steal eating leaves the opponent alone
+        let plain = test_settings(&["--multiplayer"]);
+        let food = (p1.head.0, p1.head.1 + 1);
+        p1.food = food;
+        p2.food = food;
+        p1.update_movement(&plain, &[], (0, 0), (20, 20));
+        map.resolve_food(&mut [&mut p1, &mut p2], &plain, &mut rng);
+        assert_eq!(p2.score, 3);
+
+        // Crossing the opponent's body cuts it rather than killing the crosser
+        p2.spawn_at((10, 5), Direction::East, 4);
+        p1.set_body([(8, 3), (9, 3), (10, 3)]);
+        crate::snake::collide(&mut p1, &mut p2, &settings);
+        assert!(!p1.is_dead && !p2.is_dead);
+        assert_eq!(p2.parts, [(10, 4), (10, 5)]);
+        assert_eq!(p2.length, 2);
+    }
+
+    #[test]
+    fn test_food_min_spread_keeps_respawns_away() {
+        let settings = test_settings(&["--food-min-spread", "8"]);
+        let mut snake = Snake::new(20, 20);
+        let mut map = GameMap::new(20, 20);
+        let mut rng = GameRng::seeded(RngAlgorithm::Xorshift, 5);
+        map.seed_food(&mut [&mut snake], &settings, &mut rng);
+        for _ in 0..50 {
+            let (pr, pc) = snake.food;
+            assert!(map.place_shared_food(&mut [&mut snake], &settings, &mut rng));
+            assert!(snake.food.0.abs_diff(pr) + snake.food.1.abs_diff(pc) >= 8);
+        }
+
+        // Squeezed into a corner with nowhere far enough: any open cell but the old one
+        map.walls = (0..20).flat_map(|r| (0..20).map(move |c| (r, c))).filter(|&(r, c)| r > 1 || c > 1).collect();
+        snake.set_body([(1, 1)]);
+        snake.food = (0, 0);
+        for _ in 0..10 {
+            let previous = snake.food;
+            assert!(map.place_shared_food(&mut [&mut snake], &settings, &mut rng));
+            assert_ne!(snake.food, previous);
+        }
+    }
+
+    #[test]
+    fn test_food_values_score_their_value() {
+        let settings = test_settings(&["--food-values"]);
+        let mut snake = Snake::new(20, 20);
+        snake.spawn_at((5, 5), Direction::East, INITIAL_SNAKE_LENGTH);
+        let mut map = GameMap::new(20, 20);
+        let mut rng = GameRng::seeded(RngAlgorithm::Xorshift, 9);
+        map.seed_food(&mut [&mut snake], &settings, &mut rng);
+
+        // A high-value primary pellet right ahead scores its value, not 1
+        snake.food = (5, 6);
+        snake.food_value = 4;
+        map.render_cells(&[&snake], &settings, 0);
+        assert_eq!(map.grid[5][6].ch, '4');
+        snake.update_movement(&settings, &map.walls, map.border_min, map.border_max);
+        assert!(snake.food_eaten);
+        assert_eq!(snake.points, 4);
+
+        // ...and so does a spare
+        map.extra_food = vec![(5, 7)];
+        map.spare_values.insert((5, 7), 3);
+        snake.update_movement(&settings, &map.walls, map.border_min, map.border_max);
+        assert_eq!(map.check_extra_food_eaten(&mut snake, &settings), [(5, 7)]);
+        assert_eq!(snake.points, 7);
+
+        // Values stay within range and the higher ones come up less often
+        let mut counts = [0usize; FOOD_VALUE_MAX + 1];
+        for _ in 0..3000 {
+            counts[draw_food_value(&settings, &mut rng)] += 1;
+        }
+        assert_eq!(counts[0], 0);
+        assert!(counts[1] > counts[2] && counts[2] > counts[3]);
+        let plain = test_settings(&[]);
+        assert!((0..100).all(|_| draw_food_value(&plain, &mut rng) == 1));
+    }
+
+    #[test]
+    fn test_deterministic_bonus_repeats_for_a_seed() {
+        let settings = test_settings(&["--deterministic-bonus"]);
+        let run = |seed: u64| {
+            let mut snake = Snake::new(20, 20);
+            let mut map = GameMap::new(20, 20);
+            let mut rng = GameRng::seeded(RngAlgorithm::Xorshift, seed);
+            map.seed_food(&mut [&mut snake], &settings, &mut rng);
+            let mut spots = Vec::new();
+            while spots.len() < 2 * BONUS_SEQUENCE_LEN {
+                map.maybe_spawn_bonus(&[&snake], &mut rng);
+                if let Some(bonus) = map.bonus_food.take() {
+                    spots.push(bonus.pos);
+                }
+                // Play in between draws from the same rng
+                map.place_shared_food(&mut [&mut snake], &settings, &mut rng);
+            }
+            spots
+        };
+
+        let first = run(42);
+        assert_eq!(first, run(42));
+        assert_ne!(first, run(43));
+        // The cycle repeats once every spot has been used
+        assert_eq!(first[..BONUS_SEQUENCE_LEN], first[BONUS_SEQUENCE_LEN..]);
+    }
+
+    #[test]
+    fn test_nothing_spawns_on_reserved_cells() {
+        let settings = test_settings(&["--food-count", "3"]);
+        let mut snake = Snake::new(20, 20);
+        snake.spawn_at((10, 2), Direction::East, INITIAL_SNAKE_LENGTH);
+        let mut map = GameMap::new(20, 20);
+        // Everything right of column 3 is reserved, including row 10 ahead
+        map.reserved = (0..20).flat_map(|r| (4..20).map(move |c| (r, c))).collect();
+        // The key, gate, a warp tile and a portal pair take up more of the rest
+        (map.key, map.gate) = (Some((0, 1)), Some((19, 1)));
+        map.warp_tiles = vec![(5, 0)];
+        map.portals = vec![[(15, 3), (3, 3)]];
+        let taken = [(0, 1), (19, 1), (5, 0), (15, 3), (3, 3)];
+        let mut rng = GameRng::seeded(RngAlgorithm::Xorshift, 11);
+        map.seed_food(&mut [&mut snake], &settings, &mut rng);
+        let mut bonuses = 0;
+        for _ in 0..200 {
+            map.place_walls(5, &[&snake], &settings, &mut rng);
+            assert!(map.place_shared_food(&mut [&mut snake], &settings, &mut rng));
+            map.extra_food.clear();
+            map.top_up_food(&[&snake], settings.food_count, &settings, &mut rng);
+            map.bonus_food = None;
+            map.maybe_spawn_bonus(&[&snake], &mut rng);
+            bonuses += map.bonus_food.is_some() as usize;
+            let spares = map.extra_food.iter().chain(map.bonus_food.iter().map(|b| &b.pos));
+            assert!(spares.clone().all(|pos| !taken.contains(pos)));
+            let spawned = map.walls.iter().chain(spares);
+            assert!(spawned.chain([&snake.food]).all(|pos| !map.reserved.contains(pos)));
+        }
+        assert!(bonuses > 0);
+
+        // The snake itself goes straight through
+        map.walls.clear();
+        snake.food = (0, 0);
+        for _ in 0..10 {
+            snake.update_movement(&settings, &map.walls, map.border_min, map.border_max);
+            assert!(!snake.is_dead);
+        }
+        assert!(map.reserved.contains(&snake.head));
+    }
+
+    #[test]
+    fn test_start_food_drains_to_food_count() {
+        let settings = test_settings(&["--food-count", "2", "--start-food", "5"]);
+        let mut snake = Snake::new(20, 20);
+        let mut map = GameMap::new(20, 20);
+        let mut rng = GameRng::seeded(RngAlgorithm::Xorshift, 3);
+        map.place_shared_food(&mut [&mut snake], &settings, &mut rng);
+        map.top_up_food(&[&snake], settings.initial_food(), &settings, &mut rng);
+        assert_eq!(1 + map.extra_food.len(), 5);
+
+        // Alternate eating spares and the primary; nothing respawns until
+        // the board is back down to the steady-state count
+        let mut totals = Vec::new();
+        for i in 0..6 {
+            if i % 2 == 0 && !map.extra_food.is_empty() {
+                snake.head = map.extra_food[0];
+                assert_eq!(map.check_extra_food_eaten(&mut snake, &settings), [snake.head]);
+            } else {
+                snake.food_eaten = true;
+                map.replace_shared_food(&mut [&mut snake], &settings, &mut rng);
+            }
+            map.top_up_food(&[&snake], settings.food_count, &settings, &mut rng);
+            totals.push(1 + map.extra_food.len());
+        }
+        assert_eq!(totals, vec![4, 3, 2, 2, 2, 2]);
+        assert!(!map.extra_food.contains(&snake.food));
+    }
+
+    #[test]
+    fn test_auto_food_scales_with_board_area() {
+        let settings = test_settings(&["--auto-food", "40", "--shrinking-border"]);
+        let mut rng = GameRng::seeded(RngAlgorithm::Xorshift, 5);
+        let target = |w: usize, h: usize, rng: &mut GameRng| {
+            let mut snake = Snake::new(w, h);
+            let mut map = GameMap::new(w, h);
+            map.seed_food(&mut [&mut snake], &settings, rng);
+            assert_eq!(1 + map.extra_food.len(), map.food_target(&settings));
+            map.food_target(&settings)
+        };
+        assert_eq!(target(20, 20, &mut rng), 10);
+        assert_eq!(target(40, 40, &mut rng), 40);
+        assert_eq!(target(10, 8, &mut rng), 2);
+        assert_eq!(target(5, 5, &mut rng), 1);
+
+        // One side of the border moves in and the target follows
+        let snake = Snake::new(20, 20);
+        let mut map = GameMap::new(20, 20);
+        for _ in 0..50 {
+            map.update_shrinking_border(&snake);
+        }
+        assert_eq!(map.border_max.1, 19);
+        assert_eq!(map.food_target(&settings), 9);
+
+        // Off, it's just --food-count
+        assert_eq!(map.food_target(&test_settings(&["--food-count", "3"])), 3);
+    }
+
+    #[test]
+    fn test_food_walls_wall_off_eaten_cell() {
+        let settings = test_settings(&["--food-walls"]);
+        let mut snake = Snake::new(20, 20);
+        let mut map = GameMap::new(20, 20);
+        let mut rng = GameRng::seeded(RngAlgorithm::Xorshift, 5);
+        let eaten_at = (snake.head.0, snake.head.1 + 1);
+        snake.food = eaten_at;
+        snake.update_movement(&settings, &map.walls.clone(), (0, 0), (20, 20));
+        assert!(snake.food_eaten);
+
+        let tick = map.resolve_food(&mut [&mut snake], &settings, &mut rng);
+        assert_eq!(tick.eaten, 1);
+        assert!(!tick.board_full);
+        assert_eq!(map.walls, vec![eaten_at]);
+        assert!(!map.walls.contains(&snake.food));
+
+        // A 5x1 strip: snake on columns 1-3, a wall at 0, the last pellet at 4
+        let mut snake = Snake::new(5, 1);
+        let mut map = GameMap::new(5, 1);
+        map.walls.push((0, 0));
+        snake.food = (0, 4);
+        snake.update_movement(&settings, &map.walls.clone(), (0, 0), (1, 5));
+        assert!(snake.food_eaten);
+        assert!(map.resolve_food(&mut [&mut snake], &settings, &mut rng).board_full);
+    }
+
+    #[test]
+    fn test_restart_clears_food_walls() {
+        let settings = test_settings(&["--food-walls", "--shape", "diamond"]);
+        let mut snake = Snake::new(20, 20);
+        let mut map = GameMap::new(20, 20);
+        map.apply_shape(settings.shape);
+        let outline = map.walls.clone();
+        let mut rng = GameRng::seeded(RngAlgorithm::Xorshift, 5);
+        snake.food = (snake.head.0, snake.head.1 + 1);
+        snake.update_movement(&settings, &map.walls.clone(), (0, 0), (20, 20));
+        map.resolve_food(&mut [&mut snake], &settings, &mut rng);
+        assert_eq!(map.walls.len(), outline.len() + 1);
+
+        map.reset_board(&settings);
+        assert_eq!(map.walls, outline);
+    }
+
+    #[test]
+    fn test_snake_filling_board_wins() {
+        let settings = test_settings(&[]);
+        let mut rng = GameRng::seeded(RngAlgorithm::Xorshift, 11);
+        // 4x1 strip: the snake covers columns 1-3 heading West, food at 0
+        let mut snake = Snake::new(4, 1);
+        snake.spawn_at((0, 1), Direction::West, INITIAL_SNAKE_LENGTH);
+        let mut map = GameMap::new(4, 1);
+        assert!(map.place_shared_food(&mut [&mut snake], &settings, &mut rng));
+        assert_eq!(snake.food, (0, 0));
+
+        snake.update_movement(&settings, &[], (0, 0), (1, 4));
+        assert!(snake.food_eaten && !snake.is_dead);
+        assert_eq!(snake.parts.len(), 4);
+        let tick = map.resolve_food(&mut [&mut snake], &settings, &mut rng);
+        assert_eq!(tick.eaten, 1);
+        assert!(tick.board_full);
+        assert!(!map.place_shared_food(&mut [&mut snake], &settings, &mut rng));
+    }
+
+    #[test]
+    fn test_interpolation_glyph() {
+        assert_eq!(interpolation_glyph(Direction::East, 0.0), None);
+        assert_eq!(interpolation_glyph(Direction::East, 0.5), Some('▌'));
+        assert_eq!(interpolation_glyph(Direction::North, 0.5), Some('▄'));
+        assert_eq!(interpolation_glyph(Direction::West, 1.0), Some('█'));
+    }
+
+    #[test]
+    fn test_board_padding() {
+        // 20x20 board renders 40 columns by 22 rows
+        assert_eq!(board_padding((100, 40), (40, 22)), (30, 9));
+        assert_eq!(board_padding((41, 23), (40, 22)), (0, 0));
+        // Larger than the terminal: no padding rather than underflow
+        assert_eq!(board_padding((30, 10), (40, 22)), (0, 0));
+    }
+
+    #[test]
+    fn test_health_color() {
+        assert_eq!(health_color(1.0), Color::Rgb { r: 0, g: 255, b: 0 });
+        assert_eq!(health_color(0.5), Color::Rgb { r: 255, g: 255, b: 0 });
+        assert_eq!(health_color(0.1), Color::Rgb { r: 255, g: 51, b: 0 });
+        assert_eq!(health_color(0.0), Color::Rgb { r: 255, g: 0, b: 0 });
+
+        let mut snake = Snake::new(20, 20);
+        snake.spawn_at((5, 5), Direction::East, 6);
+        assert_eq!(snake.health(0), None);
+        snake.ticks_since_food = 5;
+        assert_eq!(snake.health(10), Some(0.5));
+
+        // Only drawn when both flags are on
+        let mut map = GameMap::new(20, 20);
+        map.render_cells(&[&snake], &test_settings(&["--hungry", "10", "--health-color"]), 0);
+        assert_eq!(map.cells()[5][4].color, health_color(0.5));
+        map.render_cells(&[&snake], &test_settings(&["--health-color"]), 0);
+        assert_eq!(map.cells()[5][4].color, Color::Green);
+    }
+
+    #[test]
+    fn test_dim_color() {
+        assert_eq!(dim_color(Color::Red), Color::DarkRed);
+        assert_eq!(dim_color(Color::Green), Color::DarkGreen);
+        assert_eq!(dim_color(Color::Yellow), Color::DarkYellow);
+        assert_eq!(dim_color(Color::Cyan), Color::DarkCyan);
+        assert_eq!(dim_color(Color::White), Color::Grey);
+        assert_eq!(dim_color(Color::DarkRed), Color::DarkGrey);
+        assert_eq!(dim_color(Color::Rgb { r: 200, g: 100, b: 50 }), Color::Rgb { r: 100, g: 50, b: 25 });
+    }
+
+    #[test]
+    fn test_wall_theme_glyph_rendered() {
+        let mut snake = Snake::new(20, 20);
+        snake.food = (15, 15);
+        let mut map = GameMap::new(20, 20);
+        map.walls.push((2, 3));
+        map.border_min = (1, 0); // row 0 is shrunk-out border
+
+        let themed = test_settings(&["--no-color", "--wall-theme", "brick"]);
+        let frame = map.render(&[&snake], &themed, false, 0, 0.0);
+        let rows: Vec<&str> = frame.lines().collect();
+        assert!(rows[1].starts_with(", , ,"), "border row: {}", rows[1]);
+        assert_eq!(rows[3].chars().nth(6), Some('▓'), "obstacle row: {}", rows[3]);
+        assert!(!frame.contains('#'));
+
+        let custom = test_settings(&["--no-color", "--wall-theme", "brick", "--wall-char", "X"]);
+        let frame = map.render(&[&snake], &custom, false, 0, 0.0);
+        assert!(frame.contains('X') && !frame.contains('▓'));
+    }
+
+    #[test]
+    fn test_border_region_drawn_apart_from_walls() {
+        let mut snake = Snake::new(20, 20);
+        snake.food = (15, 15);
+        let mut map = GameMap::new(20, 20);
+        map.walls.push((5, 5));
+        map.border_min = (2, 2);
+        map.border_max = (18, 18);
+
+        let settings = test_settings(&[]);
+        map.render_cells(&[&snake], &settings, 0);
+        let (wall, border) = (map.cells()[5][5].clone(), map.cells()[0][0].clone());
+        assert_eq!((wall.ch, border.ch), (WALL_CHAR, BORDER_CHAR));
+        assert_ne!(wall.color, border.color);
+        // The far side of the border too
+        assert_eq!(map.cells()[19][19].ch, BORDER_CHAR);
+
+        let custom = test_settings(&["--border-char", "~"]);
+        map.render_cells(&[&snake], &custom, 0);
+        assert_eq!((map.cells()[5][5].ch, map.cells()[1][10].ch), (WALL_CHAR, '~'));
+        assert!(test_settings(&["--border-char", "墙"]).validate().is_err());
+    }
+
+    #[test]
+    fn test_edge_gaps_drawn_apart_from_art_target() {
+        let mut snake = Snake::new(20, 20);
+        snake.food = (15, 15);
+        let mut map = GameMap::new(20, 20);
+        let mut settings = test_settings(&["--edge-gaps", "1"]);
+        settings.art = Some(crate::art::ArtTarget { cells: vec![(5, 5)] });
+        map.render_cells(&[&snake], &settings, 0);
+
+        let gap = map.edge_gap_cells(1)[0];
+        assert_eq!(map.cells()[gap.0][gap.1].ch, EDGE_GAP_CHAR);
+        assert_eq!(map.cells()[5][5].ch, ART_TARGET_CHAR);
+        assert_ne!(EDGE_GAP_CHAR, ART_TARGET_CHAR);
+    }
+
+    #[test]
+    fn test_wall_char_width_validation() {
+        assert!(test_settings(&["--wall-char", "%"]).validate().is_ok());
+        assert!(test_settings(&["--wall-char", "█"]).validate().is_ok());
+        assert!(test_settings(&["--wall-char", "墙"]).validate().is_err());
+        assert!(test_settings(&["--wall-char", "🧱"]).validate().is_err());
+    }
+
+    #[test]
+    fn test_monochrome_matches_stripped_color_output() {
+        let color = test_settings(&[]);
+        let mono = test_settings(&["--no-color"]);
+        let snake = Snake::new(20, 20);
+        let mut map = GameMap::new(20, 20);
+        map.walls.push((2, 3));
+        map.bonus_food = Some(BonusFood { pos: (5, 5), lifetime: 10 });
+
+        let colored = map.render(&[&snake], &color, true, 7, 0.0);
+        let plain = map.render(&[&snake], &mono, true, 7, 0.0);
+
+        assert!(!plain.contains('\x1b'));
+        assert!(plain.len() < colored.len());
+        assert_eq!(plain, strip_ansi(&colored));
+    }
+
+    #[test]
+    fn test_metronome_toggles_once_per_tick() {
+        let settings = test_settings(&["--metronome", "--smooth-move"]);
+        let mut map = GameMap::new(20, 20);
+        let mut snake = Snake::new(20, 20);
+        snake.spawn_at((5, 7), Direction::East, INITIAL_SNAKE_LENGTH);
+
+        let beat_at = |map: &mut GameMap, tick: usize, fraction: f32| {
+            let frame = strip_ansi(&map.render(&[&snake], &settings, false, tick, fraction));
+            frame.chars().find(|&c| c == '●' || c == '○').unwrap()
+        };
+        let mut last = beat_at(&mut map, 0, 0.0);
+        for tick in 1..8 {
+            // Sub-tick redraws keep the previous tick's beat
+            assert_eq!(beat_at(&mut map, tick - 1, 0.5), last);
+            let beat = beat_at(&mut map, tick, 0.0);
+            assert_ne!(beat, last);
+            last = beat;
+        }
+
+        let plain = test_settings(&[]);
+        let frame = strip_ansi(&map.render(&[&snake], &plain, false, 1, 0.0));
+        assert!(!frame.contains('●') && !frame.contains('○'));
+
+        // --reduced-motion holds the dot steady
+        let steady = test_settings(&["--metronome", "--reduced-motion"]);
+        let beats: Vec<String> = (0..4).map(|tick| strip_ansi(&map.render(&[&snake], &steady, false, tick, 0.0))).collect();
+        assert!(beats.iter().all(|frame| frame.contains('●') && !frame.contains('○')));
+    }
+
+    #[test]
+    fn test_hud_separator_matches_board_width() {
+        let settings = test_settings(&["--hud-borders", "--no-color"]);
+        let mut map = GameMap::new(12, 8);
+        let mut snake = Snake::new(12, 8);
+        snake.spawn_at((4, 4), Direction::East, INITIAL_SNAKE_LENGTH);
+
+        let frame = map.render(&[&snake], &settings, false, 0, 0.0);
+        let lines: Vec<&str> = frame.split("\r\n").collect();
+        let board_width = lines[2].chars().count();
+        assert_eq!(board_width, 12 * 2);
+        let separators: Vec<&&str> = lines.iter().filter(|l| l.starts_with('─')).collect();
+        assert_eq!(separators.len(), 2);
+        assert!(separators.iter().all(|l| l.chars().count() == board_width && l.chars().all(|c| c == '─')));
+        // Score line, rule, 8 board rows, rule
+        assert!(lines[1].starts_with('─') && lines[10].starts_with('─'));
+
+        let plain = map.render(&[&snake], &test_settings(&["--no-color"]), false, 0, 0.0);
+        assert!(!plain.contains('─'));
+    }
+
+    #[test]
+    fn test_circle_shape_walls_off_the_corners() {
+        let settings = test_settings(&["--shape", "circle", "--obstacles", "5"]);
+        let mut map = GameMap::new(20, 20);
+        map.apply_shape(settings.shape);
+        for corner in [(0, 0), (0, 19), (19, 0), (19, 19)] {
+            assert!(!BoardShape::Circle.contains(corner, 20, 20));
+            assert!(map.walls.contains(&corner));
+        }
+        assert!(BoardShape::Circle.contains((10, 10), 20, 20));
+        assert!(!map.walls.contains(&(10, 10)));
+        // Edge midpoints touch the rim
+        assert!(BoardShape::Circle.contains((0, 10), 20, 20) && BoardShape::Circle.contains((10, 19), 20, 20));
+
+        // Obstacles and bombs never open the shape up
+        let mut snake = Snake::new(20, 20);
+        let mut rng = GameRng::seeded(RngAlgorithm::Xorshift, 3);
+        map.place_walls(settings.obstacles, &[&snake], &settings, &mut rng);
+        assert_eq!(map.walls.len(), map.outside.len() + settings.obstacles);
+        snake.set_body([(1, 1)]);
+        snake.bomb_charges = 1;
+        map.detonate(&mut snake, 3);
+        assert!(map.outside.iter().all(|pos| map.walls.contains(pos)));
+
+        // And they render as border
+        snake.food = (10, 10);
+        map.render_cells(&[&snake], &settings, 0);
+        assert_eq!(map.cells()[0][0].ch, BORDER_CHAR);
+
+        // The spawn has to fit inside
+        assert!(Settings::parse_from(["test", "--shape", "diamond", "--map-width", "20", "--map-height", "20"]).resolve().validate().is_ok());
+        let cornered = Settings::parse_from(["test", "--shape", "diamond", "--map-width", "20", "--map-height", "20", "--p1-spawn", "top-left"]);
+        assert!(cornered.resolve().validate().unwrap_err().contains("--shape"));
+    }
+
+    #[test]
+    fn test_bomb_clears_walls_within_radius() {
+        let settings = test_settings(&["--bomb-radius", "2"]);
+        let mut map = GameMap::new(20, 20);
+        let mut snake = Snake::new(20, 20);
+        snake.spawn_at((10, 10), Direction::East, INITIAL_SNAKE_LENGTH);
+        let near = [(8, 8), (12, 12), (10, 12), (9, 11)];
+        let far = vec![(7, 10), (10, 13), (13, 13), (0, 0)];
+        map.walls = near.iter().chain(&far).copied().collect();
+
+        // No charges: nothing happens
+        assert_eq!(map.detonate(&mut snake, settings.bomb_radius), 0);
+        assert_eq!(map.walls.len(), near.len() + far.len());
+
+        map.bomb = Some(snake.head);
+        assert!(map.check_bomb_collected(&mut snake));
+        assert_eq!((snake.bomb_charges, map.bomb), (1, None));
+        assert!(map.render(&[&snake], &settings, false, 0, 0.0).contains("BOMBS: 1"));
+
+        assert_eq!(map.detonate(&mut snake, settings.bomb_radius), near.len());
+        assert_eq!(map.walls, far);
+        assert_eq!(snake.bomb_charges, 0);
+        assert_eq!(map.detonate(&mut snake, settings.bomb_radius), 0);
+        assert_eq!(map.walls, far);
+    }
+
+    #[test]
+    fn test_portal_pairs_share_a_color() {
+        let settings = test_settings(&[]);
+        let mut map = GameMap::new(20, 20);
+        map.portals = vec![[(2, 2), (15, 15)], [(5, 10), (12, 3)]];
+        let mut snake = Snake::new(20, 20);
+        snake.spawn_at((10, 10), Direction::East, INITIAL_SNAKE_LENGTH);
+        map.render_cells(&[&snake], &settings, 0);
+
+        let color = |(r, c): (usize, usize)| map.cells()[r][c].color;
+        assert_eq!(map.cells()[2][2].ch, PORTAL_CHAR);
+        assert_eq!(color((2, 2)), color((15, 15)));
+        assert_eq!(color((5, 10)), color((12, 3)));
+        assert_ne!(color((2, 2)), color((5, 10)));
+        assert_eq!(portal_color(0), portal_color(PORTAL_COLORS.len()));
+
+        // Stepping into one end comes out of the other, still heading the same way
+        snake.spawn_at((5, 8), Direction::East, INITIAL_SNAKE_LENGTH);
+        snake.update_movement(&settings, &map.walls, (0, 0), (20, 20));
+        assert!(!map.take_portal(&mut snake));
+        snake.update_movement(&settings, &map.walls, (0, 0), (20, 20));
+        assert!(map.take_portal(&mut snake));
+        assert_eq!((snake.head, snake.direction), ((12, 3), Direction::East));
+        snake.update_movement(&settings, &map.walls, (0, 0), (20, 20));
+        assert_eq!(snake.head, (12, 4));
+        assert!(!snake.is_dead);
+    }
+
+    #[test]
+    fn test_second_head_eats_spares_and_uses_portals_and_gate() {
+        let settings = test_settings(&["--two-headed", "mirrored"]);
+        let mut rng = GameRng::seeded(RngAlgorithm::Xorshift, 5);
+        let mut map = GameMap::new(20, 20);
+        let mut snake = Snake::new(20, 20);
+        snake.spawn_at((10, 8), Direction::East, INITIAL_SNAKE_LENGTH);
+        assert!(snake.add_twin(TwinMode::Mirrored));
+        snake.food = (0, 0);
+
+        // The second head (heading west from (12, 11)) lands on a spare
+        map.extra_food = vec![(12, 10)];
+        map.step_snakes(&mut [&mut snake], [true, false], &settings);
+        let food = map.resolve_food(&mut [&mut snake], &settings, &mut rng);
+        assert_eq!(food.eaten, 1);
+        assert!(!map.extra_food.contains(&(12, 10)));
+        assert_eq!((snake.points, snake.length), (1, 4));
+
+        // ...then goes through a portal on its own
+        map.portals = vec![[(12, 9), (3, 3)]];
+        map.step_snakes(&mut [&mut snake], [true, false], &settings);
+        assert_eq!((snake.head, snake.twin_head()), ((10, 10), Some((3, 3))));
+        assert!(!snake.is_dead);
+
+        // The state hash sees the second strand
+        let hash = crate::state::GameState::new(&[&snake], &map).state_hash();
+        let twin = snake.twin.take();
+        assert_ne!(crate::state::GameState::new(&[&snake], &map).state_hash(), hash);
+        snake.twin = twin;
+
+        // ...and dies at a locked gate without the key
+        map.gate = Some((3, 2));
+        map.step_snakes(&mut [&mut snake], [true, false], &settings);
+        assert!(snake.is_dead);
+    }
+
+    #[test]
+    fn test_gate_needs_the_key() {
+        let settings = test_settings(&[]);
+        let mut map = GameMap::new(20, 20);
+        map.gate = Some((5, 12));
+        let mut snake = Snake::new(20, 20);
+
+        // Without the key the gate is a wall
+        snake.spawn_at((5, 10), Direction::East, INITIAL_SNAKE_LENGTH);
+        for _ in 0..2 {
+            snake.update_movement(&settings, &map.walls, (0, 0), (20, 20));
+            map.cross_gate(&mut snake);
+        }
+        assert!(snake.is_dead);
+
+        // Picking up the key lets it through once
+        map.key = Some((5, 11));
+        snake.is_dead = false;
+        snake.spawn_at((5, 10), Direction::East, INITIAL_SNAKE_LENGTH);
+        snake.update_movement(&settings, &map.walls, (0, 0), (20, 20));
+        assert!(map.collect_key(&mut snake));
+        assert!(snake.has_key && map.key.is_none());
+        map.render(&[&snake], &settings, false, 0, 0.0);
+        assert_eq!(map.cells()[5][12].ch, GATE_CHAR);
+        snake.update_movement(&settings, &map.walls, (0, 0), (20, 20));
+        map.cross_gate(&mut snake);
+        assert!(!snake.is_dead && !snake.has_key);
+        assert_eq!(map.gate, Some((5, 12)));
+
+        // The key is spent, so coming back around is fatal
+        snake.spawn_at((5, 10), Direction::East, INITIAL_SNAKE_LENGTH);
+        snake.update_movement(&settings, &map.walls, (0, 0), (20, 20));
+        snake.update_movement(&settings, &map.walls, (0, 0), (20, 20));
+        map.cross_gate(&mut snake);
+        assert!(snake.is_dead);
+    }
+
+    #[test]
+    fn test_fog_hides_distant_cells() {
+        let settings = test_settings(&["--fog", "3"]);
+        let mut map = GameMap::new(20, 20);
+        map.walls = vec![(5, 12), (5, 18)];
+        let mut snake = Snake::new(20, 20);
+        snake.spawn_at((5, 10), Direction::East, INITIAL_SNAKE_LENGTH);
+        snake.food = (7, 11);
+        map.extra_food = vec![(15, 15)];
+        map.render(&[&snake], &settings, false, 0, 0.0);
+        let cell = |r: usize, c: usize| (map.cells()[r][c].ch, map.cells()[r][c].color);
+        let wall = Cell::wall(&settings);
+
+        // Near the head: full color, food shown
+        assert_eq!(cell(5, 12), (wall.ch, wall.color));
+        assert_eq!(cell(7, 11), (settings.food, Color::Red));
+        // Far away: the wall is dimmed and the food isn't drawn
+        assert_eq!(cell(5, 18), (wall.ch, dim_color(wall.color)));
+        assert_eq!(cell(15, 15).0, MAP_CHAR);
+
+        // Without fog the far pellet is there
+        map.render(&[&snake], &test_settings(&[]), false, 0, 0.0);
+        assert_eq!(map.cells()[15][15].ch, settings.food);
+    }
+
+    #[test]
+    fn test_compose_frame_single_write() {
+        const HOME: &str = "\x1b[1;1H";
+        const CLEAR: &str = "\x1b[2J";
+        let settings = test_settings(&[]);
+        let mut map = GameMap::new(20, 20);
+        let snake = Snake::new(20, 20);
+        let frame = map.render(&[&snake], &settings, false, 0, 0.0);
+
+        let composed = compose_frame(&frame, true);
+        assert!(composed.starts_with(HOME));
+        assert_eq!(composed.matches(CLEAR).count(), 1);
+        assert!(composed.ends_with(&frame));
+
+        // In-between redraws go home without wiping the screen
+        let redraw = compose_frame(&frame, false);
+        assert!(redraw.starts_with(HOME));
+        assert!(!redraw.contains(CLEAR));
+    }
+
+    #[test]
+    fn test_coins_drop_behind_and_collect() {
+        let settings = test_settings(&["--coins", "--disable-borders"]);
+        let mut map = GameMap::new(20, 20);
+        let mut snake = Snake::new(20, 20);
+        snake.spawn_at((10, 5), Direction::East, INITIAL_SNAKE_LENGTH);
+        snake.food = (0, 0);
+        let mut rng = GameRng::seeded(RngAlgorithm::Xorshift, 7);
+
+        // Circling one row: the tail drops coins and the head comes back round for them
+        let (mut vacated, mut dropped, mut collected) = (Vec::new(), 0, 0);
+        for _ in 0..60 {
+            let ahead = Snake::step(snake.head, snake.direction, &settings, (0, 0), (20, 20)).unwrap();
+            let waiting = map.coins.iter().find(|coin| coin.pos == ahead).map_or(0, |coin| coin.value);
+            snake.update_movement(&settings, &map.walls, (0, 0), (20, 20));
+            let (points, coins) = (snake.points, snake.coin_points);
+            assert_eq!(map.collect_coins(&mut snake, &settings), waiting);
+            // Coins score on their own, outside the food weight
+            assert_eq!((snake.points, snake.coin_points), (points, coins + waiting));
+            collected += waiting;
+
+            map.tick_coins();
+            vacated.extend(snake.vacated);
+            let before = map.coins.len();
+            map.drop_coins(&[&snake], &settings, &mut rng);
+            dropped += map.coins.len() - before;
+            assert!(map.coins.iter().all(|coin| vacated.contains(&coin.pos)));
+            assert!(map.coins.iter().all(|coin| (1..=COIN_MAX_VALUE).contains(&coin.value)));
+        }
+        assert!(dropped > 0 && dropped < vacated.len(), "{dropped} coins from {} cells", vacated.len());
+        assert!(collected > 0);
+        // No food eaten, so the score is all coins, whatever the food weight
+        assert_eq!(snake.score, collected);
+        snake.rescore(&test_settings(&["--score-by-length"]));
+        assert_eq!(snake.score, collected);
+
+        // Coins run out of time, and never drop without --coins
+        for _ in 0..COIN_LIFETIME {
+            map.tick_coins();
+        }
+        assert!(map.coins.is_empty());
+        snake.vacated = Some((0, 5));
+        map.drop_coins(&[&snake], &test_settings(&[]), &mut rng);
+        assert!(map.coins.is_empty());
+    }
+
+    #[test]
+    fn test_score_by_length_tracks_growth() {
+        let settings = test_settings(&["--score-by-length", "--hungry", "3", "--disable-borders"]);
+        let mut map = GameMap::new(20, 20);
+        let mut snake = Snake::new(20, 20);
+        snake.spawn_at((5, 5), Direction::East, INITIAL_SNAKE_LENGTH);
+        let growth = |snake: &Snake| snake.length - INITIAL_SNAKE_LENGTH;
+        let ahead = |snake: &Snake| Snake::step(snake.head, snake.direction, &settings, (0, 0), (20, 20)).unwrap();
+
+        snake.food = ahead(&snake);
+        snake.update_movement(&settings, &[], (0, 0), (20, 20));
+        assert_eq!((snake.score, growth(&snake)), (1, 1));
+
+        // Bonus food is worth 3 food points but only one segment
+        map.bonus_food = Some(BonusFood { pos: ahead(&snake), lifetime: 10 });
+        snake.update_movement(&settings, &[], (0, 0), (20, 20));
+        assert!(map.check_bonus_eaten(&mut snake, &settings));
+        assert_eq!(snake.points, 1 + BONUS_FOOD_SCORE);
+        assert_eq!((snake.score, growth(&snake)), (2, 2));
+
+        // Starving takes the point back with the segment
+        snake.food = (0, 0);
+        for _ in 0..3 {
+            snake.update_movement(&settings, &[], (0, 0), (20, 20));
+        }
+        assert_eq!((snake.score, growth(&snake)), (1, 1));
+    }
+
+    #[test]
+    fn test_cell_spacing_row_width() {
+        for spacing in 0..=2 {
+            let spacing_arg = spacing.to_string();
+            let settings = test_settings(&["--hud-borders", "--no-color", "--cell-spacing", &spacing_arg]);
+            let mut map = GameMap::new(12, 8);
+            let mut snake = Snake::new(12, 8);
+            snake.spawn_at((4, 4), Direction::East, INITIAL_SNAKE_LENGTH);
+
+            let frame = map.render(&[&snake], &settings, false, 0, 0.0);
+            let lines: Vec<&str> = frame.split("\r\n").collect();
+            let expected = 12 * (1 + spacing);
+            assert!(lines[2..10].iter().all(|l| l.chars().count() == expected), "spacing {spacing}");
+            assert_eq!(lines[1].chars().count(), expected);
+            // The score stays centered over the board
+            let pad = lines[0].len() - lines[0].trim_start().len();
+            assert_eq!(pad, (expected - "Score: 0".len()) / 2);
+        }
+        assert!(Settings::try_parse_from(["test", "--cell-spacing", "3"]).is_err());
+    }
+
+    #[test]
+    fn test_mirror_food_sequences_match() {
+        let settings = test_settings(&["--multiplayer", "--mirror-food", "--seed", "7"]);
+        let mut map = GameMap::new(20, 20);
+        map.mirror_food = Some(MirrorFood::new(RngAlgorithm::Xorshift, 7));
+        let mut p1 = Snake::new(20, 20);
+        let mut p2 = Snake::new(20, 20);
+        for (player, snake) in [&mut p1, &mut p2].into_iter().enumerate() {
+            let (head, dir) = settings.spawn(player, 20, 20);
+            snake.spawn_at(head, dir, INITIAL_SNAKE_LENGTH);
+        }
+        assert!(map.seed_primary_food(&mut [&mut p1, &mut p2], &settings, &mut GameRng::seeded(RngAlgorithm::Xorshift, 1)));
+
+        let mut rng = GameRng::seeded(RngAlgorithm::Xorshift, 1);
+        let (mut seq1, mut seq2) = (vec![p1.food], vec![p2.food]);
+        for round in 0..8 {
+            // Players eat at different times; each only advances their own stream
+            p1.food_eaten = true;
+            p2.food_eaten = round % 2 == 0;
+            map.resolve_food(&mut [&mut p1, &mut p2], &settings, &mut rng);
+            seq1.push(p1.food);
+            if round % 2 == 0 {
+                seq2.push(p2.food);
+            }
+        }
+        assert!(seq1.iter().all(|&(r, _)| r < 10));
+        let mirrored: Vec<_> = seq1.iter().map(|&pos| map.mirror(pos)).collect();
+        assert_eq!(seq2, mirrored[..seq2.len()]);
+        assert!(seq2.len() > 3);
+
+        // One full half doesn't end the round; that player waits for room
+        let snakes: Vec<_> = p1.parts.iter().chain(&p2.parts).copied().collect();
+        let fill = |map: &mut GameMap, rows: std::ops::Range<usize>| {
+            map.walls.extend(rows.flat_map(|r| (0..20).map(move |c| (r, c))).filter(|pos| !snakes.contains(pos)));
+        };
+        fill(&mut map, 0..10);
+        p1.food_eaten = true;
+        p2.food_eaten = false;
+        assert!(!map.resolve_food(&mut [&mut p1, &mut p2], &settings, &mut rng).board_full);
+        // ...until the other half fills too
+        fill(&mut map, 10..20);
+        p1.food_eaten = false;
+        p2.food_eaten = true;
+        assert!(map.resolve_food(&mut [&mut p1, &mut p2], &settings, &mut rng).board_full);
+
+        // Room opening up again gives the waiting player a pellet
+        map.walls.retain(|&pos| pos != (2, 2));
+        p2.food_eaten = false;
+        assert!(!map.resolve_food(&mut [&mut p1, &mut p2], &settings, &mut rng).board_full);
+        assert_eq!(p1.food, (2, 2));
+    }
+
+    #[test]
+    fn test_reduced_motion_holds_colors_steady() {
+        let mut snake = Snake::new(20, 20);
+        snake.spawn_at((10, 10), Direction::East, INITIAL_SNAKE_LENGTH);
+        let colors = |args: &[&str]| {
+            let settings = test_settings(args);
+            let mut map = GameMap::new(20, 20);
+            map.bonus_food = Some(BonusFood { pos: (3, 3), lifetime: BONUS_FOOD_LIFETIME });
+            let mut bonus = Vec::new();
+            let mut death = Vec::new();
+            for frame in 0..12 {
+                map.render_cells(&[&snake], &settings, frame);
+                bonus.push(map.cells()[3][3].color);
+                map.render_death_animation(&[&snake], &settings, frame);
+                death.push(map.cells()[10][10].color);
+            }
+            (bonus, death)
+        };
+
+        let (bonus, death) = colors(&[]);
+        assert!(bonus.iter().any(|&c| c != bonus[0]), "bonus food blinks by default");
+        assert!(death.iter().any(|&c| c != death[0]), "death flashes by default");
+
+        let (bonus, death) = colors(&["--reduced-motion"]);
+        assert!(bonus.iter().all(|&c| c == Color::Magenta));
+        assert!(death.iter().all(|&c| c == Color::Red));
+
+        // A near miss still says so, without turning the line magenta
+        snake.near_miss_flash = 2;
+        let magenta = "x".with(Color::Magenta).to_string();
+        let magenta = magenta.split('x').next().unwrap();
+        let score_line = |args: &[&str]| {
+            let frame = GameMap::new(20, 20).render(&[&snake], &test_settings(args), false, 0, 0.0);
+            frame.lines().find(|line| line.contains("NEAR MISS!")).unwrap().to_string()
+        };
+        assert!(score_line(&[]).contains(magenta));
+        assert!(!score_line(&["--reduced-motion"]).contains(magenta));
+    }
+
+    #[test]
+    fn test_growing_board_expands_at_length_milestones() {
+        let settings = test_settings(&["--growing-board"]);
+        let mut map = GameMap::new(40, 20);
+        map.start_growing_board();
+        assert_eq!((map.border_min, map.border_max), ((5, 10), (15, 30)));
+
+        let mut snake = Snake::new(40, 20);
+        snake.spawn_at((10, 20), Direction::East, INITIAL_SNAKE_LENGTH);
+        map.seed_food(&mut [&mut snake], &settings, &mut GameRng::seeded(RngAlgorithm::Xorshift, 4));
+        let (f_r, f_c) = snake.food;
+        assert!((5..15).contains(&f_r) && (10..30).contains(&f_c), "food starts inside");
+
+        // One short of the milestone: nothing moves
+        snake.length = INITIAL_SNAKE_LENGTH + GROWING_BOARD_STEP - 1;
+        assert!(!map.update_growing_board(&[&snake]));
+        assert_eq!((map.border_min, map.border_max), ((5, 10), (15, 30)));
+
+        // Each milestone opens one more cell on every side
+        snake.length = INITIAL_SNAKE_LENGTH + GROWING_BOARD_STEP;
+        assert!(map.update_growing_board(&[&snake]));
+        assert_eq!((map.border_min, map.border_max), ((4, 9), (16, 31)));
+        snake.length = INITIAL_SNAKE_LENGTH + 3 * GROWING_BOARD_STEP;
+        map.update_growing_board(&[&snake]);
+        assert_eq!((map.border_min, map.border_max), ((2, 7), (18, 33)));
+        map.render_cells(&[&snake], &settings, 0);
+        assert_ne!(map.cells()[2][7].ch, WALL_CHAR, "newly opened cells are floor");
+
+        // Rows stop at the map edge while columns keep opening
+        snake.length = INITIAL_SNAKE_LENGTH + 8 * GROWING_BOARD_STEP;
+        map.update_growing_board(&[&snake]);
+        assert_eq!((map.border_min, map.border_max), ((0, 2), (20, 38)));
+        snake.length = INITIAL_SNAKE_LENGTH + 50 * GROWING_BOARD_STEP;
+        map.update_growing_board(&[&snake]);
+        assert_eq!((map.border_min, map.border_max), ((0, 0), (20, 40)));
+
+        // Food can now land in the outer ring
+        let mut rng = GameRng::seeded(RngAlgorithm::Xorshift, 8);
+        let outer = (0..200).any(|_| {
+            map.place_shared_food(&mut [&mut snake], &settings, &mut rng);
+            snake.food.0 < 5 || snake.food.0 >= 15 || snake.food.1 < 10 || snake.food.1 >= 30
+        });
+        assert!(outer);
+
+        // Spawns must fit the starting area
+        assert!(test_settings(&["--growing-board", "--p1-spawn", "top-left"]).validate().is_err());
+        assert!(test_settings(&["--growing-board"]).validate().is_ok());
+    }
+
+    #[test]
+    fn test_food_stays_inside_shrinking_border() {
+        let settings = test_settings(&["--shrinking-border", "--food-count", "3"]);
+        let mut map = GameMap::new(20, 20);
+        let mut snake = Snake::new(20, 20);
+        let mut rng = GameRng::seeded(RngAlgorithm::Xorshift, 5);
+        map.extra_food = vec![(0, 0), (19, 19)];
+        snake.food = (5, 19);
+
+        for _ in 0..40 * 50 {
+            map.update_shrinking_border(&snake);
+            map.rehome_food(&mut [&mut snake], &settings, &mut rng);
+            // Eat every tick so fresh pellets keep landing right after shrinks
+            snake.food_eaten = true;
+            map.resolve_food(&mut [&mut snake], &settings, &mut rng);
+
+            let (bmin, bmax) = (map.border_min, map.border_max);
+            let inside = |(r, c): (usize, usize)| r >= bmin.0 && r < bmax.0 && c >= bmin.1 && c < bmax.1;
+            assert!(inside(snake.food), "{:?} outside {bmin:?}..{bmax:?}", snake.food);
+            assert!(map.extra_food.iter().all(|&pos| inside(pos)));
+        }
+        // The border really did shrink all the way down
+        let (h, w) = (map.border_max.0 - map.border_min.0, map.border_max.1 - map.border_min.1);
+        assert_eq!(h.min(w), 6);
+    }
+
+    #[test]
+    fn test_walls_avoid_spawn_immunity_window() {
+        let mut snake = Snake::new(12, 12);
+        snake.spawn_at((6, 3), Direction::East, INITIAL_SNAKE_LENGTH);
+        snake.food = (0, 0);
+        let ahead: Vec<_> = (4..=8).map(|c| (6, c)).collect();
+
+        let immune = test_settings(&["--spawn-immunity", "5"]);
+        assert_eq!(snake.path_ahead(5, &immune, (0, 0), (12, 12)), ahead);
+        let plain = test_settings(&[]);
+        let mut blocked_without = false;
+        for seed in 0..20 {
+            let mut map = GameMap::new(12, 12);
+            map.place_walls(80, &[&snake], &immune, &mut GameRng::seeded(RngAlgorithm::Xorshift, seed));
+            assert_eq!(map.walls.len(), 80);
+            assert!(map.walls.iter().all(|w| !ahead.contains(w)), "seed {seed}");
+
+            map.place_walls(80, &[&snake], &plain, &mut GameRng::seeded(RngAlgorithm::Xorshift, seed));
+            blocked_without |= map.walls.iter().any(|w| ahead.contains(w));
+        }
+        assert!(blocked_without);
+
+        // The window stops at the border rather than running off the board
+        assert_eq!(snake.path_ahead(20, &immune, (0, 0), (12, 12)).len(), 8);
+
+        // Never on the key or gate, and a crowded board just gets fewer walls
+        let mut map = GameMap::new(12, 12);
+        (map.key, map.gate) = (Some((1, 1)), Some((10, 10)));
+        map.place_walls(500, &[&snake], &immune, &mut GameRng::seeded(RngAlgorithm::Xorshift, 1));
+        assert_eq!(map.walls.len(), 144 - 3 - 1 - ahead.len() - 2);
+        assert!(!map.walls.contains(&(1, 1)) && !map.walls.contains(&(10, 10)));
+
+        // Player 2 gets the same window
+        let mut p2 = Snake::new(12, 12);
+        p2.spawn_at((2, 8), Direction::West, INITIAL_SNAKE_LENGTH);
+        p2.food = (0, 0);
+        let ahead2 = p2.path_ahead(5, &immune, (0, 0), (12, 12));
+        let mut map = GameMap::new(12, 12);
+        map.place_walls(70, &[&snake, &p2], &immune, &mut GameRng::seeded(RngAlgorithm::Xorshift, 3));
+        assert!(map.walls.iter().all(|w| !ahead.contains(w) && !ahead2.contains(w)));
+
+        // A respawn clears walls placed since, but not the --shape outline
+        map.walls.extend(&ahead);
+        map.outside = vec![ahead[4]];
+        map.clear_spawn_paths(&[&snake], &immune);
+        assert!(map.walls.contains(&ahead[4]));
+        assert!(ahead[..4].iter().all(|w| !map.walls.contains(w)));
+    }
+
+    #[test]
+    fn test_warp_tile_relocates_snake() {
+        let mut map = GameMap::new(10, 10);
+        let mut snake = Snake::new(10, 10);
+        snake.spawn_at((5, 5), Direction::East, 4);
+        let mut rival = Snake::new(10, 10);
+        rival.spawn_at((1, 8), Direction::East, 6);
+        snake.food = (8, 8);
+        rival.food = (8, 8);
+        map.walls = vec![(0, 0), (3, 3), (7, 1)];
+        map.warp_tiles = vec![(5, 5), (9, 9)];
+
+        let mut rng = GameRng::seeded(RngAlgorithm::Xorshift, 3);
+        assert_eq!(map.warp_snakes(&mut [&mut snake, &mut rival], &mut rng), 1);
+        assert_eq!(map.warp_tiles, vec![(9, 9)]);
+        assert_eq!(snake.parts.len(), 4);
+        assert_eq!(snake.head, *snake.parts.back().unwrap());
+        assert_eq!(snake.direction, Direction::East);
+        // A straight, connected body clear of walls, the rival, food and tiles
+        for (&(r1, c1), &(r2, c2)) in snake.parts.iter().zip(snake.parts.iter().skip(1)) {
+            assert_eq!((r2, c2), (r1, c1 + 1));
+        }
+        for &pos in &snake.parts {
+            assert!(!map.walls.contains(&pos) && !rival.parts.contains(&pos) && pos != snake.food && pos != (9, 9));
+        }
+
+        // No tile under the head: nothing happens
+        let before = snake.parts.clone();
+        assert_eq!(map.warp_snakes(&mut [&mut snake, &mut rival], &mut rng), 0);
+        assert_eq!(snake.parts, before);
+    }
+
+    #[test]
+    fn test_time_attack_respawn_avoids_walls_and_rival() {
+        let mut map = GameMap::new(10, 10);
+        let mut rival = Snake::new(10, 10);
+        rival.spawn_at((2, 8), Direction::East, 6);
+        rival.food = (8, 8);
+        let mut rng = GameRng::seeded(RngAlgorithm::Xorshift, 5);
+
+        // An open spawn is kept
+        assert_eq!(map.open_spawn((5, 5), Direction::East, &[&rival], (8, 8), &mut rng), (5, 5));
+
+        // Food walls piled up on it: a random open stretch facing the same way
+        map.walls = vec![(5, 4), (7, 2)];
+        for _ in 0..20 {
+            let head = map.open_spawn((5, 5), Direction::East, &[&rival], (8, 8), &mut rng);
+            assert_ne!(head, (5, 5));
+            let cells = spawn_cells(head, Direction::East, INITIAL_SNAKE_LENGTH, 10, 10).unwrap();
+            assert!(cells.iter().all(|pos| !map.walls.contains(pos) && !rival.occupies(*pos) && *pos != (8, 8)));
+        }
+
+        // Nowhere to go: stays put
+        map.walls = (0..10).flat_map(|r| (0..10).map(move |c| (r, c))).collect();
+        assert_eq!(map.open_spawn((5, 5), Direction::East, &[&rival], (8, 8), &mut rng), (5, 5));
+    }
+
+    #[test]
+    fn test_reseed_on_restart_repeats_first_food() {
+        // The live restart sequence on one board: play on, then a new round
+        let rounds = |args: &[&str]| {
+            let settings = test_settings(args);
+            let first_seed = settings.round_seed();
+            let mut rng = GameRng::seeded(settings.rng, first_seed);
+            let mut map = GameMap::new(20, 20);
+            let mut snake = Snake::new(20, 20);
+            let mut starts = Vec::new();
+            for _round in 0..2 {
+                map.start_round(&mut [&mut snake], &settings, &mut rng).unwrap();
+                starts.push((snake.food, map.walls.clone(), map.extra_food.clone()));
+                for _ in 0..5 {
+                    snake.food_eaten = true;
+                    map.resolve_food(&mut [&mut snake], &settings, &mut rng);
+                }
+                map.bonus_food = Some(BonusFood { pos: (0, 0), lifetime: 10 });
+                if let Some(seed) = settings.next_round_seed(first_seed, &mut rng) {
+                    rng = GameRng::seeded(settings.rng, seed);
+                }
+            }
+            assert!(map.bonus_food.is_some());
+            map.start_round(&mut [&mut snake], &settings, &mut rng).unwrap();
+            assert!(map.bonus_food.is_none());
+            starts
+        };
+
+        let args = ["--seed", "77", "--auto-restart", "--obstacles", "6", "--food-walls", "--start-food", "3"];
+        let reseeded = rounds(&[&args[..], &["--reseed-on-restart"]].concat());
+        assert_eq!(reseeded[0], reseeded[1]);
+        assert_eq!(reseeded[0].1.len(), 6);
+        let drifting = rounds(&args);
+        assert_eq!(drifting[0], reseeded[0]);
+        assert_ne!(drifting[0], drifting[1]);
+    }
+
+    #[test]
+    fn test_tail_glyph_on_front_segment() {
+        let settings = test_settings(&["--multiplayer", "--tail-char", "~"]);
+        let mut map = GameMap::new(20, 20);
+        let mut p1 = Snake::new(20, 20);
+        p1.spawn_at((5, 6), Direction::East, 4);
+        let mut p2 = Snake::new(20, 20);
+        p2.spawn_at((12, 6), Direction::East, 4);
+        map.render_cells(&[&p1, &p2], &settings, 0);
+
+        let cells = map.cells();
+        assert_eq!(p1.parts.front(), Some(&(5, 3)));
+        assert_eq!((cells[5][3].ch, cells[5][3].color), ('~', Color::DarkGreen));
+        assert_eq!((cells[5][4].ch, cells[5][4].color), (settings.body, Color::Green));
+        assert_eq!(cells[5][5].ch, settings.body);
+        assert_eq!(cells[5][6].ch, settings.head_char(Direction::East));
+        // Player 2's tail is a dimmer shade of its own palette
+        assert_eq!((cells[12][3].ch, cells[12][3].color), ('~', Color::DarkCyan));
+        assert_eq!(cells[12][4].color, Color::Cyan);
 
-        buf
+        // Without --tail-char the tail is just body
+        map.render_cells(&[&p1], &test_settings(&[]), 0);
+        assert_eq!(map.cells()[5][3].ch, settings.body);
     }
 }
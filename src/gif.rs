@@ -0,0 +1,191 @@
+//! Replay-to-GIF export for `--export-gif`, encoded with the `gif` crate.
+//! Each board cell becomes a `CELL_PX` square: known glyphs are drawn from
+//! a small bitmap font in the cell's color, anything else as a solid block.
+
+use std::borrow::Cow;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use ::gif::{Encoder, Frame, Repeat};
+use crossterm::style::Color;
+
+use crate::config::Settings;
+use crate::game_map::Cell;
+use crate::replay::{Player, ReplaySim};
+use crate::snake::Snake;
+
+const CELL_PX: usize = 7;
+
+/// The 16 ANSI colors, in palette-index order.
+const PALETTE: [(Color, [u8; 3]); 16] = [
+    (Color::Black, [0x00, 0x00, 0x00]),
+    (Color::DarkRed, [0x80, 0x00, 0x00]),
+    (Color::DarkGreen, [0x00, 0x80, 0x00]),
+    (Color::DarkYellow, [0x80, 0x80, 0x00]),
+    (Color::DarkBlue, [0x00, 0x00, 0x80]),
+    (Color::DarkMagenta, [0x80, 0x00, 0x80]),
+    (Color::DarkCyan, [0x00, 0x80, 0x80]),
+    (Color::Grey, [0xC0, 0xC0, 0xC0]),
+    (Color::DarkGrey, [0x60, 0x60, 0x60]),
+    (Color::Red, [0xFF, 0x40, 0x40]),
+    (Color::Green, [0x40, 0xFF, 0x40]),
+    (Color::Yellow, [0xFF, 0xFF, 0x40]),
+    (Color::Blue, [0x40, 0x40, 0xFF]),
+    (Color::Magenta, [0xFF, 0x40, 0xFF]),
+    (Color::Cyan, [0x40, 0xFF, 0xFF]),
+    (Color::White, [0xFF, 0xFF, 0xFF]),
+];
+
+fn palette_index(color: Color) -> u8 {
+    PALETTE
+        .iter()
+        .position(|&(c, _)| c == color)
+        .unwrap_or(15) as u8 // RGB and other colors fall back to white
+}
+
+/// 5x5 glyph bitmaps, one byte per row with bit 4 as the leftmost pixel.
+fn glyph_bitmap(ch: char) -> [u8; 5] {
+    match ch {
+        '.' => [0b00000, 0b00000, 0b00100, 0b00000, 0b00000],
+        '@' => [0b01110, 0b10001, 0b10111, 0b10000, 0b01110],
+        '*' => [0b10101, 0b01110, 0b11111, 0b01110, 0b10101],
+        '$' => [0b01111, 0b10100, 0b01110, 0b00101, 0b11110],
+        '#' => [0b01010, 0b11111, 0b01010, 0b11111, 0b01010],
+        '<' => [0b00011, 0b01100, 0b10000, 0b01100, 0b00011],
+        '>' => [0b11000, 0b00110, 0b00001, 0b00110, 0b11000],
+        '^' => [0b00100, 0b01010, 0b10001, 0b00000, 0b00000],
+        'v' => [0b00000, 0b10001, 0b10001, 0b01010, 0b00100],
+        ' ' => [0; 5],
+        _ => [0b11111; 5],
+    }
+}
+
+/// Rasterise a cell grid into palette indices, row-major.
+pub fn rasterize(cells: &[Vec<Cell>]) -> (usize, usize, Vec<u8>) {
+    let rows = cells.len();
+    let cols = cells.first().map_or(0, Vec::len);
+    let (w, h) = (cols * CELL_PX, rows * CELL_PX);
+    let mut pixels = vec![0u8; w * h];
+    for (r, row) in cells.iter().enumerate() {
+        for (c, cell) in row.iter().enumerate() {
+            let index = palette_index(cell.color);
+            for (gy, bits) in glyph_bitmap(cell.ch).iter().enumerate() {
+                for gx in 0..5 {
+                    if bits & (0b10000 >> gx) != 0 {
+                        let y = r * CELL_PX + 1 + gy;
+                        let x = c * CELL_PX + 1 + gx;
+                        pixels[y * w + x] = index;
+                    }
+                }
+            }
+        }
+    }
+    (w, h, pixels)
+}
+
+/// Accumulates frames and writes them out as a looping animated GIF.
+pub struct GifEncoder {
+    width: u16,
+    height: u16,
+    frames: Vec<(u16, Vec<u8>)>, // (delay in 1/100 s, palette indices)
+}
+
+impl GifEncoder {
+    pub fn new(width: usize, height: usize) -> Self {
+        GifEncoder { width: width as u16, height: height as u16, frames: Vec::new() }
+    }
+
+    /// Add one frame of palette indices shown for `delay_ms`.
+    pub fn add_frame(&mut self, pixels: &[u8], delay_ms: u64) {
+        let delay = (delay_ms / 10).min(u16::MAX as u64) as u16;
+        self.frames.push((delay, pixels.to_vec()));
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn finish(&self) -> io::Result<Vec<u8>> {
+        let palette: Vec<u8> = PALETTE.iter().flat_map(|(_, rgb)| *rgb).collect();
+        let mut out = Vec::new();
+        let mut encoder = Encoder::new(&mut out, self.width, self.height, &palette).map_err(io::Error::other)?;
+        encoder.set_repeat(Repeat::Infinite).map_err(io::Error::other)?;
+        for (delay, pixels) in &self.frames {
+            let frame = Frame {
+                width: self.width,
+                height: self.height,
+                delay: *delay,
+                buffer: Cow::Borrowed(pixels),
+                ..Frame::default()
+            };
+            encoder.write_frame(&frame).map_err(io::Error::other)?;
+        }
+        drop(encoder);
+        Ok(out)
+    }
+}
+
+/// Render every frame of `player` and return the encoded GIF.
+pub fn encode_replay(player: &mut Player, settings: &Settings) -> GifEncoder {
+    let mut settings = settings.clone();
+    player.apply_meta(&mut settings);
+    let settings = &settings;
+    let mut sim = ReplaySim::start(settings, player);
+    let (w, h, pixels) = rasterize(render(&mut sim, settings));
+    let mut encoder = GifEncoder::new(w, h);
+    encoder.add_frame(&pixels, settings.effective_speed(sim.snake.length, sim.fill()));
+
+    while sim.step(player, settings) {
+        let (_, _, pixels) = rasterize(render(&mut sim, settings));
+        encoder.add_frame(&pixels, settings.effective_speed(sim.snake.length, sim.fill()));
+    }
+    encoder
+}
+
+fn render<'a>(sim: &'a mut ReplaySim, settings: &Settings) -> &'a [Vec<Cell>] {
+    let snakes: Vec<&Snake> = std::iter::once(&sim.snake).chain(sim.snake2.as_ref()).collect();
+    sim.map.render_cells(&snakes, settings, sim.frame_count);
+    sim.map.cells()
+}
+
+/// `--export-gif`: write the replay to `out` and return the frame count.
+pub fn export_replay(player: &mut Player, settings: &Settings, out: &Path) -> io::Result<usize> {
+    let encoder = encode_replay(player, settings);
+    fs::write(out, encoder.finish()?)?;
+    Ok(encoder.frame_count())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn test_replay_encodes_to_valid_gif() {
+        let path = std::env::temp_dir().join(format!("snake-term-gif-{}.rep", std::process::id()));
+        fs::write(&path, "E\n.\n.\nS\n.\n").unwrap();
+        let mut settings = Settings::parse_from(["test", "--seed", "7"]).resolve();
+        settings.map_width = 20;
+        settings.map_height = 20;
+
+        let mut player = Player::load(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        let encoder = encode_replay(&mut player, &settings);
+        // Initial board plus one frame per recorded input
+        assert_eq!(encoder.frame_count(), 6);
+
+        // Decodes back frame for frame
+        let data = encoder.finish().unwrap();
+        let mut options = ::gif::DecodeOptions::new();
+        options.set_color_output(::gif::ColorOutput::Indexed);
+        let mut decoder = options.read_info(data.as_slice()).unwrap();
+        assert_eq!((decoder.width() as usize, decoder.height() as usize), (20 * CELL_PX, 20 * CELL_PX));
+        let mut frames = 0;
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            assert_eq!(frame.buffer.len(), 20 * CELL_PX * 20 * CELL_PX);
+            frames += 1;
+        }
+        assert_eq!(frames, 6);
+    }
+}
@@ -1,35 +1,222 @@
+use std::ffi::OsString;
 use std::fs;
-use std::path::PathBuf;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
-fn highscore_path() -> PathBuf {
-    if let Some(data_dir) = dirs::data_local_dir() {
-        let dir = data_dir.join("snake-term");
-        let _ = fs::create_dir_all(&dir);
-        dir.join("highscores.txt")
-    } else {
-        PathBuf::from(".snake-term-highscores.txt")
+/// Environment variable that relocates all data files.
+pub const DATA_DIR_ENV: &str = "SNAKE_TERM_DATA";
+
+static DATA_DIR: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+/// Set once a write to the data dir fails; every data file is written here
+/// from then on.
+static FALLBACK_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Directory for data files: `--scores-dir`, then `$SNAKE_TERM_DATA`, then
+/// the platform's local data dir.
+pub fn resolve_data_dir(flag: Option<&Path>, env: Option<OsString>) -> Option<PathBuf> {
+    flag.map(Path::to_path_buf)
+        .or_else(|| env.filter(|v| !v.is_empty()).map(PathBuf::from))
+        .or_else(|| dirs::data_local_dir().map(|d| d.join("snake-term")))
+}
+
+/// Fix the data directory for the rest of the run. Called once at startup,
+/// before any data file is read.
+pub fn init_data_dir(flag: Option<&Path>) {
+    let _ = DATA_DIR.set(resolve_data_dir(flag, std::env::var_os(DATA_DIR_ENV)));
+}
+
+/// Location of a persisted data file (high scores, recovery saves, ...).
+pub fn data_path(file: &str) -> PathBuf {
+    if let Some(dir) = FALLBACK_DIR.get() {
+        return data_path_in(Some(dir), file);
+    }
+    data_path_in(data_dir(), file)
+}
+
+/// Where to read data file `file` from. After a switch to the fallback only
+/// the files written since live there, so the rest are still read from the
+/// data dir.
+pub fn read_data_path(file: &str) -> PathBuf {
+    read_path_in(FALLBACK_DIR.get().map(PathBuf::as_path), data_dir(), file)
+}
+
+fn data_dir() -> Option<&'static Path> {
+    DATA_DIR.get_or_init(|| resolve_data_dir(None, std::env::var_os(DATA_DIR_ENV))).as_deref()
+}
+
+/// `file` in `fallback` if it has been written there, else in `dir`.
+fn read_path_in(fallback: Option<&Path>, dir: Option<&Path>, file: &str) -> PathBuf {
+    fallback
+        .map(|fallback| fallback.join(file))
+        .filter(|path| path.exists())
+        .unwrap_or_else(|| data_path_in(dir, file))
+}
+
+/// Save data file `file` through `write`. The first time the data dir
+/// can't be written (read-only sandboxes and the like), all data files move
+/// to a temp-dir fallback for the rest of the run and the write is retried
+/// there. The original error is still returned so the caller can say the
+/// file didn't land where expected.
+pub fn save_data_file(file: &str, write: impl Fn(&Path) -> io::Result<()>) -> io::Result<()> {
+    let fallback = || {
+        let mut switched = false;
+        let dir = FALLBACK_DIR.get_or_init(|| {
+            switched = true;
+            std::env::temp_dir().join("snake-term")
+        });
+        switched.then(|| data_path_in(Some(dir), file))
+    };
+    write_with_fallback(&data_path(file), fallback, write)
+}
+
+/// Run `write` against `path`, then against `fallback()` if that fails.
+/// The first error is returned either way.
+fn write_with_fallback(
+    path: &Path,
+    fallback: impl FnOnce() -> Option<PathBuf>,
+    write: impl Fn(&Path) -> io::Result<()>,
+) -> io::Result<()> {
+    let result = write(path);
+    if result.is_err() {
+        if let Some(fallback) = fallback() {
+            let _ = write(&fallback);
+        }
+    }
+    result
+}
+
+/// `file` inside `dir`, creating the directory if needed. Without any data
+/// dir the file lands in the current directory.
+fn data_path_in(dir: Option<&Path>, file: &str) -> PathBuf {
+    match dir {
+        Some(dir) => {
+            let _ = fs::create_dir_all(dir);
+            dir.join(file)
+        }
+        None => PathBuf::from(format!(".snake-term-{file}")),
     }
 }
 
+const HIGHSCORE_FILE: &str = "highscores.txt";
+
+pub fn highscore_path() -> PathBuf {
+    read_data_path(HIGHSCORE_FILE)
+}
+
 pub fn load_high_score() -> usize {
-    let path = highscore_path();
+    read_high_score(&highscore_path())
+}
+
+fn read_high_score(path: &Path) -> usize {
     fs::read_to_string(path)
         .ok()
         .and_then(|s| s.trim().parse().ok())
         .unwrap_or(0)
 }
 
-pub fn save_high_score(score: usize) {
-    let path = highscore_path();
-    let _ = fs::write(path, score.to_string());
+pub fn save_high_score(score: usize) -> io::Result<()> {
+    save_data_file(HIGHSCORE_FILE, |path| fs::write(path, score.to_string()))
 }
 
-pub fn update_high_score(score: usize) -> (usize, bool) {
+/// Record `score` if it beats the stored one. Returns the high score, whether
+/// it's new, and any error saving it.
+pub fn update_high_score(score: usize) -> (usize, bool, io::Result<()>) {
     let current = load_high_score();
     if score > current {
-        save_high_score(score);
-        (score, true)
+        (score, true, save_high_score(score))
     } else {
-        (current, false)
+        (current, false, Ok(()))
+    }
+}
+
+/// Delete a data file for `--reset-scores` / `--reset-stats`. A file
+/// that was never written is already reset. Returns whether one existed.
+pub fn reset_data_file(path: &Path) -> io::Result<bool> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_dir_override() {
+        let flag = std::env::temp_dir().join(format!("snake-term-data-{}", std::process::id()));
+        let env = Some(OsString::from("/tmp/from-env"));
+
+        assert_eq!(resolve_data_dir(Some(&flag), env.clone()), Some(flag.clone()));
+        assert_eq!(resolve_data_dir(None, env), Some(PathBuf::from("/tmp/from-env")));
+        // An empty variable counts as unset
+        assert_eq!(
+            resolve_data_dir(None, Some(OsString::new())),
+            resolve_data_dir(None, None)
+        );
+
+        // Missing directories are created on first use
+        let nested = flag.join("nested");
+        let path = data_path_in(Some(&nested), "highscores.txt");
+        assert_eq!(path, nested.join("highscores.txt"));
+        assert!(nested.is_dir());
+        let _ = fs::remove_dir_all(&flag);
+    }
+
+    #[test]
+    fn test_write_failure_reported() {
+        let base = std::env::temp_dir().join(format!("snake-term-readonly-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(&base).unwrap();
+        // A plain file where the data dir should be makes every write fail
+        let blocked = base.join("not-a-dir");
+        fs::write(&blocked, "").unwrap();
+        let fallback = base.join("fallback.txt");
+        let write = |path: &Path| fs::write(path, "42");
+
+        let result = write_with_fallback(&blocked.join(HIGHSCORE_FILE), || Some(fallback.clone()), write);
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&fallback).unwrap(), "42");
+
+        let ok = base.join("ok.txt");
+        let result = write_with_fallback(&ok, || panic!("no fallback needed"), write);
+        assert!(result.is_ok());
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn test_reads_fall_through_to_data_dir() {
+        let base = std::env::temp_dir().join(format!("snake-term-fallback-read-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&base);
+        let (dir, fallback) = (base.join("data"), base.join("fallback"));
+        fs::create_dir_all(&dir).unwrap();
+        fs::create_dir_all(&fallback).unwrap();
+        fs::write(dir.join(HIGHSCORE_FILE), "120").unwrap();
+
+        // Only stats have been written since the switch: the high score still counts
+        fs::write(fallback.join("stats.txt"), "").unwrap();
+        let path = read_path_in(Some(&fallback), Some(&dir), HIGHSCORE_FILE);
+        assert_eq!(read_high_score(&path), 120);
+        assert_eq!(read_path_in(Some(&fallback), Some(&dir), "stats.txt"), fallback.join("stats.txt"));
+
+        // Once the high score is rewritten, the fallback copy wins
+        fs::write(fallback.join(HIGHSCORE_FILE), "150").unwrap();
+        let path = read_path_in(Some(&fallback), Some(&dir), HIGHSCORE_FILE);
+        assert_eq!(read_high_score(&path), 150);
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn test_reset_data_file() {
+        let path = std::env::temp_dir().join(format!("snake-term-reset-{}.txt", std::process::id()));
+        fs::write(&path, "120").unwrap();
+
+        assert!(reset_data_file(&path).unwrap());
+        assert!(!path.exists());
+        // Nothing there any more: still fine, just nothing to delete
+        assert!(!reset_data_file(&path).unwrap());
     }
 }
@@ -0,0 +1,92 @@
+use std::process::{Command, Stdio};
+
+use crate::bell::{BellEvent, EventSink};
+
+/// Starts an `--on-event` command. Split out so tests can record calls
+/// instead of spawning processes.
+pub trait CommandRunner {
+    fn run(&mut self, command: &str, event: &str);
+}
+
+/// Runs the command through the shell with the event name as its last
+/// argument. The child is detached: nothing waits on it in the game loop,
+/// and failures to start are ignored.
+pub struct ShellRunner;
+
+impl CommandRunner for ShellRunner {
+    fn run(&mut self, command: &str, event: &str) {
+        #[cfg(windows)]
+        let mut cmd = {
+            let mut cmd = Command::new("cmd");
+            cmd.arg("/C").arg(format!("{command} {event}"));
+            cmd
+        };
+        #[cfg(not(windows))]
+        let mut cmd = {
+            let mut cmd = Command::new("sh");
+            cmd.arg("-c").arg(format!("{command} \"$1\"")).arg("sh").arg(event);
+            cmd
+        };
+        let spawned = cmd.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).spawn();
+        if let Ok(mut child) = spawned {
+            // Reap it off the render thread so it doesn't linger as a zombie
+            std::thread::spawn(move || child.wait());
+        }
+    }
+}
+
+/// `--on-event`: hands food, bonus, death and level-up events to a user command.
+pub struct EventHook<R = ShellRunner> {
+    command: String,
+    runner: R,
+}
+
+impl EventHook {
+    pub fn new(command: Option<&str>) -> Option<Self> {
+        command.filter(|c| !c.trim().is_empty()).map(|c| EventHook::with_runner(c, ShellRunner))
+    }
+}
+
+impl<R: CommandRunner> EventHook<R> {
+    pub fn with_runner(command: &str, runner: R) -> Self {
+        EventHook { command: command.to_string(), runner }
+    }
+}
+
+impl<R: CommandRunner> EventSink for EventHook<R> {
+    fn send(&mut self, event: BellEvent) {
+        if let Some(name) = event.name() {
+            self.runner.run(&self.command, name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct MockRunner {
+        calls: Vec<(String, String)>,
+    }
+
+    impl CommandRunner for MockRunner {
+        fn run(&mut self, command: &str, event: &str) {
+            self.calls.push((command.to_string(), event.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_on_event_passes_event_names() {
+        let mut hook = EventHook::with_runner("obs-scene", MockRunner::default());
+        for event in [BellEvent::Food, BellEvent::Bonus, BellEvent::Beat, BellEvent::LevelUp, BellEvent::Death] {
+            hook.send(event);
+        }
+        let events: Vec<&str> = hook.runner.calls.iter().map(|(_, e)| e.as_str()).collect();
+        assert_eq!(events, ["food", "bonus", "level-up", "death"]);
+        assert!(hook.runner.calls.iter().all(|(c, _)| c == "obs-scene"));
+
+        assert!(EventHook::new(None).is_none());
+        assert!(EventHook::new(Some("  ")).is_none());
+    }
+}
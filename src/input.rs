@@ -1,107 +1,321 @@
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
-use std::time::Duration;
+use clap::ValueEnum;
+use crossterm::event::{KeyCode, KeyModifiers};
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
 
-use crate::config::{Direction, Settings};
+use crate::clock::IdleTimeout;
+use crate::config::{Direction, Settings, TwinMode};
+use crate::replay::Player;
+use crate::session::read_key;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GameInput {
     Move(Direction),
     MoveP2(Direction),
     Pause,
     Quit,
+    UseBomb,
+    Rewind,
     None,
 }
 
-pub fn poll_input(settings: &Settings, timeout: Duration) -> GameInput {
-    if !event::poll(timeout).unwrap_or(false) {
-        return GameInput::None;
+/// Rebindable keys for player 1 movement, pause and quit.
+/// Arrow keys and Ctrl+C keep their fixed meanings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyBindings {
+    pub up: Vec<KeyCode>,
+    pub down: Vec<KeyCode>,
+    pub left: Vec<KeyCode>,
+    pub right: Vec<KeyCode>,
+    pub pause: Vec<KeyCode>,
+    pub quit: Vec<KeyCode>,
+    pub bomb: Vec<KeyCode>,
+    pub rewind: Vec<KeyCode>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings {
+            up: vec![KeyCode::Char('w')],
+            down: vec![KeyCode::Char('s')],
+            left: vec![KeyCode::Char('a')],
+            right: vec![KeyCode::Char('d')],
+            pause: vec![KeyCode::Char('p'), KeyCode::Char(' ')],
+            quit: vec![KeyCode::Char('q'), KeyCode::Esc],
+            bomb: vec![KeyCode::Char('b')],
+            rewind: vec![KeyCode::Char('z')],
+        }
     }
+}
 
-    match event::read() {
-        Ok(Event::Key(KeyEvent {
-            code, modifiers, ..
-        })) => {
-            if modifiers.contains(KeyModifiers::CONTROL) && code == KeyCode::Char('c') {
-                return GameInput::Quit;
-            }
+impl KeyBindings {
+    pub fn from_settings(settings: &Settings) -> Result<Self, String> {
+        let bindings = KeyBindings {
+            up: parse_key_list(&settings.keys_up)?,
+            down: parse_key_list(&settings.keys_down)?,
+            left: parse_key_list(&settings.keys_left)?,
+            right: parse_key_list(&settings.keys_right)?,
+            pause: parse_key_list(&settings.pause_keys)?,
+            quit: parse_key_list(&settings.quit_keys)?,
+            bomb: parse_key_list(&settings.bomb_keys)?,
+            rewind: parse_key_list(&settings.rewind_keys)?,
+        };
 
-            match code {
-                KeyCode::Char('p') | KeyCode::Char('P') | KeyCode::Char(' ') => {
-                    return GameInput::Pause;
-                }
-                KeyCode::Char('w') | KeyCode::Char('W') => {
-                    let dir = if settings.invert_controls { Direction::South } else { Direction::North };
-                    return GameInput::Move(dir);
-                }
-                KeyCode::Char('s') | KeyCode::Char('S') => {
-                    let dir = if settings.invert_controls { Direction::North } else { Direction::South };
-                    return GameInput::Move(dir);
-                }
-                KeyCode::Char('a') | KeyCode::Char('A') => {
-                    let dir = if settings.invert_controls { Direction::East } else { Direction::West };
-                    return GameInput::Move(dir);
-                }
-                KeyCode::Char('d') | KeyCode::Char('D') => {
-                    let dir = if settings.invert_controls { Direction::West } else { Direction::East };
-                    return GameInput::Move(dir);
-                }
-                KeyCode::Up => {
-                    let dir = if settings.invert_controls { Direction::South } else { Direction::North };
-                    return if settings.multiplayer { GameInput::MoveP2(dir) } else { GameInput::Move(dir) };
-                }
-                KeyCode::Down => {
-                    let dir = if settings.invert_controls { Direction::North } else { Direction::South };
-                    return if settings.multiplayer { GameInput::MoveP2(dir) } else { GameInput::Move(dir) };
-                }
-                KeyCode::Left => {
-                    let dir = if settings.invert_controls { Direction::East } else { Direction::West };
-                    return if settings.multiplayer { GameInput::MoveP2(dir) } else { GameInput::Move(dir) };
-                }
-                KeyCode::Right => {
-                    let dir = if settings.invert_controls { Direction::West } else { Direction::East };
-                    return if settings.multiplayer { GameInput::MoveP2(dir) } else { GameInput::Move(dir) };
+        // Every key may only trigger one action
+        let actions = [
+            ("up", &bindings.up),
+            ("down", &bindings.down),
+            ("left", &bindings.left),
+            ("right", &bindings.right),
+            ("pause", &bindings.pause),
+            ("quit", &bindings.quit),
+            ("bomb", &bindings.bomb),
+            ("rewind", &bindings.rewind),
+        ];
+        for (i, (name, keys)) in actions.iter().enumerate() {
+            for (other, other_keys) in &actions[i + 1..] {
+                if let Some(key) = keys.iter().find(|k| other_keys.contains(k)) {
+                    return Err(format!("key {key:?} is bound to both {name} and {other}"));
                 }
-                KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => {
-                    return GameInput::Quit;
-                }
-                _ => return GameInput::None,
             }
         }
-        _ => GameInput::None,
+        Ok(bindings)
+    }
+}
+
+/// Parse a comma-separated key list such as "p,space" or "q,esc".
+fn parse_key_list(list: &str) -> Result<Vec<KeyCode>, String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(parse_key)
+        .collect()
+}
+
+fn parse_key(name: &str) -> Result<KeyCode, String> {
+    let mut chars = name.chars();
+    if let (Some(ch), None) = (chars.next(), chars.next()) {
+        return Ok(KeyCode::Char(ch.to_ascii_lowercase()));
+    }
+    match name.to_ascii_lowercase().as_str() {
+        "space" => Ok(KeyCode::Char(' ')),
+        "comma" => Ok(KeyCode::Char(',')),
+        "esc" | "escape" => Ok(KeyCode::Esc),
+        "enter" => Ok(KeyCode::Enter),
+        "tab" => Ok(KeyCode::Tab),
+        "backspace" => Ok(KeyCode::Backspace),
+        _ => Err(format!("unknown key name '{name}'")),
+    }
+}
+
+/// Where the game loop's input comes from.
+pub trait InputSource {
+    /// Input for the start of a new frame.
+    fn frame_input(&mut self, settings: &Settings, timeout: Duration) -> GameInput;
+    /// Input arriving while the rest of the frame is waited out.
+    fn wait_input(&mut self, settings: &Settings, timeout: Duration) -> GameInput;
+}
+
+/// The terminal keyboard.
+pub struct Keyboard;
+
+impl InputSource for Keyboard {
+    fn frame_input(&mut self, settings: &Settings, timeout: Duration) -> GameInput {
+        poll_input(settings, timeout)
+    }
+
+    fn wait_input(&mut self, settings: &Settings, timeout: Duration) -> GameInput {
+        poll_input(settings, timeout)
+    }
+}
+
+/// What `--input-script` does once every line has been played.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ScriptEnd {
+    /// Start again from the first line
+    Loop,
+    /// End the game
+    Quit,
+}
+
+/// Player 1's moves read from a file, one frame per line in the replay
+/// alphabet (N, S, E, W or '.'). The keyboard still pauses and quits.
+#[derive(Clone)]
+pub struct ScriptInput {
+    moves: Player,
+    end: ScriptEnd,
+}
+
+impl ScriptInput {
+    pub fn load(path: &Path, end: ScriptEnd) -> io::Result<Self> {
+        Ok(ScriptInput { moves: Player::load(path)?, end })
+    }
+
+    /// The scripted input for the next frame.
+    fn next_move(&mut self) -> GameInput {
+        let frame = match self.moves.next_frame() {
+            Some(frame) => frame,
+            None if self.end == ScriptEnd::Loop => {
+                self.moves.seek(0);
+                self.moves.next_frame().flatten()
+            }
+            None => return GameInput::Quit,
+        };
+        frame.map_or(GameInput::None, GameInput::Move)
+    }
+}
+
+impl InputSource for ScriptInput {
+    fn frame_input(&mut self, settings: &Settings, timeout: Duration) -> GameInput {
+        match self.wait_input(settings, timeout) {
+            GameInput::None => self.next_move(),
+            other => other,
+        }
+    }
+
+    fn wait_input(&mut self, settings: &Settings, timeout: Duration) -> GameInput {
+        match poll_input(settings, timeout) {
+            GameInput::Move(_) | GameInput::MoveP2(_) => GameInput::None,
+            other => other,
+        }
+    }
+}
+
+pub fn poll_input(settings: &Settings, timeout: Duration) -> GameInput {
+    match read_key(timeout) {
+        Some((code, modifiers)) => map_key(settings, code, modifiers),
+        None => GameInput::None,
+    }
+}
+
+/// Translate a key press into a game action using the configured bindings.
+pub fn map_key(settings: &Settings, code: KeyCode, modifiers: KeyModifiers) -> GameInput {
+    if modifiers.contains(KeyModifiers::CONTROL) && code == KeyCode::Char('c') {
+        return GameInput::Quit;
+    }
+
+    let key = match code {
+        KeyCode::Char(ch) => KeyCode::Char(ch.to_ascii_lowercase()),
+        other => other,
+    };
+    let bindings = &settings.bindings;
+    let bound_dir = if bindings.up.contains(&key) {
+        Some(Direction::North)
+    } else if bindings.down.contains(&key) {
+        Some(Direction::South)
+    } else if bindings.left.contains(&key) {
+        Some(Direction::West)
+    } else if bindings.right.contains(&key) {
+        Some(Direction::East)
+    } else {
+        None
+    };
+    let invert = |dir: Direction| if settings.invert_controls { dir.opposite() } else { dir };
+
+    if let Some(dir) = bound_dir {
+        return GameInput::Move(invert(dir));
+    }
+    if bindings.pause.contains(&key) {
+        return GameInput::Pause;
+    }
+    if bindings.quit.contains(&key) {
+        return GameInput::Quit;
+    }
+    if bindings.bomb.contains(&key) {
+        return GameInput::UseBomb;
     }
+    if bindings.rewind.contains(&key) {
+        return GameInput::Rewind;
+    }
+
+    let arrow_dir = match key {
+        KeyCode::Up => Direction::North,
+        KeyCode::Down => Direction::South,
+        KeyCode::Left => Direction::West,
+        KeyCode::Right => Direction::East,
+        _ => return GameInput::None,
+    };
+    let dir = invert(arrow_dir);
+    // Arrows steer player 2, an independent --two-headed second head, or
+    // the split-off half of a --powerups snake
+    if settings.multiplayer || settings.two_headed == Some(TwinMode::Independent) || settings.powerups { GameInput::MoveP2(dir) } else { GameInput::Move(dir) }
+}
+
+/// Split a frame delay into input-poll waits of at most `interval`.
+pub fn poll_waits(frame: Duration, interval: Duration) -> impl Iterator<Item = Duration> {
+    let interval = interval.max(Duration::from_millis(1));
+    let mut remaining = frame;
+    std::iter::from_fn(move || {
+        if remaining.is_zero() {
+            return None;
+        }
+        let wait = remaining.min(interval);
+        remaining -= wait;
+        Some(wait)
+    })
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MenuInput {
     Enter,
     Up,
     Down,
+    Left,
+    Right,
     Quit,
     None,
 }
 
 pub fn poll_menu_input(timeout: Duration) -> MenuInput {
-    if !event::poll(timeout).unwrap_or(false) {
-        return MenuInput::None;
+    match read_key(timeout) {
+        Some((code, modifiers)) => map_menu_key(code, modifiers),
+        None => MenuInput::None,
     }
+}
 
-    match event::read() {
-        Ok(Event::Key(KeyEvent {
-            code, modifiers, ..
-        })) => {
-            if modifiers.contains(KeyModifiers::CONTROL) && code == KeyCode::Char('c') {
-                return MenuInput::Quit;
-            }
-            match code {
-                KeyCode::Up | KeyCode::Char('w') | KeyCode::Char('W') => MenuInput::Up,
-                KeyCode::Down | KeyCode::Char('s') | KeyCode::Char('S') => MenuInput::Down,
-                KeyCode::Enter | KeyCode::Char(' ') => MenuInput::Enter,
-                KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => MenuInput::Quit,
-                _ => MenuInput::None,
-            }
-        }
+/// Translate a key press on the start menu.
+pub fn map_menu_key(code: KeyCode, modifiers: KeyModifiers) -> MenuInput {
+    if modifiers.contains(KeyModifiers::CONTROL) && code == KeyCode::Char('c') {
+        return MenuInput::Quit;
+    }
+    match code {
+        KeyCode::Up | KeyCode::Char('w') | KeyCode::Char('W') => MenuInput::Up,
+        KeyCode::Down | KeyCode::Char('s') | KeyCode::Char('S') => MenuInput::Down,
+        KeyCode::Left | KeyCode::Char('a') | KeyCode::Char('A') => MenuInput::Left,
+        KeyCode::Right | KeyCode::Char('d') | KeyCode::Char('D') => MenuInput::Right,
+        KeyCode::Enter | KeyCode::Char(' ') => MenuInput::Enter,
+        KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => MenuInput::Quit,
         _ => MenuInput::None,
     }
 }
 
+pub enum ReplayInput {
+    TogglePause,
+    StepForward,
+    StepBack,
+    Quit,
+    Other, // any other key
+    None,
+}
+
+/// Replay controls: the pause and quit bindings, plus '.' and ',' to step
+/// one frame forward or back while paused.
+pub fn poll_replay_input(settings: &Settings, timeout: Duration) -> ReplayInput {
+    match read_key(timeout) {
+        Some((code, modifiers)) => match code {
+            KeyCode::Char('.') => ReplayInput::StepForward,
+            KeyCode::Char(',') => ReplayInput::StepBack,
+            _ => match map_key(settings, code, modifiers) {
+                GameInput::Pause => ReplayInput::TogglePause,
+                GameInput::Quit => ReplayInput::Quit,
+                _ => ReplayInput::Other,
+            },
+        },
+        None => ReplayInput::None,
+    }
+}
+
 pub enum GameOverInput {
     Restart,
     Quit,
@@ -109,25 +323,145 @@ pub enum GameOverInput {
     None,
 }
 
-pub fn poll_game_over_input() -> GameOverInput {
-    if !event::poll(Duration::from_millis(100)).unwrap_or(false) {
+pub fn poll_game_over_input(timeout: Duration) -> GameOverInput {
+    let Some((code, modifiers)) = read_key(timeout) else {
         return GameOverInput::None;
+    };
+    if modifiers.contains(KeyModifiers::CONTROL) && code == KeyCode::Char('c') {
+        return GameOverInput::Quit;
     }
+    match code {
+        KeyCode::Char('r') | KeyCode::Char('R') => GameOverInput::Restart,
+        KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => GameOverInput::Quit,
+        KeyCode::Char('m') | KeyCode::Char('M') => GameOverInput::Menu,
+        _ => GameOverInput::None,
+    }
+}
 
-    match event::read() {
-        Ok(Event::Key(KeyEvent {
-            code, modifiers, ..
-        })) => {
-            if modifiers.contains(KeyModifiers::CONTROL) && code == KeyCode::Char('c') {
-                return GameOverInput::Quit;
-            }
-            match code {
-                KeyCode::Char('r') | KeyCode::Char('R') => GameOverInput::Restart,
-                KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => GameOverInput::Quit,
-                KeyCode::Char('m') | KeyCode::Char('M') => GameOverInput::Menu,
-                _ => GameOverInput::None,
+/// Turn an idle game-over poll into `Menu` once `timeout` has expired.
+pub fn game_over_timeout(input: GameOverInput, timeout: Option<&IdleTimeout>, now: Instant) -> GameOverInput {
+    match input {
+        GameOverInput::None if timeout.is_some_and(|t| t.expired(now)) => GameOverInput::Menu,
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{INITIAL_SNAKE_LENGTH, LOW_POWER_POLL_INTERVAL, POLL_INTERVAL};
+    use clap::Parser;
+
+    fn settings_with(args: &[&str]) -> Settings {
+        Settings::parse_from(std::iter::once("test").chain(args.iter().copied())).resolve()
+    }
+
+    fn is_move(input: GameInput, expected: Direction) -> bool {
+        matches!(input, GameInput::Move(dir) if dir == expected)
+    }
+
+    #[test]
+    fn test_space_rebound_from_pause_to_movement() {
+        let defaults = settings_with(&[]);
+        assert!(matches!(map_key(&defaults, KeyCode::Char(' '), KeyModifiers::NONE), GameInput::Pause));
+
+        let settings = settings_with(&["--pause-keys", "p", "--keys-up", "w,space"]);
+        assert!(settings.validate().is_ok());
+        assert!(is_move(map_key(&settings, KeyCode::Char(' '), KeyModifiers::NONE), Direction::North));
+        assert!(matches!(map_key(&settings, KeyCode::Char('P'), KeyModifiers::NONE), GameInput::Pause));
+    }
+
+    #[test]
+    fn test_paste_event_is_ignored() {
+        use crate::session::key_of;
+        use crossterm::event::{Event, KeyEvent};
+
+        let settings = settings_with(&[]);
+        let input = |event: Event| key_of(&event).map_or(GameInput::None, |(code, mods)| map_key(&settings, code, mods));
+
+        assert_eq!(input(Event::Paste("wwddss".to_string())), GameInput::None);
+        assert_eq!(input(Event::Paste("q".to_string())), GameInput::None);
+        // The same text typed key by key does steer
+        let typed = Event::Key(KeyEvent::new(KeyCode::Char('w'), KeyModifiers::NONE));
+        assert!(is_move(input(typed), Direction::North));
+    }
+
+    #[test]
+    fn test_conflicting_bindings_rejected() {
+        let settings = settings_with(&["--keys-up", "w,space"]);
+        assert!(settings.validate().is_err());
+        let settings = settings_with(&["--quit-keys", "q,p"]);
+        assert!(settings.validate().is_err());
+        let settings = settings_with(&["--pause-keys", "bogus"]);
+        assert!(settings.validate().is_err());
+        let settings = settings_with(&["--bomb-keys", "p"]);
+        assert!(settings.validate().is_err());
+    }
+
+    #[test]
+    fn test_low_power_poll_interval() {
+        let normal = settings_with(&[]);
+        let low = settings_with(&["--low-power"]);
+        assert_eq!(normal.poll_interval, POLL_INTERVAL);
+        assert_eq!(low.poll_interval, LOW_POWER_POLL_INTERVAL);
+        assert!(low.menu_poll_interval() > normal.menu_poll_interval());
+
+        for settings in [&normal, &low] {
+            let frame = Duration::from_millis(205);
+            let waits: Vec<Duration> = poll_waits(frame, settings.poll_interval).collect();
+            assert_eq!(waits.iter().sum::<Duration>(), frame);
+            assert!(waits.iter().all(|&w| w <= settings.poll_interval));
+        }
+        assert!(poll_waits(Duration::ZERO, POLL_INTERVAL).next().is_none());
+    }
+
+    #[test]
+    fn test_game_over_timeout_returns_to_menu() {
+        let start = Instant::now();
+        let timeout = IdleTimeout::new(5, start);
+        let at = |secs: u64| start + Duration::from_secs(secs);
+
+        assert!(matches!(game_over_timeout(GameOverInput::None, timeout.as_ref(), at(4)), GameOverInput::None));
+        assert!(matches!(game_over_timeout(GameOverInput::None, timeout.as_ref(), at(5)), GameOverInput::Menu));
+        // A real key press still wins after the deadline
+        assert!(matches!(game_over_timeout(GameOverInput::Restart, timeout.as_ref(), at(9)), GameOverInput::Restart));
+
+        // 0 = wait forever
+        let forever = IdleTimeout::new(0, start);
+        assert!(matches!(game_over_timeout(GameOverInput::None, forever.as_ref(), at(3600)), GameOverInput::None));
+    }
+
+    #[test]
+    fn test_script_drives_snake() {
+        use crate::snake::Snake;
+
+        let path = std::env::temp_dir().join(format!("snake-term-script-{}.txt", std::process::id()));
+        std::fs::write(&path, "N\n.\nW\n").unwrap();
+        let mut script = ScriptInput::load(&path, ScriptEnd::Loop).unwrap();
+        let mut once = ScriptInput::load(&path, ScriptEnd::Quit).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        let mut settings = settings_with(&[]);
+        settings.map_width = 20;
+        settings.map_height = 20;
+        let mut snake = Snake::new(20, 20);
+        snake.spawn_at((10, 5), Direction::East, INITIAL_SNAKE_LENGTH);
+
+        let mut path = Vec::new();
+        for _ in 0..6 {
+            if let GameInput::Move(dir) = script.next_move() {
+                snake.queue_direction(dir);
             }
+            snake.apply_queued_input();
+            snake.update_movement(&settings, &[], (0, 0), (20, 20));
+            path.push(snake.head);
         }
-        _ => GameOverInput::None,
+        // N . W, then the script loops: N . W again
+        assert_eq!(path, vec![(9, 5), (8, 5), (8, 4), (7, 4), (6, 4), (6, 3)]);
+
+        for _ in 0..3 {
+            assert!(!matches!(once.next_move(), GameInput::Quit));
+        }
+        assert!(matches!(once.next_move(), GameInput::Quit));
     }
 }
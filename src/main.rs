@@ -1,55 +1,214 @@
+mod ai;
+mod announce;
+mod art;
+mod bell;
+mod clock;
 mod config;
 mod game_map;
+#[cfg(feature = "gif")]
+mod gif;
 mod highscore;
+mod hook;
 mod input;
+mod mapfile;
+mod menu;
 mod replay;
+mod rng;
+mod runcode;
+mod score;
+mod screen;
+mod session;
+mod skin;
 mod snake;
+mod snapshot;
+mod state;
+mod stats;
 
 use std::io::{self, Write};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use crossterm::{
     cursor,
     style::{Color, Stylize},
     terminal::{self, ClearType},
     ExecutableCommand,
 };
-use rand::rngs::StdRng;
-use rand::SeedableRng;
 
-use config::Settings;
+use announce::Announcer;
+use art::{ArtProgress, ArtTarget};
+use bell::{Bell, BellEvent, EventSink};
+use clock::{IdleTimeout, SlowDetector, StepAccumulator, TimeAttack};
+use config::{SetupAnswers, Settings};
 use game_map::GameMap;
 use highscore::update_high_score;
+use hook::EventHook;
+use stats::Stats;
 use input::*;
-use replay::{Player, Recorder};
+use mapfile::MapFile;
+use menu::{CustomOption, GameMode, MenuTheme, SetupOption};
+use replay::{FrameEvent, Player, Recorder, ReplaySim};
+use rng::GameRng;
+use runcode::RunCode;
+use score::{decide_winner, Milestones, PlayerResult, RunStats, Session};
+use screen::Screen;
+use skin::Skin;
 use snake::Snake;
+use snapshot::{Autosave, Snapshot};
+use state::GameState;
 
-fn bell(stdout: &mut io::Stdout) {
-    let _ = write!(stdout, "\x07");
-    let _ = stdout.flush();
+/// Pass a game event to the bell and any `--on-event` hook.
+fn announce(sinks: &mut [&mut dyn EventSink], event: BellEvent) {
+    for sink in sinks.iter_mut() {
+        sink.send(event);
+    }
+}
+
+/// Write a rendered frame, plus this frame's share of any queued bell
+/// pattern, in a single write and flush so no half-drawn frame shows.
+fn present(stdout: &mut io::Stdout, frame: &str, bell: &mut Bell) -> io::Result<()> {
+    let mut out = game_map::compose_frame(frame, true);
+    out.push_str(bell.tick());
+    stdout.write_all(out.as_bytes())?;
+    stdout.flush()
 }
 
 fn main() {
-    let settings = Settings::parse().resolve();
+    let mut settings = Settings::parse().with_default_config().resolve();
+    if let Err(e) = settings.validate() {
+        eprintln!("Error: {e}");
+        std::process::exit(2);
+    }
+    highscore::init_data_dir(settings.scores_dir.as_deref());
+    if settings.reset_scores || settings.reset_stats {
+        reset_data(&settings);
+        return;
+    }
+    if let Some(path) = settings.from_snapshot.clone() {
+        match Snapshot::load(&path) {
+            Ok(snap) => {
+                settings.use_snapshot(snap);
+                // Spawns have to fit the snapshot's size instead
+                if let Err(e) = settings.validate() {
+                    eprintln!("Error: {}: {e}", path.display());
+                    std::process::exit(2);
+                }
+            }
+            Err(e) => {
+                eprintln!("Error: failed to load {}: {e}", path.display());
+                std::process::exit(2);
+            }
+        }
+    }
+    if let Some(path) = settings.map_file.clone() {
+        match MapFile::load(&path) {
+            Ok(map) => {
+                settings.use_map(map);
+                // Spawns have to fit the map's size instead
+                if let Err(e) = settings.validate() {
+                    eprintln!("Error: {}: {e}", path.display());
+                    std::process::exit(2);
+                }
+            }
+            Err(e) => {
+                eprintln!("Error: failed to load {}: {e}", path.display());
+                std::process::exit(2);
+            }
+        }
+    }
+    if let Some(ref path) = settings.art_target {
+        match ArtTarget::load(path) {
+            Ok(art) if art.fits(settings.map_width, settings.map_height) => settings.art = Some(art),
+            Ok(_) => {
+                eprintln!("Error: {} is larger than the board", path.display());
+                std::process::exit(2);
+            }
+            Err(e) => {
+                eprintln!("Error: failed to load {}: {e}", path.display());
+                std::process::exit(2);
+            }
+        }
+    }
+    // After the board files, so the log's header holds the board they made
+    if let Some(path) = settings.session_log.clone() {
+        // Every game of the session starts from the one seed in the header
+        settings.seed = settings.round_seed();
+        if let Err(e) = session::record_to(&path, &settings) {
+            eprintln!("Error: failed to create {}: {e}", path.display());
+            std::process::exit(2);
+        }
+    }
+    if let Some(path) = settings.session_play.clone() {
+        if let Err(e) = session::play_from(&path, &mut settings) {
+            eprintln!("Error: failed to load {}: {e}", path.display());
+            std::process::exit(2);
+        }
+    }
+    if !skin::is_unlocked(settings.skin, highscore::load_high_score(), &settings.skin_unlocks.0) {
+        eprintln!(
+            "Error: the {} skin unlocks at a high score of {}",
+            settings.skin.label(),
+            skin::unlock_threshold(settings.skin, &settings.skin_unlocks.0)
+        );
+        std::process::exit(2);
+    }
+
+    if let [frame, text] = &settings.annotate[..] {
+        annotate_replay(&settings, frame, text);
+        return;
+    }
+    if let Some(out) = &settings.export_gif {
+        export_gif(&settings, out);
+        return;
+    }
+
+    if let Some(out) = &settings.render_to {
+        render_to(&settings, out);
+        return;
+    }
+    if let Some(seed) = settings.audit {
+        run_audit(&settings, seed);
+        return;
+    }
+
+    // Loaded once; every game of the session plays it from the top
+    let mut script = None;
+    if let Some(ref path) = settings.input_script {
+        match ScriptInput::load(path, settings.script_end) {
+            Ok(loaded) => script = Some(loaded),
+            Err(e) => {
+                eprintln!("Error: failed to load {}: {e}", path.display());
+                std::process::exit(2);
+            }
+        }
+    }
+    let mut announcer = None;
+    if let Some(ref path) = settings.announce {
+        match Announcer::new(path) {
+            Ok(opened) => announcer = Some(opened),
+            Err(e) => {
+                eprintln!("Error: failed to open {}: {e}", path.display());
+                std::process::exit(2);
+            }
+        }
+    }
 
     let mut stdout = io::stdout();
+    let screen = Screen::new(settings.no_alt_screen, settings.no_bracketed_paste);
     terminal::enable_raw_mode().expect("Failed to enable raw mode");
-    stdout
-        .execute(terminal::EnterAlternateScreen)
-        .expect("Failed to enter alternate screen");
-    stdout
-        .execute(cursor::Hide)
-        .expect("Failed to hide cursor");
+    screen.setup(&mut stdout).expect("Failed to set up the terminal");
 
     let result = if settings.replay.is_some() {
         run_replay(&settings, &mut stdout)
     } else {
-        show_menu_and_play(&settings, &mut stdout)
+        let first_run = settings.first_run && !settings.scripted_input();
+        let setup = if settings.setup || first_run { run_setup_wizard(&mut settings, &mut stdout) } else { Ok(()) };
+        setup
+            .and_then(|()| show_splash(&settings, &mut stdout))
+            .and_then(|()| show_menu_and_play(&settings, &mut stdout, script.as_ref(), &mut announcer))
     };
 
-    let _ = stdout.execute(cursor::Show);
-    let _ = stdout.execute(terminal::LeaveAlternateScreen);
+    let _ = screen.teardown(&mut stdout);
     let _ = terminal::disable_raw_mode();
 
     if let Err(e) = result {
@@ -58,25 +217,195 @@ fn main() {
     }
 }
 
-fn show_menu_and_play(settings: &Settings, stdout: &mut io::Stdout) -> io::Result<()> {
+/// `--reset-scores` / `--reset-stats`: delete the chosen data files once
+/// the player confirms (or straight away with `--yes`).
+fn reset_data(settings: &Settings) {
+    let targets: Vec<(&str, std::path::PathBuf)> = [
+        (settings.reset_scores, "high score", highscore::highscore_path()),
+        (settings.reset_stats, "stats", stats::stats_path()),
+    ]
+    .into_iter()
+    .filter_map(|(chosen, label, path)| chosen.then_some((label, path)))
+    .collect();
+
+    if !settings.yes {
+        let names: Vec<&str> = targets.iter().map(|(label, _)| *label).collect();
+        print!("Delete the saved {}? [y/N] ", names.join(" and "));
+        let _ = io::stdout().flush();
+        let mut answer = String::new();
+        let _ = io::stdin().read_line(&mut answer);
+        if !matches!(answer.trim(), "y" | "Y" | "yes") {
+            println!("Nothing deleted.");
+            return;
+        }
+    }
+
+    for (label, path) in targets {
+        match highscore::reset_data_file(&path) {
+            Ok(true) => println!("Deleted {label} ({})", path.display()),
+            Ok(false) => println!("No {label} saved yet"),
+            Err(e) => {
+                eprintln!("Failed to delete {}: {e}", path.display());
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+fn annotate_replay(settings: &Settings, frame: &str, text: &str) {
+    let path = settings.replay.as_ref().unwrap();
+    let Ok(frame) = frame.parse() else {
+        eprintln!("Error: --annotate frame must be a number, got '{frame}'");
+        std::process::exit(2);
+    };
+    match replay::annotate(path, frame, text) {
+        Ok(()) => println!("Added a note at frame {frame} to {}", path.display()),
+        Err(e) => {
+            eprintln!("Failed to annotate replay: {e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(feature = "gif")]
+fn export_gif(settings: &Settings, out: &std::path::Path) {
+    let path = settings.replay.as_ref().unwrap();
+    let result = Player::load(path).and_then(|mut player| gif::export_replay(&mut player, settings, out));
+    match result {
+        Ok(frames) => println!("Wrote {frames} frames to {}", out.display()),
+        Err(e) => {
+            eprintln!("Failed to export GIF: {e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(not(feature = "gif"))]
+fn export_gif(_settings: &Settings, _out: &std::path::Path) {
+    eprintln!("Error: --export-gif requires building with `--features gif`");
+    std::process::exit(2);
+}
+
+fn render_to(settings: &Settings, out: &std::path::Path) {
+    let result = std::fs::File::create(out)
+        .and_then(|file| replay::render_frames(settings, settings.frames, &mut io::BufWriter::new(file)));
+    match result {
+        Ok(frames) => println!("Wrote {frames} frames to {}", out.display()),
+        Err(e) => {
+            eprintln!("Failed to render frames: {e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn run_audit(settings: &Settings, seed: u64) {
+    let settings = Settings { seed, ..settings.clone() };
+    match replay::audit(&settings, settings.audit_frames) {
+        Ok(Ok(frames)) => println!("Deterministic: {frames} frames matched for seed {seed}"),
+        Ok(Err(d)) => {
+            let hash = |h: Option<u64>| h.map_or("(ended)".to_string(), |h| format!("{h:016x}"));
+            eprintln!("Diverged at frame {}: {} vs {}", d.frame, hash(d.first), hash(d.second));
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Audit failed: {e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Title, version and a snake crawling past before the menu, with
+/// `--splash`. Any key skips straight to the menu.
+fn show_splash(settings: &Settings, stdout: &mut io::Stdout) -> io::Result<()> {
+    if !settings.splash_enabled() {
+        return Ok(());
+    }
+    let (cols, rows) = terminal::size().map_or((80, 24), |(c, r)| (c as usize, r as usize));
+    let mut theme = settings.menu_theme.fit(rows);
+    // A banner wider than the terminal would wrap into a mess
+    if theme.banner().iter().any(|line| line.chars().count() + 2 > cols) {
+        theme = MenuTheme::Minimal;
+    }
+    let (banner_color, _, detail_color) = theme.colors();
+    let width = menu::SPLASH_CRAWL_WIDTH.min(cols.saturating_sub(4));
+    let credits: String = env!("CARGO_PKG_DESCRIPTION").chars().take(cols.saturating_sub(4)).collect();
+
+    for (step, frame_ms) in menu::splash_timeline(width, settings.reduced_motion) {
+        let mut buf = String::from("\r\n");
+        for line in theme.banner() {
+            buf.push_str(&format!("  {}\r\n", line.with(banner_color)));
+        }
+        buf.push_str(&format!("\r\n  {}\r\n", format!("v{}", env!("CARGO_PKG_VERSION")).with(detail_color)));
+        buf.push_str(&format!("  {}\r\n\r\n", credits.as_str().with(Color::DarkGrey)));
+        buf.push_str(&format!("  {}\r\n\r\n", menu::splash_crawl(step, width).with(Color::Green)));
+        buf.push_str(&format!("  {}\r\n", "Press any key to skip".with(Color::DarkGrey)));
+        stdout.execute(cursor::MoveTo(0, 0))?;
+        stdout.execute(terminal::Clear(ClearType::All))?;
+        write!(stdout, "{buf}")?;
+        stdout.flush()?;
+        if session::read_key(Duration::from_millis(frame_ms)).is_some() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn show_menu_and_play(settings: &Settings, stdout: &mut io::Stdout, script: Option<&ScriptInput>, announcer: &mut Option<Announcer>) -> io::Result<()> {
+    // Menu choices such as the skin apply for the rest of the session
+    let mut settings = settings.clone();
+    let settings = &mut settings;
+    let mut session = Session::default();
     loop {
-        let choice = show_start_menu(settings, stdout)?;
+        let choice = show_start_menu(settings, stdout, &session)?;
         match choice {
-            MenuChoice::Play => {
-                run_game(settings, stdout)?;
+            MenuChoice::Resume => {
+                // A corrupt recovery file is discarded rather than blocking the menu
+                match Snapshot::load(&snapshot::recovery_path()) {
+                    Ok(snap) => run_game(settings, stdout, Some(snap), &mut session, script, announcer)?,
+                    Err(_) => {
+                        let _ = snapshot::clear_recovery(&snapshot::recovery_path());
+                    }
+                }
+            }
+            MenuChoice::Play(mode) => {
+                let mut mode_settings = mode.settings(settings);
+                if mode == GameMode::Custom && !show_options_menu(&mut mode_settings, stdout)? {
+                    continue;
+                }
+                match mode_settings.validate() {
+                    Ok(()) => run_game(&mode_settings, stdout, None, &mut session, script, announcer)?,
+                    Err(e) => show_notice(settings, stdout, mode.label(), &format!("Can't start: {e}"))?,
+                }
+            }
+            MenuChoice::Skins => {
+                if let Some(skin) = show_skin_menu(settings, stdout)? {
+                    settings.apply_skin(skin);
+                }
             }
+            MenuChoice::Heatmap => show_heatmap(settings, stdout)?,
             MenuChoice::Quit => return Ok(()),
         }
     }
 }
 
+#[derive(Clone, Copy)]
 enum MenuChoice {
-    Play,
+    Resume,
+    Play(GameMode),
+    Skins,
+    Heatmap,
     Quit,
 }
 
-fn show_start_menu(settings: &Settings, stdout: &mut io::Stdout) -> io::Result<MenuChoice> {
-    let items = ["Start Game", "Quit"];
+fn show_start_menu(settings: &Settings, stdout: &mut io::Stdout, session: &Session) -> io::Result<MenuChoice> {
+    let mut items = Vec::new();
+    if !settings.multiplayer && snapshot::recovery_path().exists() {
+        items.push(("Resume Previous Game", MenuChoice::Resume));
+    }
+    items.extend(GameMode::ALL.map(|mode| (mode.label(), MenuChoice::Play(mode))));
+    items.push(("Skins", MenuChoice::Skins));
+    items.push(("Death Heatmap", MenuChoice::Heatmap));
+    items.push(("Quit", MenuChoice::Quit));
     let mut selected = 0usize;
     let high = highscore::load_high_score();
 
@@ -84,20 +413,15 @@ fn show_start_menu(settings: &Settings, stdout: &mut io::Stdout) -> io::Result<M
         stdout.execute(cursor::MoveTo(0, 0))?;
         stdout.execute(terminal::Clear(ClearType::All))?;
 
+        let rows = terminal::size().map_or(usize::MAX, |(_, r)| r as usize);
+        let theme = settings.menu_theme.fit(rows);
+        let (banner_color, selected_color, detail_color) = theme.colors();
+
         let mut buf = String::new();
         buf.push_str("\r\n");
-        buf.push_str(&format!(
-            "{}",
-            "  ╔═══════════════════════════════╗\r\n".with(Color::Green)
-        ));
-        buf.push_str(&format!(
-            "{}",
-            "  ║     SNAKE — Terminal Edition  ║\r\n".with(Color::Green)
-        ));
-        buf.push_str(&format!(
-            "{}",
-            "  ╚═══════════════════════════════╝\r\n".with(Color::Green)
-        ));
+        for line in theme.banner() {
+            buf.push_str(&format!("  {}\r\n", line.with(banner_color)));
+        }
         buf.push_str("\r\n");
 
         if high > 0 {
@@ -107,18 +431,27 @@ fn show_start_menu(settings: &Settings, stdout: &mut io::Stdout) -> io::Result<M
                 high.to_string().with(Color::Yellow)
             ));
         }
+        if session.rounds > 0 {
+            buf.push_str(&format!(
+                "  {}  {}   {}  {}\r\n\r\n",
+                "Session Best:".with(Color::DarkYellow),
+                session.best.to_string().with(Color::Yellow),
+                "Streak:".with(Color::DarkYellow),
+                session.streak.to_string().with(Color::Yellow)
+            ));
+        }
 
         let mode = if settings.multiplayer { "Multiplayer" } else { "Singleplayer" };
-        buf.push_str(&format!("  Mode: {}\r\n", mode.with(Color::Cyan)));
+        buf.push_str(&format!("  Mode: {}\r\n", mode.with(detail_color)));
         buf.push_str(&format!(
             "  Map: {}x{}\r\n\r\n",
-            settings.map_width.to_string().with(Color::Cyan),
-            settings.map_height.to_string().with(Color::Cyan)
+            settings.map_width.to_string().with(detail_color),
+            settings.map_height.to_string().with(detail_color)
         ));
 
-        for (i, item) in items.iter().enumerate() {
+        for (i, (item, _)) in items.iter().enumerate() {
             if i == selected {
-                buf.push_str(&format!("  {} {}\r\n", ">".with(Color::Yellow), item.with(Color::Yellow)));
+                buf.push_str(&format!("  {} {}\r\n", ">".with(selected_color), item.with(selected_color)));
             } else {
                 buf.push_str(&format!("    {}\r\n", item.with(Color::White)));
             }
@@ -132,151 +465,414 @@ fn show_start_menu(settings: &Settings, stdout: &mut io::Stdout) -> io::Result<M
         write!(stdout, "{buf}")?;
         stdout.flush()?;
 
-        match poll_menu_input(Duration::from_millis(100)) {
+        match poll_menu_input(settings.menu_poll_interval()) {
             MenuInput::Up => {
-                if selected > 0 {
-                    selected -= 1;
-                }
+                selected = selected.saturating_sub(1);
             }
             MenuInput::Down => {
                 if selected < items.len() - 1 {
                     selected += 1;
                 }
             }
+            MenuInput::Enter => return Ok(items[selected].1),
+            MenuInput::Quit => return Ok(MenuChoice::Quit),
+            MenuInput::Left | MenuInput::Right | MenuInput::None => {}
+        }
+    }
+}
+
+/// Skin picker: locked skins are greyed out with their unlock score.
+/// Returns the chosen skin, or None if the player backed out.
+fn show_skin_menu(settings: &Settings, stdout: &mut io::Stdout) -> io::Result<Option<Skin>> {
+    let high = highscore::load_high_score();
+    let (skins, unlocks) = (Skin::value_variants(), &settings.skin_unlocks.0);
+    let mut selected = skins.iter().position(|&s| s == settings.skin).unwrap_or(0);
+
+    loop {
+        stdout.execute(cursor::MoveTo(0, 0))?;
+        stdout.execute(terminal::Clear(ClearType::All))?;
+
+        let mut buf = String::new();
+        buf.push_str(&format!("\r\n  {}\r\n\r\n", "Skins".with(Color::Green)));
+        for (i, &skin) in skins.iter().enumerate() {
+            let (body, heads) = skin.glyphs();
+            let preview = format!("{body}{body}{body}{}", heads[2]);
+            let marker = if i == selected { ">" } else { " " };
+            let row = format!("{:<10}{preview}", skin.label());
+            if skin::is_unlocked(skin, high, unlocks) {
+                let color = if i == selected { Color::Yellow } else { Color::White };
+                let current = if skin == settings.skin { "  (current)" } else { "" };
+                buf.push_str(&format!("  {} {}{}\r\n", marker.with(Color::Yellow), row.with(color), current));
+            } else {
+                let locked = format!("{row}  locked: high score {}", skin::unlock_threshold(skin, unlocks));
+                buf.push_str(&format!("  {} {}\r\n", marker.with(Color::Yellow), locked.with(Color::DarkGrey)));
+            }
+        }
+        buf.push_str(&format!(
+            "\r\n  {}\r\n",
+            "Enter to apply, Q/Esc to go back".with(Color::DarkGrey)
+        ));
+
+        write!(stdout, "{buf}")?;
+        stdout.flush()?;
+
+        match poll_menu_input(settings.menu_poll_interval()) {
+            MenuInput::Up => selected = selected.saturating_sub(1),
+            MenuInput::Down => selected = (selected + 1).min(skins.len() - 1),
             MenuInput::Enter => {
-                return Ok(match selected {
-                    0 => MenuChoice::Play,
-                    _ => MenuChoice::Quit,
-                });
+                let skin = skins[selected];
+                if skin::is_unlocked(skin, high, unlocks) {
+                    return Ok(Some(skin));
+                }
             }
-            MenuInput::Quit => return Ok(MenuChoice::Quit),
-            MenuInput::None => {}
+            MenuInput::Quit => return Ok(None),
+            MenuInput::Left | MenuInput::Right | MenuInput::None => {}
         }
     }
 }
 
-fn run_game(settings: &Settings, stdout: &mut io::Stdout) -> io::Result<()> {
-    let w = settings.map_width;
-    let h = settings.map_height;
+/// The Custom mode's options: left/right change the highlighted option,
+/// Enter on "Start" plays. Returns false if the player backed out.
+fn show_options_menu(settings: &mut Settings, stdout: &mut io::Stdout) -> io::Result<bool> {
+    let mut selected = 0usize;
+    let start_row = CustomOption::ALL.len();
 
-    let mut snake1 = Snake::new(w, h);
-    let mut snake2 = if settings.multiplayer {
-        // Place P1 on upper third, P2 on lower third so they don't collide
-        snake1.init_at(h / 3, w / 2 - config::INITIAL_SNAKE_LENGTH / 2, config::Direction::East, false);
-        let mut s = Snake::new(w, h);
-        s.init_at(2 * h / 3, w / 2 + config::INITIAL_SNAKE_LENGTH / 2, config::Direction::West, true);
-        Some(s)
-    } else {
-        None
+    loop {
+        stdout.execute(cursor::MoveTo(0, 0))?;
+        stdout.execute(terminal::Clear(ClearType::All))?;
+
+        let mut buf = String::new();
+        buf.push_str(&format!("\r\n  {}\r\n\r\n", "Custom Game".with(Color::Green)));
+        for (i, option) in CustomOption::ALL.iter().enumerate() {
+            let marker = if i == selected { ">" } else { " " };
+            let color = if i == selected { Color::Yellow } else { Color::White };
+            buf.push_str(&format!(
+                "  {} {}< {} >\r\n",
+                marker.with(Color::Yellow),
+                format!("{:<18}", option.label()).with(color),
+                option.value(settings).with(Color::Cyan)
+            ));
+        }
+        let start = if selected == start_row { "> Start".with(Color::Yellow) } else { "  Start".with(Color::White) };
+        buf.push_str(&format!("\r\n  {start}\r\n"));
+        buf.push_str(&format!(
+            "\r\n  {}\r\n",
+            "A/D or arrows to change, Enter on Start to play, Q/Esc to go back".with(Color::DarkGrey)
+        ));
+
+        write!(stdout, "{buf}")?;
+        stdout.flush()?;
+
+        match poll_menu_input(settings.menu_poll_interval()) {
+            MenuInput::Up => selected = selected.saturating_sub(1),
+            MenuInput::Down => selected = (selected + 1).min(start_row),
+            MenuInput::Left | MenuInput::Right | MenuInput::Enter if CustomOption::ALL.get(selected) == Some(&CustomOption::Skin) => {
+                if let Some(skin) = show_skin_menu(settings, stdout)? {
+                    settings.apply_skin(skin);
+                }
+            }
+            input @ (MenuInput::Left | MenuInput::Right) if selected < start_row => {
+                CustomOption::ALL[selected].adjust(settings, input == MenuInput::Right);
+            }
+            MenuInput::Enter if selected == start_row => return Ok(true),
+            MenuInput::Enter if selected < start_row => CustomOption::ALL[selected].adjust(settings, true),
+            MenuInput::Quit => return Ok(false),
+            _ => {}
+        }
+    }
+}
+
+/// First-launch (or `--setup`) wizard: speed, map size and glyphs, saved to
+/// the default config file and used for this session too. Q/Esc skips it
+/// without saving, so a first launch asks again next time.
+fn run_setup_wizard(settings: &mut Settings, stdout: &mut io::Stdout) -> io::Result<()> {
+    let path = config::default_config_path();
+    let existing = path.as_ref().and_then(|p| std::fs::read_to_string(p).ok()).unwrap_or_default();
+    let mut answers = SetupAnswers::from_config(&existing);
+    let mut selected = 0usize;
+    let save_row = SetupOption::ALL.len();
+
+    loop {
+        stdout.execute(cursor::MoveTo(0, 0))?;
+        stdout.execute(terminal::Clear(ClearType::All))?;
+
+        let mut buf = String::new();
+        buf.push_str(&format!("\r\n  {}\r\n\r\n", "Setup".with(Color::Green)));
+        for (i, option) in SetupOption::ALL.iter().enumerate() {
+            let marker = if i == selected { ">" } else { " " };
+            let color = if i == selected { Color::Yellow } else { Color::White };
+            buf.push_str(&format!(
+                "  {} {}< {} >\r\n",
+                marker.with(Color::Yellow),
+                format!("{:<18}", option.label()).with(color),
+                option.value(&answers).with(Color::Cyan)
+            ));
+        }
+        let save = if selected == save_row { "> Save".with(Color::Yellow) } else { "  Save".with(Color::White) };
+        buf.push_str(&format!("\r\n  {save}\r\n"));
+        buf.push_str(&format!(
+            "\r\n  {}\r\n",
+            "A/D or arrows to change, Enter on Save to keep, Q/Esc to skip".with(Color::DarkGrey)
+        ));
+
+        write!(stdout, "{buf}")?;
+        stdout.flush()?;
+
+        match poll_menu_input(settings.menu_poll_interval()) {
+            MenuInput::Up => selected = selected.saturating_sub(1),
+            MenuInput::Down => selected = (selected + 1).min(save_row),
+            input @ (MenuInput::Left | MenuInput::Right) if selected < save_row => {
+                SetupOption::ALL[selected].adjust(&mut answers, input == MenuInput::Right);
+            }
+            MenuInput::Enter if selected == save_row => break,
+            MenuInput::Enter if selected < save_row => SetupOption::ALL[selected].adjust(&mut answers, true),
+            MenuInput::Quit => return Ok(()),
+            _ => {}
+        }
+    }
+
+    answers.apply(settings);
+    let Some(path) = path else {
+        return show_notice(settings, stdout, "Setup", "No config directory on this system; settings kept for this session only");
     };
+    let written = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|()| std::fs::write(&path, answers.to_config(&existing)));
+    if let Err(e) = written {
+        show_notice(settings, stdout, "Setup", &format!("Couldn't save {}: {e}", path.display()))?;
+    }
+    Ok(())
+}
 
-    let mut game_map = GameMap::new(w, h);
-    let mut rng: StdRng = if settings.seed != 0 {
-        StdRng::seed_from_u64(settings.seed)
+/// A one-off message on its own screen; any key goes back.
+fn show_notice(settings: &Settings, stdout: &mut io::Stdout, title: &str, text: &str) -> io::Result<()> {
+    stdout.execute(cursor::MoveTo(0, 0))?;
+    stdout.execute(terminal::Clear(ClearType::All))?;
+    write!(
+        stdout,
+        "\r\n  {}\r\n\r\n  {}\r\n\r\n  {}\r\n",
+        title.with(Color::Green),
+        text.with(Color::Red),
+        "Press any key to go back".with(Color::DarkGrey)
+    )?;
+    stdout.flush()?;
+    while matches!(poll_menu_input(settings.menu_poll_interval()), MenuInput::None) {}
+    Ok(())
+}
+
+/// Where past runs ended, scaled to a fixed grid; any key goes back.
+fn show_heatmap(settings: &Settings, stdout: &mut io::Stdout) -> io::Result<()> {
+    let stats = Stats::load(&stats::stats_path());
+    stdout.execute(cursor::MoveTo(0, 0))?;
+    stdout.execute(terminal::Clear(ClearType::All))?;
+
+    let mut buf = String::new();
+    buf.push_str(&format!("\r\n  {}\r\n\r\n", "Death Heatmap".with(Color::Green)));
+    if stats.total_deaths() == 0 {
+        buf.push_str(&format!("  {}\r\n", "No deaths recorded yet.".with(Color::DarkGrey)));
     } else {
-        StdRng::from_entropy()
+        for line in stats::render_heatmap(&stats) {
+            buf.push_str(&format!("  {}\r\n", line.with(Color::Red)));
+        }
+        buf.push_str(&format!(
+            "\r\n  Deaths: {}   {}\r\n",
+            stats.total_deaths().to_string().with(Color::Yellow),
+            "(. cold  @ hot)".with(Color::DarkGrey)
+        ));
+    }
+    buf.push_str(&format!("\r\n  {}\r\n", "Press any key to go back".with(Color::DarkGrey)));
+    write!(stdout, "{buf}")?;
+    stdout.flush()?;
+
+    while matches!(poll_menu_input(settings.menu_poll_interval()), MenuInput::None) {}
+    Ok(())
+}
+
+fn run_game(
+    settings: &Settings,
+    stdout: &mut io::Stdout,
+    resume: Option<Snapshot>,
+    session: &mut Session,
+    script: Option<&ScriptInput>,
+    announcer: &mut Option<Announcer>,
+) -> io::Result<()> {
+    let result = play_rounds(settings, stdout, resume, session, script, announcer);
+    snapshot::finish_run(&snapshot::recovery_path(), &result);
+    result
+}
+
+fn play_rounds(
+    settings: &Settings,
+    stdout: &mut io::Stdout,
+    resume: Option<Snapshot>,
+    session: &mut Session,
+    script: Option<&ScriptInput>,
+    announcer: &mut Option<Announcer>,
+) -> io::Result<()> {
+    let (w, h) = match resume.as_ref().or(settings.layout.as_ref()) {
+        Some(snap) => (snap.width, snap.height),
+        None => (settings.map_width, settings.map_height),
     };
 
-    game_map.place_food(&mut snake1, &mut rng);
-    if settings.obstacles > 0 {
-        game_map.place_walls(settings.obstacles, &snake1, &mut rng);
-    }
+    let first_seed = settings.round_seed();
+    let mut round_seed = first_seed;
+    let mut rng = GameRng::seeded(settings.rng, round_seed);
+
+    let mut snake1 = Snake::new(w, h);
+    snake1.allow_reverse = settings.allow_reverse;
+    let mut snake2 = settings.multiplayer.then(|| {
+        let mut s = Snake::new(w, h);
+        s.allow_reverse = settings.allow_reverse;
+        s
+    });
+
+    let mut game_map = GameMap::new(w, h);
+    let mut bell = Bell::new(settings.no_bell);
+    let mut hook = EventHook::new(settings.on_event.as_deref());
+    let mut source: Box<dyn InputSource> = match script {
+        Some(script) => Box::new(script.clone()),
+        None => Box::new(Keyboard),
+    };
+    start_round(&mut game_map, &mut snake1, &mut snake2, settings, &mut rng)?;
 
-    let mut recorder = settings.record.as_ref().map(|_| Recorder::new());
+    let mut recorder = settings.record.as_ref().map(|_| Recorder::for_settings(settings, round_seed));
+    let mut round_map = settings.save_map.as_ref().map(|_| MapFile::capture(&game_map, &snake1));
     let mut paused = false;
     let mut frame_count: usize = 0;
 
+    if let Some(ref snap) = resume {
+        snap.restore_snake(&mut snake1, settings);
+        snap.restore_map(&mut game_map);
+        game_map.top_up_food(&[&snake1], game_map.food_target(settings), settings, &mut rng);
+        frame_count = snap.frame_count;
+    }
+    // Recovery saves only cover singleplayer runs
+    let mut autosave = if snake2.is_none() {
+        Autosave::new(settings.autosave_interval, Instant::now())
+    } else {
+        None
+    };
+    let mut stats = RunStats::default();
+    let mut round_start = Instant::now();
+    let mut time_attack = TimeAttack::new(settings.time_attack, round_start);
+    let mut idle = IdleTimeout::new(settings.idle_timeout, round_start);
+    let mut milestones = Milestones::new(settings.milestone_pause);
+    let mut pause_started: Option<Instant> = None;
+    let mut art_progress = [ArtProgress::default(); 2];
+    let mut steps = [StepAccumulator::default(); 2];
+    let mut slow = SlowDetector::new(settings.slow_margin);
+    // Lighter settings swapped in by --auto-degrade while running slow
+    let mut degraded: Option<Settings> = None;
+
     loop {
+        let mut time_up = false;
+        let mut idle_out = false;
+        let mut board_full = false;
+        let mut art_complete = false;
         // Main game loop
-        while !snake1.is_dead && snake2.as_ref().map_or(true, |s| !s.is_dead) {
-            let input = poll_input(settings, Duration::from_millis(1));
+        while !snake1.is_dead && snake2.as_ref().is_none_or(|s| !s.is_dead) {
+            let mut input = source.frame_input(settings, Duration::from_millis(1));
+            if let (GameInput::Move(_) | GameInput::MoveP2(_), Some(idle)) = (&input, idle.as_mut()) {
+                idle.reset(Instant::now());
+            }
+            if game_map.milestone.is_some() && !matches!(input, GameInput::None | GameInput::Quit) {
+                // Any key ends a milestone pause
+                game_map.milestone = None;
+                input = GameInput::Pause;
+            }
             match &input {
                 GameInput::Move(dir) => snake1.queue_direction(*dir),
-                GameInput::MoveP2(dir) => {
-                    if let Some(ref mut s2) = snake2 {
-                        s2.queue_direction(*dir);
-                    }
-                }
+                GameInput::MoveP2(dir) => match snake2 {
+                    Some(ref mut s2) => s2.queue_direction(*dir),
+                    // Until a --powerups snake splits, the arrows steer it whole
+                    None if snake1.twin.is_none() => snake1.queue_direction(*dir),
+                    None => snake1.queue_twin_direction(*dir),
+                },
                 GameInput::Pause => {
                     paused = !paused;
                     // Consume lingering events
                     let _ = poll_input(settings, Duration::from_millis(1));
                 }
                 GameInput::Quit => {
-                    if let (Some(rec), Some(path)) = (recorder.as_ref(), settings.record.as_ref()) {
-                        let _ = rec.save(path);
-                    }
+                    save_recording(&mut recorder, settings, &snake1, snake2.as_ref());
                     return Ok(());
                 }
-                GameInput::None => {}
+                GameInput::UseBomb if !paused => use_bomb(&mut game_map, &mut snake1, settings, &mut recorder),
+                GameInput::Rewind if !paused => use_rewind(&game_map, &mut snake1, &mut recorder),
+                GameInput::UseBomb | GameInput::Rewind | GameInput::None => {}
+            }
+
+            // Time spent paused doesn't count against the time-attack or idle clocks
+            if paused {
+                pause_started.get_or_insert_with(Instant::now);
+            } else if let Some(started) = pause_started.take() {
+                if let Some(ref mut ta) = time_attack {
+                    ta.extend(started.elapsed());
+                }
+                if let Some(ref mut idle) = idle {
+                    idle.extend(started.elapsed());
+                }
             }
 
             if paused {
                 // Render with pause overlay
-                stdout.execute(cursor::MoveTo(0, 0))?;
-                stdout.execute(terminal::Clear(ClearType::All))?;
                 let snakes_ref: Vec<&Snake> = if let Some(ref s2) = snake2 {
                     vec![&snake1, s2]
                 } else {
                     vec![&snake1]
                 };
-                let frame = game_map.render(&snakes_ref, settings, true, frame_count);
-                write!(stdout, "{frame}")?;
+                let frame = game_map.render(&snakes_ref, settings, true, frame_count, 0.0);
+                write!(stdout, "{}", game_map::compose_frame(&frame, true))?;
                 stdout.flush()?;
                 std::thread::sleep(Duration::from_millis(50));
                 continue;
             }
 
-            // Record input
+            // With --p2-speed the snakes step on their own clocks
+            let periods = player_speeds(settings, &game_map, &snake1, snake2.as_ref());
+            let tick_ms = periods[0].min(periods[1]);
+            let moves = [steps[0].advance(tick_ms, periods[0]), steps[1].advance(tick_ms, periods[1])];
+
+            let mut snakes: Vec<&mut Snake> = std::iter::once(&mut snake1).chain(snake2.as_mut()).collect();
+            let applied = game_map.step_snakes(&mut snakes, moves, settings);
+
+            // Record the turns that took effect, wherever they were typed:
+            // keys pressed during the frame delay are queued, not polled here
             if let Some(ref mut rec) = recorder {
-                let dir_input = match &input {
-                    GameInput::Move(d) => Some(*d),
-                    _ => None,
-                };
-                rec.record_frame(dir_input);
+                rec.record_frames(applied);
             }
-
-            snake1.apply_queued_input();
             if let Some(ref mut s2) = snake2 {
-                s2.apply_queued_input();
+                snake::collide(&mut snake1, s2, settings);
             }
 
-            let walls = game_map.walls.clone();
-            let border_min = game_map.border_min;
-            let border_max = game_map.border_max;
-
-            snake1.update_movement(settings, &walls, border_min, border_max);
-            if let Some(ref mut s2) = snake2 {
-                s2.update_movement(settings, &walls, border_min, border_max);
-                // Check P2 colliding with P1 body
-                if snake1.parts.contains(&s2.head) {
-                    s2.is_dead = true;
+            if snake1.is_dead || snake2.as_ref().is_some_and(|s| s.is_dead) {
+                announce(&mut [&mut bell, &mut hook, announcer], BellEvent::Death);
+                if time_attack.is_none() {
+                    break;
                 }
-                if s2.parts.contains(&snake1.head) {
-                    snake1.is_dead = true;
+                // Time attack: respawn and keep going until the clock runs out
+                if snake1.is_dead {
+                    let others: Vec<&Snake> = snake2.iter().collect();
+                    respawn_player(&mut snake1, 0, &game_map, &others, settings, &mut rng)?;
+                    game_map.clear_spawn_paths(&[&snake1], settings);
+                }
+                if let Some(ref mut s2) = snake2 {
+                    if s2.is_dead {
+                        respawn_player(s2, 1, &game_map, &[&snake1], settings, &mut rng)?;
+                        game_map.clear_spawn_paths(&[s2], settings);
+                    }
                 }
             }
 
-            if snake1.is_dead || snake2.as_ref().map_or(false, |s| s.is_dead) {
-                bell(stdout);
-                break;
-            }
-
-            if snake1.food_eaten {
-                bell(stdout);
-                game_map.place_food(&mut snake1, &mut rng);
-            }
-
-            // Bonus food
-            game_map.maybe_spawn_bonus(&snake1, &mut rng);
-            game_map.tick_bonus();
-            if game_map.check_bonus_eaten(&mut snake1) {
-                bell(stdout);
-            }
+            let mut snakes: Vec<&mut Snake> = std::iter::once(&mut snake1).chain(snake2.as_mut()).collect();
+            game_map.warp_snakes(&mut snakes, &mut rng);
 
-            // Shrinking border
+            // Shrink before food is settled, so new pellets land inside the new border
             if settings.shrinking_border {
                 game_map.update_shrinking_border(&snake1);
+                let mut snakes: Vec<&mut Snake> = std::iter::once(&mut snake1).chain(snake2.as_mut()).collect();
+                game_map.rehome_food(&mut snakes, settings, &mut rng);
                 // Check if snake is outside new border
                 let (bmin_r, bmin_c) = game_map.border_min;
                 let (bmax_r, bmax_c) = game_map.border_max;
@@ -284,72 +880,208 @@ fn run_game(settings: &Settings, stdout: &mut io::Stdout) -> io::Result<()> {
                     || snake1.head.1 < bmin_c || snake1.head.1 >= bmax_c
                 {
                     snake1.is_dead = true;
-                    bell(stdout);
+                    announce(&mut [&mut bell, &mut hook, announcer], BellEvent::Death);
+                    if time_attack.is_none() {
+                        break;
+                    }
+                    let others: Vec<&Snake> = snake2.iter().collect();
+                    respawn_player(&mut snake1, 0, &game_map, &others, settings, &mut rng)?;
+                    game_map.clear_spawn_paths(&[&snake1], settings);
+                }
+            }
+
+            // Food is shared: whichever head reaches it eats it
+            let mut snakes: Vec<&mut Snake> = std::iter::once(&mut snake1).chain(snake2.as_mut()).collect();
+            // Level-ups follow length (--progressive-speed), not board fill
+            let pace = settings.effective_speed(snakes[0].length, 0.0);
+            let food = game_map.resolve_food(&mut snakes, settings, &mut rng);
+            if food.eaten > 0 {
+                announce(&mut [&mut bell, &mut hook, announcer], BellEvent::Food);
+                stats.food_eaten += food.eaten;
+                if settings.effective_speed(snake1.length, 0.0) < pace {
+                    announce(&mut [&mut bell, &mut hook, announcer], BellEvent::LevelUp);
+                }
+            }
+            if settings.growing_board {
+                let snakes: Vec<&Snake> = std::iter::once(&snake1).chain(snake2.as_ref()).collect();
+                game_map.update_growing_board(&snakes);
+            }
+            if food.board_full {
+                // Nowhere left to put food: the player has filled the board
+                board_full = true;
+                break;
+            }
+
+            // Bonus food
+            let snakes: Vec<&Snake> = std::iter::once(&snake1).chain(snake2.as_ref()).collect();
+            game_map.maybe_spawn_bonus(&snakes, &mut rng);
+            game_map.tick_bonus();
+            let bonus_eaten = game_map.check_bonus_eaten(&mut snake1, settings)
+                || snake2.as_mut().is_some_and(|s2| game_map.check_bonus_eaten(s2, settings));
+            if bonus_eaten {
+                announce(&mut [&mut bell, &mut hook, announcer], BellEvent::Bonus);
+                stats.bonuses_eaten += 1;
+            }
+            let snakes: Vec<&Snake> = std::iter::once(&snake1).chain(snake2.as_ref()).collect();
+            game_map.maybe_spawn_bomb(&snakes, settings, &mut rng);
+            game_map.maybe_spawn_rewind(&snakes, settings, &mut rng);
+            game_map.maybe_spawn_split(&snakes, settings, &mut rng);
+            game_map.check_bomb_collected(&mut snake1);
+            game_map.check_rewind_collected(&mut snake1);
+            game_map.check_split_collected(&mut snake1);
+
+            // Coins: pick up what the heads landed on, then let the tails drop more
+            for snake in std::iter::once(&mut snake1).chain(snake2.as_mut()) {
+                game_map.collect_coins(snake, settings);
+            }
+            game_map.tick_coins();
+            let snakes: Vec<&Snake> = std::iter::once(&snake1).chain(snake2.as_ref()).collect();
+            game_map.drop_coins(&snakes, settings, &mut rng);
+
+            // Art mode: reward new coverage of the target picture
+            if let Some(ref art) = settings.art {
+                let snakes = std::iter::once(&mut snake1).chain(snake2.as_mut());
+                for (snake, progress) in snakes.zip(art_progress.iter_mut()) {
+                    let covered = art::overlap(&snake.parts, art);
+                    snake.art_points += progress.award(covered);
+                    snake.rescore(settings);
+                    art_complete |= covered == art.cells.len();
+                }
+                if art_complete {
                     break;
                 }
             }
 
+            if let Some(ref ta) = time_attack {
+                let now = Instant::now();
+                game_map.status = ta.status(now);
+                if ta.is_over(now) {
+                    time_up = true;
+                    break;
+                }
+            }
+            if idle.as_ref().is_some_and(|idle| idle.expired(Instant::now())) {
+                idle_out = true;
+                break;
+            }
+
             frame_count += 1;
+            if settings.metronome && settings.metronome_bell {
+                bell.ring(BellEvent::Beat);
+            }
+            if let Some(announcer) = announcer.as_mut() {
+                let snakes: Vec<&Snake> = std::iter::once(&snake1).chain(snake2.as_ref()).collect();
+                announcer.tick(&snakes, &game_map, settings);
+            }
+
+            if let Some(ref mut rec) = recorder {
+                let snakes_ref: Vec<&Snake> = std::iter::once(&snake1).chain(snake2.as_ref()).collect();
+                rec.checkpoint(&GameState::new(&snakes_ref, &game_map));
+            }
+
+            if let Some(ref mut autosave) = autosave {
+                if autosave.due(Instant::now()) {
+                    let _ = Snapshot::capture(&snake1, &game_map, frame_count).save(&snapshot::recovery_path());
+                }
+            }
+
+            let best = snake2.as_ref().map_or(snake1.score, |s2| snake1.score.max(s2.score));
+            if let Some(points) = milestones.as_mut().and_then(|m| m.crossed(best)) {
+                game_map.milestone = Some(points);
+                paused = true;
+                continue;
+            }
 
             // Render
-            stdout.execute(cursor::MoveTo(0, 0))?;
-            stdout.execute(terminal::Clear(ClearType::All))?;
+            let frame_start = Instant::now();
+            let view = degraded.as_ref().unwrap_or(settings);
             let snakes_ref: Vec<&Snake> = if let Some(ref s2) = snake2 {
                 vec![&snake1, s2]
             } else {
                 vec![&snake1]
             };
-            let frame = game_map.render(&snakes_ref, settings, false, frame_count);
-            write!(stdout, "{frame}")?;
-            stdout.flush()?;
+            let frame = game_map.render(&snakes_ref, view, false, frame_count, 0.0);
+            present(stdout, &frame, &mut bell)?;
 
-            // Frame delay with input polling
-            let effective_speed = settings.effective_speed(snake1.length);
-            let frame_duration = Duration::from_millis(effective_speed);
-            let mut remaining = frame_duration;
-            let poll_interval = Duration::from_millis(10);
-            while remaining > Duration::ZERO {
-                let wait = remaining.min(poll_interval);
-                match poll_input(settings, wait) {
+            // Frame delay with input polling, at the faster snake's pace
+            let periods = player_speeds(settings, &game_map, &snake1, snake2.as_ref());
+            let frame_duration = Duration::from_millis(periods[0].min(periods[1]));
+            let mut waited = Duration::ZERO;
+            for wait in poll_waits(frame_duration, settings.poll_interval) {
+                if view.smooth_move && !paused {
+                    // Redraw between ticks with the heads partway into their next cell
+                    let fraction = waited.as_secs_f32() / frame_duration.as_secs_f32();
+                    let snakes_ref: Vec<&Snake> = std::iter::once(&snake1).chain(snake2.as_ref()).collect();
+                    let frame = game_map.render(&snakes_ref, view, false, frame_count, fraction);
+                    write!(stdout, "{}", game_map::compose_frame(&frame, false))?;
+                    stdout.flush()?;
+                }
+                waited += wait;
+                let input = source.wait_input(settings, wait);
+                if let (GameInput::Move(_) | GameInput::MoveP2(_), Some(idle)) = (&input, idle.as_mut()) {
+                    idle.reset(Instant::now());
+                }
+                match input {
                     GameInput::Move(dir) => snake1.queue_direction(dir),
-                    GameInput::MoveP2(dir) => {
-                        if let Some(ref mut s2) = snake2 {
-                            s2.queue_direction(dir);
-                        }
-                    }
+                    GameInput::MoveP2(dir) => match snake2 {
+                        Some(ref mut s2) => s2.queue_direction(dir),
+                        None if snake1.twin.is_none() => snake1.queue_direction(dir),
+                        None => snake1.queue_twin_direction(dir),
+                    },
                     GameInput::Pause => paused = !paused,
                     GameInput::Quit => {
-                        if let (Some(rec), Some(path)) = (recorder.as_ref(), settings.record.as_ref()) {
-                            let _ = rec.save(path);
-                        }
+                        save_recording(&mut recorder, settings, &snake1, snake2.as_ref());
                         return Ok(());
                     }
-                    GameInput::None => {}
+                    GameInput::UseBomb if !paused => use_bomb(&mut game_map, &mut snake1, settings, &mut recorder),
+                    GameInput::Rewind if !paused => use_rewind(&game_map, &mut snake1, &mut recorder),
+                    GameInput::UseBomb | GameInput::Rewind | GameInput::None => {}
                 }
-                remaining = remaining.saturating_sub(wait);
             }
+
+            if let Some(slow) = slow.as_mut() {
+                slow.record(frame_start.elapsed(), frame_duration);
+                game_map.running_slow = slow.is_slow();
+                if settings.auto_degrade && slow.is_slow() && degraded.is_none() {
+                    degraded = Some(Settings { no_color: true, smooth_move: false, ..settings.clone() });
+                }
+            }
+        }
+
+        let survived = time_up || idle_out || board_full || art_complete;
+        if let Some(announcer) = announcer.as_mut() {
+            let scores: Vec<usize> = std::iter::once(&snake1).chain(snake2.as_ref()).map(|s| s.score).collect();
+            announcer.game_over(&scores);
         }
 
         // Death animation (6 frames of flashing)
-        {
+        if !survived {
             let snakes_ref: Vec<&Snake> = if let Some(ref s2) = snake2 {
                 vec![&snake1, s2]
             } else {
                 vec![&snake1]
             };
             for i in 0..6 {
-                stdout.execute(cursor::MoveTo(0, 0))?;
-                stdout.execute(terminal::Clear(ClearType::All))?;
                 let frame = game_map.render_death_animation(&snakes_ref, settings, i);
-                write!(stdout, "{frame}")?;
-                stdout.flush()?;
+                present(stdout, &frame, &mut bell)?;
                 std::thread::sleep(Duration::from_millis(150));
             }
         }
 
-        // Save recording
-        if let (Some(rec), Some(path)) = (recorder.as_ref(), settings.record.as_ref()) {
-            let _ = rec.save(path);
+        save_recording(&mut recorder, settings, &snake1, snake2.as_ref());
+
+        // Persistence problems are shown on the game-over screen, not fatal
+        let mut save_warnings = Vec::new();
+
+        // Feed the death heat map
+        if !survived {
+            let mut lifetime = Stats::load(&stats::stats_path());
+            for snake in std::iter::once(&snake1).chain(snake2.as_ref()).filter(|s| s.is_dead) {
+                lifetime.record_death(snake.head, game_map.width, game_map.height);
+            }
+            if let Err(e) = highscore::save_data_file(stats::STATS_FILE, |path| lifetime.save(path)) {
+                save_warnings.push(format!("Could not save stats: {e}"));
+            }
         }
 
         // Update high score
@@ -358,7 +1090,19 @@ fn run_game(settings: &Settings, stdout: &mut io::Stdout) -> io::Result<()> {
         } else {
             snake1.score
         };
-        let (high, is_new) = update_high_score(best_score);
+        let (high, is_new, saved) = update_high_score(best_score);
+        if let Err(e) = saved {
+            save_warnings.push(format!("Could not save high score: {e}"));
+        }
+        if let (Some(path), Some(map)) = (&settings.save_map, &round_map) {
+            if let Err(e) = map.save(path) {
+                save_warnings.push(format!("Could not save map: {e}"));
+            }
+        }
+        session.record_round(best_score);
+        stats.final_length = snake1.length;
+        stats.score = snake1.score;
+        stats.elapsed = round_start.elapsed();
 
         // Game over screen
         stdout.execute(cursor::MoveTo(0, 0))?;
@@ -369,7 +1113,7 @@ fn run_game(settings: &Settings, stdout: &mut io::Stdout) -> io::Result<()> {
             } else {
                 vec![&snake1]
             };
-            let frame = game_map.render(&snakes_ref, settings, false, frame_count);
+            let frame = game_map.render(&snakes_ref, settings, false, frame_count, 0.0);
             write!(stdout, "{frame}")?;
         }
 
@@ -381,35 +1125,58 @@ fn run_game(settings: &Settings, stdout: &mut io::Stdout) -> io::Result<()> {
             )?;
             stdout.flush()?;
             std::thread::sleep(Duration::from_secs(1));
-            snake1.reset();
-            if let Some(ref mut s2) = snake2 {
-                snake1.init_at(h / 3, w / 2 - config::INITIAL_SNAKE_LENGTH / 2, config::Direction::East, false);
-                s2.reset();
-                s2.init_at(2 * h / 3, w / 2 + config::INITIAL_SNAKE_LENGTH / 2, config::Direction::West, true);
-            }
-            game_map.place_food(&mut snake1, &mut rng);
-            game_map.border_min = (0, 0);
-            game_map.border_max = (h, w);
-            game_map.shrink_timer = 0;
+            restart_rng(&mut rng, &mut round_seed, first_seed, settings);
+            start_round(&mut game_map, &mut snake1, &mut snake2, settings, &mut rng)?;
             frame_count = 0;
-            recorder = settings.record.as_ref().map(|_| Recorder::new());
+            stats = RunStats::default();
+            round_start = Instant::now();
+            time_attack = TimeAttack::new(settings.time_attack, round_start);
+            idle = IdleTimeout::new(settings.idle_timeout, round_start);
+            milestones = Milestones::new(settings.milestone_pause);
+            art_progress = [ArtProgress::default(); 2];
+            steps = [StepAccumulator::default(); 2];
+            slow = SlowDetector::new(settings.slow_margin);
+            degraded = None;
+            game_map.running_slow = false;
+            recorder = settings.record.as_ref().map(|_| Recorder::for_settings(settings, round_seed));
+            round_map = settings.save_map.as_ref().map(|_| MapFile::capture(&game_map, &snake1));
             continue;
         }
 
         write!(stdout, "\r\n")?;
-        if snake2.is_some() {
+        let header = if board_full {
+            "PERFECT! Board cleared"
+        } else if art_complete {
+            "PICTURE COMPLETE!"
+        } else if time_up {
+            "TIME UP!"
+        } else if idle_out {
+            "IDLE TIMEOUT"
+        } else {
+            "GAME OVER!"
+        };
+        let header_color = if board_full || art_complete { Color::Green } else { Color::Red };
+        if let Some(ref s2) = snake2 {
             write!(
                 stdout,
                 "  {}  P1: {}  P2: {}\r\n",
-                "GAME OVER!".with(Color::Red),
+                header.with(header_color),
                 snake1.score.to_string().with(Color::Green),
-                snake2.as_ref().unwrap().score.to_string().with(Color::Cyan),
+                s2.score.to_string().with(Color::Cyan),
             )?;
+            // The round ends on the first death, so a snake still alive outlived the other
+            let result = |s: &Snake| PlayerResult {
+                score: s.score,
+                length: s.length,
+                survived_frames: if s.is_dead { frame_count } else { frame_count + 1 },
+            };
+            let outcome = decide_winner(&result(&snake1), &result(s2), settings.tie_breaker);
+            write!(stdout, "  {}\r\n", outcome.message().with(Color::Yellow))?;
         } else {
             write!(
                 stdout,
                 "  {}  Score: {}\r\n",
-                "GAME OVER!".with(Color::Red),
+                header.with(header_color),
                 snake1.score.to_string().with(Color::Yellow),
             )?;
         }
@@ -420,6 +1187,35 @@ fn run_game(settings: &Settings, stdout: &mut io::Stdout) -> io::Result<()> {
             high.to_string().with(Color::Yellow),
             if is_new { " (NEW!)" } else { "" }
         )?;
+        write!(
+            stdout,
+            "  Session Best: {}  Streak: {}\r\n",
+            session.best.to_string().with(Color::Yellow),
+            session.streak.to_string().with(Color::Yellow)
+        )?;
+        if settings.show_run_code {
+            write!(
+                stdout,
+                "  Seed: {}  Run code: {}\r\n",
+                round_seed.to_string().with(Color::Yellow),
+                RunCode::capture(settings, round_seed).encode().with(Color::White)
+            )?;
+        }
+        for warning in &save_warnings {
+            write!(stdout, "  {}\r\n", warning.as_str().with(Color::DarkYellow))?;
+        }
+        if snake2.is_none() {
+            write!(stdout, "\r\n")?;
+            for (label, value) in stats.summary_rows() {
+                write!(
+                    stdout,
+                    "  {}{}\r\n",
+                    format!("{:<12}", format!("{label}:")).with(Color::DarkGrey),
+                    value.with(Color::White)
+                )?;
+            }
+            write!(stdout, "\r\n")?;
+        }
         write!(
             stdout,
             "  {}\r\n",
@@ -427,22 +1223,26 @@ fn run_game(settings: &Settings, stdout: &mut io::Stdout) -> io::Result<()> {
         )?;
         stdout.flush()?;
 
+        let timeout = IdleTimeout::new(settings.gameover_timeout, Instant::now());
         loop {
-            match poll_game_over_input() {
+            let input = poll_game_over_input(settings.menu_poll_interval());
+            match game_over_timeout(input, timeout.as_ref(), Instant::now()) {
                 GameOverInput::Restart => {
-                    snake1.reset();
-                    if let Some(ref mut s2) = snake2 {
-                        snake1.init_at(h / 3, w / 2 - config::INITIAL_SNAKE_LENGTH / 2, config::Direction::East, false);
-                        s2.reset();
-                        s2.init_at(2 * h / 3, w / 2 + config::INITIAL_SNAKE_LENGTH / 2, config::Direction::West, true);
-                    }
-                    game_map.place_food(&mut snake1, &mut rng);
-                    game_map.border_min = (0, 0);
-                    game_map.border_max = (h, w);
-                    game_map.shrink_timer = 0;
-                    game_map.bonus_food = None;
+                    restart_rng(&mut rng, &mut round_seed, first_seed, settings);
+                    start_round(&mut game_map, &mut snake1, &mut snake2, settings, &mut rng)?;
                     frame_count = 0;
-                    recorder = settings.record.as_ref().map(|_| Recorder::new());
+                    stats = RunStats::default();
+                    round_start = Instant::now();
+                    time_attack = TimeAttack::new(settings.time_attack, round_start);
+                    idle = IdleTimeout::new(settings.idle_timeout, round_start);
+                    milestones = Milestones::new(settings.milestone_pause);
+                    art_progress = [ArtProgress::default(); 2];
+                    steps = [StepAccumulator::default(); 2];
+                    slow = SlowDetector::new(settings.slow_margin);
+                    degraded = None;
+                    game_map.running_slow = false;
+                    recorder = settings.record.as_ref().map(|_| Recorder::for_settings(settings, round_seed));
+                    round_map = settings.save_map.as_ref().map(|_| MapFile::capture(&game_map, &snake1));
                     break;
                 }
                 GameOverInput::Menu => return Ok(()),
@@ -453,94 +1253,193 @@ fn run_game(settings: &Settings, stdout: &mut io::Stdout) -> io::Result<()> {
     }
 }
 
+/// Bring a player back after a `--time-attack` death, keeping their score,
+/// on open cells of the board as it is now (see `GameMap::open_spawn`).
+fn respawn_player(snake: &mut Snake, player: usize, game_map: &GameMap, others: &[&Snake], settings: &Settings, rng: &mut GameRng) -> io::Result<()> {
+    let (head, dir) = settings.round_spawn(player, snake.map_width, snake.map_height, rng);
+    let head = game_map.open_spawn(head, dir, others, snake.food, rng);
+    snake
+        .respawn(settings, |s| s.spawn_fresh(head, dir, settings))
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+/// Spawn the players and lay out the board for a round (see
+/// `GameMap::start_round`).
+fn start_round(game_map: &mut GameMap, snake1: &mut Snake, snake2: &mut Option<Snake>, settings: &Settings, rng: &mut GameRng) -> io::Result<()> {
+    let mut snakes: Vec<&mut Snake> = std::iter::once(snake1).chain(snake2.as_mut()).collect();
+    game_map
+        .start_round(&mut snakes, settings, rng)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+/// Spend one of player 1's bomb charges, noting it in the recording so
+/// playback clears the same walls.
+fn use_bomb(game_map: &mut GameMap, snake: &mut Snake, settings: &Settings, recorder: &mut Option<Recorder>) {
+    if snake.bomb_charges == 0 {
+        return;
+    }
+    game_map.detonate(snake, settings.bomb_radius);
+    if let Some(rec) = recorder {
+        rec.record_event(FrameEvent::Bomb);
+    }
+}
+
+/// Spend one of player 1's rewind charges, noting it in the recording.
+fn use_rewind(game_map: &GameMap, snake: &mut Snake, recorder: &mut Option<Recorder>) {
+    if snake.rewind(&game_map.walls, game_map.border_min, game_map.border_max) {
+        if let Some(rec) = recorder {
+            rec.record_event(FrameEvent::Rewind);
+        }
+    }
+}
+
+/// Write the recording, if any, with the round's final scores.
+fn save_recording(recorder: &mut Option<Recorder>, settings: &Settings, snake1: &Snake, snake2: Option<&Snake>) {
+    if let (Some(rec), Some(path)) = (recorder.as_mut(), settings.record.as_ref()) {
+        let scores: Vec<usize> = std::iter::once(snake1).chain(snake2).map(|s| s.score).collect();
+        rec.set_scores(&scores);
+        let _ = rec.save(path);
+    }
+}
+
+/// Reseed the RNG for a restarted round (see `Settings::next_round_seed`).
+fn restart_rng(rng: &mut GameRng, round_seed: &mut u64, first_seed: u64, settings: &Settings) {
+    if let Some(seed) = settings.next_round_seed(first_seed, rng) {
+        *round_seed = seed;
+        *rng = GameRng::seeded(settings.rng, seed);
+    }
+}
+
+/// Step periods for both players on the board as it stands.
+fn player_speeds(settings: &Settings, game_map: &GameMap, snake1: &Snake, snake2: Option<&Snake>) -> [u64; 2] {
+    let snakes: Vec<&Snake> = std::iter::once(snake1).chain(snake2).collect();
+    settings.player_speeds(snake1.length, snake2.map(|s| s.length), game_map.fill_fraction(&snakes))
+}
+
 fn run_replay(settings: &Settings, stdout: &mut io::Stdout) -> io::Result<()> {
     let path = settings.replay.as_ref().unwrap();
     let mut player = match Player::load(path) {
         Ok(p) => p,
         Err(e) => {
-            let _ = stdout.execute(cursor::Show);
-            let _ = stdout.execute(terminal::LeaveAlternateScreen);
+            let _ = Screen::new(settings.no_alt_screen, settings.no_bracketed_paste).teardown(stdout);
             let _ = terminal::disable_raw_mode();
             eprintln!("Failed to load replay: {e}");
             std::process::exit(1);
         }
     };
 
-    let w = settings.map_width;
-    let h = settings.map_height;
-    let mut snake = Snake::new(w, h);
-    let mut game_map = GameMap::new(w, h);
-    let mut rng: StdRng = if settings.seed != 0 {
-        StdRng::seed_from_u64(settings.seed)
-    } else {
-        StdRng::seed_from_u64(42) // replays need deterministic food
-    };
-
-    game_map.place_food(&mut snake, &mut rng);
-    if settings.obstacles > 0 {
-        game_map.place_walls(settings.obstacles, &snake, &mut rng);
-    }
+    // The recording's own seed, board and mode flags win over the command line
+    let mut settings = settings.clone();
+    player.apply_meta(&mut settings);
+    let settings = &settings;
+    let recorded_scores = player.recorded_scores();
 
-    let mut frame_count: usize = 0;
+    let mut sim = ReplaySim::start(settings, &player);
+    let mut paused = false;
+    let mut finished = false;
+    let mut dirty = true;
+    let mut last_step = Instant::now();
+    let mut diverged: Option<usize> = None;
 
-    while !snake.is_dead {
-        // Check for quit
-        match poll_input(settings, Duration::from_millis(1)) {
-            GameInput::Quit => return Ok(()),
-            _ => {}
+    loop {
+        let input = poll_replay_input(settings, settings.poll_interval);
+        if settings.loop_replay && !matches!(input, ReplayInput::None) {
+            return Ok(()); // a looping display runs until any key
         }
+        dirty |= match input {
+            ReplayInput::Quit => return Ok(()),
+            ReplayInput::TogglePause => {
+                paused = !paused;
+                true
+            }
+            ReplayInput::StepForward if paused => {
+                finished = !sim.step(&mut player, settings);
+                true
+            }
+            ReplayInput::StepBack if paused => {
+                sim = ReplaySim::at_frame(settings, &mut player, sim.frame_count.saturating_sub(1));
+                finished = false;
+                true
+            }
+            _ => false,
+        };
 
-        match player.next_frame() {
-            Some(Some(dir)) => snake.queue_direction(dir),
-            Some(None) => {}
-            None => break, // replay ended
+        if !paused && !finished && last_step.elapsed() >= Duration::from_millis(settings.speed) {
+            finished = !sim.step(&mut player, settings);
+            // Stop on the last frame so it can be stepped back from
+            paused = finished;
+            last_step = Instant::now();
+            dirty = true;
         }
-
-        snake.apply_queued_input();
-        let walls = game_map.walls.clone();
-        snake.update_movement(settings, &walls, game_map.border_min, game_map.border_max);
-
-        if snake.is_dead {
-            break;
+        if !dirty {
+            continue;
         }
+        dirty = false;
 
-        if snake.food_eaten {
-            game_map.place_food(&mut snake, &mut rng);
+        // Note the first checkpoint whose hash doesn't match the simulation
+        if diverged.is_none() {
+            if let Some(expected) = player.checkpoint(sim.frame_count) {
+                if GameState::new(&sim.snakes(), &sim.map).state_hash() != expected {
+                    diverged = Some(sim.frame_count);
+                }
+            }
         }
 
-        frame_count += 1;
-
         stdout.execute(cursor::MoveTo(0, 0))?;
         stdout.execute(terminal::Clear(ClearType::All))?;
-        let frame = game_map.render(&[&snake], settings, false, frame_count);
+        let snakes_ref: Vec<&Snake> = std::iter::once(&sim.snake).chain(sim.snake2.as_ref()).collect();
+        let frame = sim.map.render(&snakes_ref, settings, false, sim.frame_count, 0.0);
         write!(stdout, "{frame}")?;
         write!(
             stdout,
             "  {}\r\n",
-            "REPLAY — press Q to exit".with(Color::DarkGrey)
+            format!("Frame {}/{}", sim.frame_count, player.total_frames()).with(Color::Cyan)
         )?;
-        stdout.flush()?;
-
-        std::thread::sleep(Duration::from_millis(settings.speed));
-    }
-
-    write!(
-        stdout,
-        "\r\n  {}  Final Score: {}\r\n",
-        "Replay finished.".with(Color::Yellow),
-        snake.score
-    )?;
-    write!(
-        stdout,
-        "  {}\r\n",
-        "Press any key to exit".with(Color::DarkGrey)
-    )?;
-    stdout.flush()?;
+        if let Some(note) = player.caption(sim.frame_count) {
+            write!(stdout, "  {}\r\n", note.with(Color::Yellow))?;
+        }
+        if let Some(frame) = diverged {
+            write!(
+                stdout,
+                "  {}\r\n",
+                format!("Replay diverged from the recording at frame {frame}").with(Color::Red)
+            )?;
+        }
+        if finished {
+            let scores = sim.scores();
+            let label = if scores.len() > 1 {
+                format!("Final Scores: P1 {}  P2 {}", scores[0], scores[1])
+            } else {
+                format!("Final Score: {}", scores[0])
+            };
+            write!(stdout, "  {}  {}\r\n", "Replay finished.".with(Color::Yellow), label)?;
+            if recorded_scores.as_ref().is_some_and(|recorded| *recorded != scores) {
+                write!(
+                    stdout,
+                    "  {}\r\n",
+                    "Scores differ from the ones the recording was saved with".with(Color::Red)
+                )?;
+            }
+        }
+        let help = if settings.loop_replay {
+            "REPLAY — looping, any key exits"
+        } else if paused {
+            "REPLAY — Space resume, '.'/',' step one frame, Q exit"
+        } else {
+            "REPLAY — Space pause, Q exit"
+        };
+        write!(stdout, "  {}\r\n", help.with(Color::DarkGrey))?;
 
-    loop {
-        match poll_input(settings, Duration::from_millis(100)) {
-            GameInput::None => {}
-            _ => return Ok(()),
+        if finished && settings.loop_replay {
+            write!(stdout, "  {}\r\n", "Looping...".with(Color::Cyan))?;
+            stdout.flush()?;
+            if !matches!(poll_replay_input(settings, config::LOOP_REPLAY_PAUSE), ReplayInput::None) {
+                return Ok(());
+            }
+            sim.restart(settings, &mut player);
+            (paused, finished, diverged, dirty) = (false, false, None, true);
+            last_step = Instant::now();
         }
+        stdout.flush()?;
     }
 }
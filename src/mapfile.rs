@@ -0,0 +1,249 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::config::{Direction, INITIAL_SNAKE_LENGTH};
+use crate::game_map::GameMap;
+use crate::snake::{spawn_cells, Snake};
+use crate::snapshot::Snapshot;
+
+pub const MAP_WALL: char = '#';
+pub const MAP_OPEN: char = '.';
+pub const MAP_START: char = 'S';
+pub const MAP_FOOD: char = 'F';
+pub const MAP_KEY: char = 'K';
+pub const MAP_GATE: char = 'G';
+pub const MAP_RESERVED: char = 'R';
+/// Player 1's head facing another way than east, which `S` stands for.
+pub const MAP_START_FACING: [(char, Direction); 4] =
+    [('^', Direction::North), ('v', Direction::South), ('<', Direction::West), ('>', Direction::East)];
+
+/// Hand-editable board for `--map-file` / `--save-map`: one text line per
+/// row, `#` for a wall, `.` for open floor, `S` for player 1's head at the
+/// start facing east (or `^`, `v`, `<`, `>` for the other ways), `F` for
+/// each starting pellet, an optional `K` key that lets the snake through the
+/// locked gate `G` once, and `R` for reserved floor that nothing ever
+/// spawns on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapFile {
+    pub width: usize,
+    pub height: usize,
+    pub walls: Vec<(usize, usize)>,
+    pub start: Option<(usize, usize)>,
+    pub start_dir: Direction,
+    pub food: Vec<(usize, usize)>,
+    pub key: Option<(usize, usize)>,
+    pub gate: Option<(usize, usize)>,
+    pub reserved: Vec<(usize, usize)>,
+}
+
+impl MapFile {
+    /// The board as a round starts: walls, player 1's head and the food.
+    pub fn capture(map: &GameMap, snake: &Snake) -> MapFile {
+        let mut food = vec![snake.food];
+        food.extend(map.extra_food.iter().copied().filter(|&pos| pos != snake.food));
+        MapFile {
+            width: map.width,
+            height: map.height,
+            walls: map.walls.clone(),
+            start: Some(snake.head),
+            start_dir: snake.direction,
+            food,
+            key: map.key,
+            gate: map.gate,
+            reserved: {
+                let mut reserved: Vec<(usize, usize)> = map.reserved.iter().copied().collect();
+                reserved.sort_unstable();
+                reserved
+            },
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<MapFile, String> {
+        let rows: Vec<&str> = text.lines().map(str::trim_end).filter(|l| !l.is_empty()).collect();
+        let height = rows.len();
+        let width = rows.first().map_or(0, |r| r.chars().count());
+        if width == 0 {
+            return Err("map file is empty".to_string());
+        }
+        let mut map = MapFile {
+            width,
+            height,
+            walls: Vec::new(),
+            start: None,
+            start_dir: Direction::East,
+            food: Vec::new(),
+            key: None,
+            gate: None,
+            reserved: Vec::new(),
+        };
+        for (r, row) in rows.iter().enumerate() {
+            if row.chars().count() != width {
+                return Err(format!("map row {} is {} cells wide, expected {width}", r + 1, row.chars().count()));
+            }
+            for (c, ch) in row.chars().enumerate() {
+                let facing = MAP_START_FACING.iter().find(|&&(glyph, _)| glyph == ch).map(|&(_, dir)| dir);
+                if let Some(dir) = facing.filter(|_| map.start.is_none()) {
+                    map.start = Some((r, c));
+                    map.start_dir = dir;
+                    continue;
+                }
+                match ch {
+                    MAP_WALL => map.walls.push((r, c)),
+                    MAP_OPEN => {}
+                    MAP_START if map.start.is_none() => map.start = Some((r, c)),
+                    _ if ch == MAP_START || facing.is_some() => {
+                        return Err(format!("more than one start (second at row {}, column {})", r + 1, c + 1));
+                    }
+                    MAP_FOOD => map.food.push((r, c)),
+                    MAP_RESERVED => map.reserved.push((r, c)),
+                    MAP_KEY if map.key.is_none() => map.key = Some((r, c)),
+                    MAP_GATE if map.gate.is_none() => map.gate = Some((r, c)),
+                    MAP_KEY | MAP_GATE => return Err(format!("more than one '{ch}' (second at row {}, column {})", r + 1, c + 1)),
+                    other => return Err(format!("unknown map cell '{other}' at row {}, column {}", r + 1, c + 1)),
+                }
+            }
+        }
+        map.check_start()?;
+        Ok(map)
+    }
+
+    /// Player 1's starting body has to fit on open floor, and the head
+    /// mustn't face straight into a wall.
+    fn check_start(&self) -> Result<(), String> {
+        let Some(head) = self.start else {
+            return Ok(());
+        };
+        let blocked = |pos: &(usize, usize)| self.walls.contains(pos) || self.gate == Some(*pos);
+        let body = spawn_cells(head, self.start_dir, INITIAL_SNAKE_LENGTH, self.width, self.height);
+        if body.is_none_or(|cells| cells.iter().any(blocked)) {
+            return Err(format!("the snake's starting body at row {}, column {} runs into a wall or off the map", head.0 + 1, head.1 + 1));
+        }
+        let (dr, dc) = self.start_dir.delta();
+        let ahead = (head.0 as i32 + dr, head.1 as i32 + dc);
+        if ahead.0 >= 0 && ahead.1 >= 0 && blocked(&(ahead.0 as usize, ahead.1 as usize)) {
+            return Err(format!("the snake starts at row {}, column {} facing straight into a wall", head.0 + 1, head.1 + 1));
+        }
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> io::Result<MapFile> {
+        let text = fs::read_to_string(path)?;
+        MapFile::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut grid = vec![vec![MAP_OPEN; self.width]; self.height];
+        for &(r, c) in &self.walls {
+            grid[r][c] = MAP_WALL;
+        }
+        for &(r, c) in &self.reserved {
+            grid[r][c] = MAP_RESERVED;
+        }
+        for &(r, c) in &self.food {
+            grid[r][c] = MAP_FOOD;
+        }
+        if let Some((r, c)) = self.key {
+            grid[r][c] = MAP_KEY;
+        }
+        if let Some((r, c)) = self.gate {
+            grid[r][c] = MAP_GATE;
+        }
+        if let Some((r, c)) = self.start {
+            grid[r][c] = match MAP_START_FACING.iter().find(|&&(_, dir)| dir == self.start_dir) {
+                Some(&(glyph, _)) if self.start_dir != Direction::East => glyph,
+                _ => MAP_START,
+            };
+        }
+        grid.iter().map(|row| row.iter().collect::<String>() + "\n").collect()
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+
+    /// Walls and full-board borders as a `--from-snapshot` style layout,
+    /// so restarts rebuild the board the same way.
+    pub fn layout(&self) -> Snapshot {
+        let mut map = GameMap::new(self.width, self.height);
+        map.walls = self.walls.clone();
+        Snapshot::capture(&Snake::new(self.width, self.height), &map, 0)
+    }
+
+    /// Replace the randomly seeded food with the map's pellets, if it has any.
+    pub fn place_food(&self, map: &mut GameMap, snakes: &mut [&mut Snake]) {
+        let Some((&first, rest)) = self.food.split_first() else {
+            return;
+        };
+        for snake in snakes.iter_mut() {
+            snake.food = first;
+        }
+        map.extra_food = rest.to_vec();
+    }
+
+    /// Mark the map's reserved cells, before anything is placed on the board.
+    pub fn place_reserved(&self, map: &mut GameMap) {
+        map.reserved = self.reserved.iter().copied().collect();
+    }
+
+    /// Put the map's key and gate back on the board for a new round.
+    pub fn place_lock(&self, map: &mut GameMap) {
+        map.key = self.key;
+        map.gate = self.gate;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Direction, INITIAL_SNAKE_LENGTH};
+
+    #[test]
+    fn test_save_map_round_trip() {
+        let mut map = GameMap::new(8, 5);
+        map.walls = vec![(0, 0), (0, 7), (2, 3), (4, 4)];
+        map.extra_food = vec![(3, 6)];
+        map.key = Some((2, 0));
+        map.gate = Some((3, 7));
+        map.reserved = [(0, 3), (0, 4)].into_iter().collect();
+        let mut snake = Snake::new(8, 5);
+        snake.spawn_at((1, 4), Direction::East, INITIAL_SNAKE_LENGTH);
+        snake.food = (4, 1);
+
+        let saved = MapFile::capture(&map, &snake);
+        let text = saved.to_text();
+        assert_eq!(text, "#..RR..#\n....S...\nK..#....\n......FG\n.F..#...\n");
+
+        let path = std::env::temp_dir().join(format!("snake-term-map-{}.txt", std::process::id()));
+        saved.save(&path).unwrap();
+        let loaded = MapFile::load(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!((loaded.width, loaded.height), (8, 5));
+        let mut walls = loaded.walls.clone();
+        walls.sort_unstable();
+        let mut expected = map.walls.clone();
+        expected.sort_unstable();
+        assert_eq!(walls, expected);
+        assert_eq!(loaded.start, Some(snake.head));
+        assert_eq!(loaded.food.len(), 2);
+        assert_eq!((loaded.key, loaded.gate), (map.key, map.gate));
+        assert_eq!(loaded.reserved, [(0, 3), (0, 4)]);
+
+        let mut board = GameMap::new(8, 5);
+        loaded.layout().restore_layout(&mut board);
+        assert_eq!(board.walls, loaded.walls);
+
+        // A start facing another way keeps its direction
+        snake.spawn_at((1, 2), Direction::North, INITIAL_SNAKE_LENGTH);
+        let facing_north = MapFile::capture(&map, &snake);
+        assert_eq!(facing_north.to_text().lines().nth(1), Some("..^....."));
+        let reloaded = MapFile::parse(&facing_north.to_text()).unwrap();
+        assert_eq!((reloaded.start, reloaded.start_dir), (Some((1, 2)), Direction::North));
+
+        assert!(MapFile::parse("##\n#\n").is_err());
+        assert!(MapFile::parse("S.\n.S\n").is_err());
+        assert!(MapFile::parse("...S.\n..^..\n").is_err());
+        assert!(MapFile::parse("x.\n").is_err());
+        assert!(MapFile::parse("GG\n").is_err());
+    }
+}
@@ -0,0 +1,387 @@
+use clap::ValueEnum;
+use crossterm::style::Color;
+
+use crate::config::{SetupAnswers, Settings};
+
+/// Rows the start menu needs below the banner (scores, mode, items, help).
+const MENU_BODY_ROWS: usize = 18;
+
+/// Columns the splash snake crawls across, and the pause between its steps.
+pub const SPLASH_CRAWL_WIDTH: usize = 40;
+pub const SPLASH_FRAME_MS: u64 = 40;
+const SPLASH_SNAKE_LENGTH: usize = 6;
+
+/// Obstacles for the Obstacles mode when the command line didn't ask for any.
+pub const MODE_OBSTACLES: usize = 10;
+
+/// Speed steps on the Custom options screen, in ms per frame.
+const CUSTOM_SPEED_STEP: u64 = 10;
+const CUSTOM_SPEED_RANGE: (u64, u64) = (30, 500);
+const CUSTOM_OBSTACLE_STEP: usize = 5;
+const CUSTOM_MAX_OBSTACLES: usize = 60;
+
+/// Map size steps in the setup wizard; stepping below the minimum goes
+/// back to auto-detect (0).
+const SETUP_SIZE_STEP: usize = 5;
+const SETUP_SIZE_RANGE: (usize, usize) = (10, 80);
+
+/// Glyphs the setup wizard cycles through.
+const SETUP_BODY_GLYPHS: &[char] = &['@', 'o', '#', '=', '█', '●'];
+const SETUP_FOOD_GLYPHS: &[char] = &['*', '+', '$', '%', '♦', '●'];
+
+const SNAKE_LOGO: &[&str] = &[
+    r" ____  _   _    _    _  _______ ",
+    r"/ ___|| \ | |  / \  | |/ / ____|",
+    r"\___ \|  \| | / _ \ | ' /|  _|  ",
+    r" ___) | |\  |/ ___ \| . \| |___ ",
+    r"|____/|_| \_/_/   \_\_|\_\_____|",
+];
+
+const BOX_BANNER: &[&str] = &[
+    "╔═══════════════════════════════╗",
+    "║     SNAKE — Terminal Edition  ║",
+    "╚═══════════════════════════════╝",
+];
+
+/// Banner and color scheme of the start menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MenuTheme {
+    /// Large ASCII-art logo in green
+    Classic,
+    /// Box-drawing banner in amber
+    Retro,
+    /// One-line title, for short terminals
+    Minimal,
+}
+
+impl MenuTheme {
+    pub fn banner(self) -> &'static [&'static str] {
+        match self {
+            MenuTheme::Classic => SNAKE_LOGO,
+            MenuTheme::Retro => BOX_BANNER,
+            MenuTheme::Minimal => &["SNAKE"],
+        }
+    }
+
+    /// (banner, highlighted item, detail values)
+    pub fn colors(self) -> (Color, Color, Color) {
+        match self {
+            MenuTheme::Classic => (Color::Green, Color::Yellow, Color::Cyan),
+            MenuTheme::Retro => (Color::DarkYellow, Color::Yellow, Color::DarkYellow),
+            MenuTheme::Minimal => (Color::White, Color::Yellow, Color::Cyan),
+        }
+    }
+
+    /// Fall back to the minimal theme when the banner wouldn't leave room
+    /// for the menu itself in a terminal `rows` tall.
+    pub fn fit(self, rows: usize) -> MenuTheme {
+        if self.banner().len() + MENU_BODY_ROWS > rows {
+            MenuTheme::Minimal
+        } else {
+            self
+        }
+    }
+}
+
+/// Frames in the splash animation: long enough for the snake to cross.
+pub fn splash_frames(width: usize) -> usize {
+    width + SPLASH_SNAKE_LENGTH
+}
+
+/// The splash as (crawl step, ms on screen) frames. `--reduced-motion`
+/// holds one still frame, the whole snake in view, for the same time.
+pub fn splash_timeline(width: usize, reduced_motion: bool) -> Vec<(usize, u64)> {
+    let frames = splash_frames(width);
+    if reduced_motion {
+        return vec![(SPLASH_SNAKE_LENGTH - 1, frames as u64 * SPLASH_FRAME_MS)];
+    }
+    (0..frames).map(|step| (step, SPLASH_FRAME_MS)).collect()
+}
+
+/// Frame `step` of the splash: a snake crawling left to right across
+/// `width` columns, head first.
+pub fn splash_crawl(step: usize, width: usize) -> String {
+    (0..width)
+        .map(|col| match step.checked_sub(col) {
+            Some(0) => '@',
+            Some(behind) if behind < SPLASH_SNAKE_LENGTH => 'o',
+            _ => ' ',
+        })
+        .collect()
+}
+
+/// Start-menu entries that launch a game, each with its own mode flags on
+/// top of the command-line settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameMode {
+    Classic,
+    Obstacles,
+    Multiplayer,
+    ShrinkingBorder,
+    /// Opens the options screen first
+    Custom,
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 5] =
+        [GameMode::Classic, GameMode::Obstacles, GameMode::Multiplayer, GameMode::ShrinkingBorder, GameMode::Custom];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GameMode::Classic => "Classic",
+            GameMode::Obstacles => "Obstacles",
+            GameMode::Multiplayer => "Multiplayer",
+            GameMode::ShrinkingBorder => "Shrinking Border",
+            GameMode::Custom => "Custom",
+        }
+    }
+
+    /// `base` with this mode's flags. The preset modes clear the flags the
+    /// others set, so each plays the same whatever the command line said;
+    /// Custom starts from `base` unchanged.
+    pub fn settings(self, base: &Settings) -> Settings {
+        let mut settings = base.clone();
+        if self == GameMode::Custom {
+            return settings;
+        }
+        settings.multiplayer = false;
+        settings.mirror_food = false;
+        settings.shrinking_border = false;
+        settings.growing_board = false;
+        settings.obstacles = 0;
+        match self {
+            GameMode::Obstacles => {
+                settings.obstacles = if base.obstacles > 0 { base.obstacles } else { MODE_OBSTACLES };
+            }
+            GameMode::Multiplayer => settings.multiplayer = true,
+            GameMode::ShrinkingBorder => settings.shrinking_border = true,
+            GameMode::Classic | GameMode::Custom => {}
+        }
+        settings
+    }
+}
+
+/// One row of the Custom options screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CustomOption {
+    Players,
+    Obstacles,
+    ShrinkingBorder,
+    Wrap,
+    ProgressiveSpeed,
+    Speed,
+    /// Opens the skin picker, which knows the high score and what's locked
+    Skin,
+}
+
+impl CustomOption {
+    pub const ALL: [CustomOption; 7] = [
+        CustomOption::Players,
+        CustomOption::Obstacles,
+        CustomOption::ShrinkingBorder,
+        CustomOption::Wrap,
+        CustomOption::ProgressiveSpeed,
+        CustomOption::Speed,
+        CustomOption::Skin,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CustomOption::Players => "Players",
+            CustomOption::Obstacles => "Obstacles",
+            CustomOption::ShrinkingBorder => "Shrinking border",
+            CustomOption::Wrap => "Wrap-around",
+            CustomOption::ProgressiveSpeed => "Speed up",
+            CustomOption::Speed => "Speed (ms)",
+            CustomOption::Skin => "Skin",
+        }
+    }
+
+    pub fn value(self, settings: &Settings) -> String {
+        let on_off = |on: bool| if on { "on" } else { "off" }.to_string();
+        match self {
+            CustomOption::Players => if settings.multiplayer { "2" } else { "1" }.to_string(),
+            CustomOption::Obstacles => settings.obstacles.to_string(),
+            CustomOption::ShrinkingBorder => on_off(settings.shrinking_border),
+            CustomOption::Wrap => on_off(settings.disable_borders),
+            CustomOption::ProgressiveSpeed => on_off(settings.progressive_speed),
+            CustomOption::Speed => settings.speed.to_string(),
+            CustomOption::Skin => settings.skin.label().to_string(),
+        }
+    }
+
+    /// Step the option left (`up` false) or right. Toggles flip either way.
+    pub fn adjust(self, settings: &mut Settings, up: bool) {
+        match self {
+            CustomOption::Players => {
+                settings.multiplayer = !settings.multiplayer;
+                settings.mirror_food &= settings.multiplayer;
+            }
+            CustomOption::Obstacles => {
+                settings.obstacles = if up {
+                    (settings.obstacles + CUSTOM_OBSTACLE_STEP).min(CUSTOM_MAX_OBSTACLES)
+                } else {
+                    settings.obstacles.saturating_sub(CUSTOM_OBSTACLE_STEP)
+                };
+            }
+            CustomOption::ShrinkingBorder => {
+                settings.shrinking_border = !settings.shrinking_border;
+                settings.growing_board &= !settings.shrinking_border;
+            }
+            CustomOption::Wrap => settings.disable_borders = !settings.disable_borders,
+            CustomOption::ProgressiveSpeed => {
+                settings.progressive_speed = !settings.progressive_speed;
+                // The two pacing modes can't be combined
+                settings.fill_speed &= !settings.progressive_speed;
+            }
+            CustomOption::Speed => {
+                let (min, max) = CUSTOM_SPEED_RANGE;
+                let speed = if up { settings.speed + CUSTOM_SPEED_STEP } else { settings.speed.saturating_sub(CUSTOM_SPEED_STEP) };
+                settings.speed = speed.clamp(min, max);
+            }
+            CustomOption::Skin => {} // chosen on the skin picker instead
+        }
+    }
+}
+
+/// One row of the setup wizard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetupOption {
+    Speed,
+    MapWidth,
+    MapHeight,
+    Body,
+    Food,
+}
+
+impl SetupOption {
+    pub const ALL: [SetupOption; 5] =
+        [SetupOption::Speed, SetupOption::MapWidth, SetupOption::MapHeight, SetupOption::Body, SetupOption::Food];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SetupOption::Speed => "Speed (ms)",
+            SetupOption::MapWidth => "Map width",
+            SetupOption::MapHeight => "Map height",
+            SetupOption::Body => "Body glyph",
+            SetupOption::Food => "Food glyph",
+        }
+    }
+
+    pub fn value(self, answers: &SetupAnswers) -> String {
+        let size = |n: usize| if n == 0 { "auto".to_string() } else { n.to_string() };
+        match self {
+            SetupOption::Speed => answers.speed.to_string(),
+            SetupOption::MapWidth => size(answers.map_width),
+            SetupOption::MapHeight => size(answers.map_height),
+            SetupOption::Body => answers.body.to_string(),
+            SetupOption::Food => answers.food.to_string(),
+        }
+    }
+
+    /// Step the answer left (`up` false) or right. Glyphs cycle through
+    /// their list, starting over from a glyph that isn't in it.
+    pub fn adjust(self, answers: &mut SetupAnswers, up: bool) {
+        match self {
+            SetupOption::Speed => {
+                let (min, max) = CUSTOM_SPEED_RANGE;
+                let speed = if up { answers.speed + CUSTOM_SPEED_STEP } else { answers.speed.saturating_sub(CUSTOM_SPEED_STEP) };
+                answers.speed = speed.clamp(min, max);
+            }
+            SetupOption::MapWidth => answers.map_width = step_size(answers.map_width, up),
+            SetupOption::MapHeight => answers.map_height = step_size(answers.map_height, up),
+            SetupOption::Body => answers.body = cycle_glyph(SETUP_BODY_GLYPHS, answers.body, up),
+            SetupOption::Food => answers.food = cycle_glyph(SETUP_FOOD_GLYPHS, answers.food, up),
+        }
+    }
+}
+
+fn step_size(size: usize, up: bool) -> usize {
+    let (min, max) = SETUP_SIZE_RANGE;
+    match (size, up) {
+        (0, true) => min,
+        (0, false) => 0,
+        (n, false) if n <= min => 0,
+        (n, true) => (n + SETUP_SIZE_STEP).min(max),
+        (n, false) => (n - SETUP_SIZE_STEP).max(min),
+    }
+}
+
+fn cycle_glyph(glyphs: &[char], current: char, up: bool) -> char {
+    match glyphs.iter().position(|&g| g == current) {
+        Some(i) if up => glyphs[(i + 1) % glyphs.len()],
+        Some(i) => glyphs[(i + glyphs.len() - 1) % glyphs.len()],
+        None => glyphs[0],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn test_reduced_motion_splash_holds_still() {
+        let moving = splash_timeline(20, false);
+        assert_eq!(moving.len(), splash_frames(20));
+        let still = splash_timeline(20, true);
+        assert_eq!(still.len(), 1);
+        // Just as long on screen, with the whole snake showing
+        let total = |frames: &[(usize, u64)]| frames.iter().map(|&(_, ms)| ms).sum::<u64>();
+        assert_eq!(total(&still), total(&moving));
+        assert_eq!(splash_crawl(still[0].0, 20).trim_end(), "ooooo@");
+    }
+
+    #[test]
+    fn test_minimal_theme_on_short_terminal() {
+        assert_eq!(MenuTheme::Classic.fit(40), MenuTheme::Classic);
+        assert_eq!(MenuTheme::Retro.fit(40), MenuTheme::Retro);
+
+        let classic_rows = SNAKE_LOGO.len() + MENU_BODY_ROWS;
+        assert_eq!(MenuTheme::Classic.fit(classic_rows), MenuTheme::Classic);
+        assert_eq!(MenuTheme::Classic.fit(classic_rows - 1), MenuTheme::Minimal);
+        // The shorter retro banner still fits where the logo doesn't
+        assert_eq!(MenuTheme::Retro.fit(classic_rows - 1), MenuTheme::Retro);
+        assert_eq!(MenuTheme::Minimal.fit(5), MenuTheme::Minimal);
+    }
+
+    #[test]
+    fn test_mode_entries_set_expected_flags() {
+        // Command-line flags the modes must override, and one they must keep
+        let base = Settings::parse_from(["test", "--multiplayer", "--shrinking-border", "--speed", "120"]).resolve();
+
+        let classic = GameMode::Classic.settings(&base);
+        assert!(!classic.multiplayer && !classic.shrinking_border);
+        assert_eq!((classic.obstacles, classic.speed), (0, 120));
+
+        let obstacles = GameMode::Obstacles.settings(&base);
+        assert_eq!(obstacles.obstacles, MODE_OBSTACLES);
+        assert!(!obstacles.multiplayer && !obstacles.shrinking_border);
+        let own = Settings::parse_from(["test", "--obstacles", "25"]).resolve();
+        assert_eq!(GameMode::Obstacles.settings(&own).obstacles, 25);
+
+        let multiplayer = GameMode::Multiplayer.settings(&base);
+        assert!(multiplayer.multiplayer && !multiplayer.shrinking_border);
+        assert_eq!(multiplayer.obstacles, 0);
+
+        let shrinking = GameMode::ShrinkingBorder.settings(&base);
+        assert!(shrinking.shrinking_border && !shrinking.multiplayer);
+
+        for mode in GameMode::ALL {
+            assert!(mode.settings(&base).validate().is_ok(), "{}", mode.label());
+        }
+
+        // Custom keeps the command line, then the options screen edits it
+        let mut custom = GameMode::Custom.settings(&base);
+        assert!(custom.multiplayer && custom.shrinking_border);
+        CustomOption::Players.adjust(&mut custom, true);
+        CustomOption::Obstacles.adjust(&mut custom, true);
+        CustomOption::Speed.adjust(&mut custom, false);
+        assert!(!custom.multiplayer);
+        assert_eq!((custom.obstacles, custom.speed), (CUSTOM_OBSTACLE_STEP, 110));
+        assert_eq!(CustomOption::Players.value(&custom), "1");
+        CustomOption::Obstacles.adjust(&mut custom, false);
+        CustomOption::Obstacles.adjust(&mut custom, false);
+        assert_eq!(custom.obstacles, 0);
+    }
+}
@@ -1,69 +1,1218 @@
 use std::fs;
-use std::io::{BufRead, BufReader, Write};
+use std::io::{self, Write};
 use std::path::Path;
 
-use crate::config::Direction;
+use clap::{Parser, ValueEnum};
+
+use crate::ai;
+use crate::art::{self, ArtProgress, ArtTarget};
+use crate::clock::StepAccumulator;
+use crate::config::{Direction, Settings, SpawnPoint, TwinMode};
+use crate::game_map::GameMap;
+use crate::mapfile::MapFile;
+use crate::rng::GameRng;
+use crate::snake::{self, Snake};
+use crate::snapshot::Snapshot;
+use crate::state::GameState;
+
+/// Frames between state-hash checkpoints in a recording.
+pub const CHECKPOINT_INTERVAL: usize = 25;
+
+/// Frames a replay note stays on screen, so it can be read at full speed.
+pub const NOTE_CAPTION_FRAMES: usize = 15;
+
+/// When a recording starts capturing frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum RecordFrom {
+    /// From the first frame of the round
+    Start,
+    /// From the first direction key; idle frames before it are only counted
+    FirstMove,
+}
+
+/// Inputs for one frame: player 1, then player 2 (always None solo).
+pub type FrameInput = [Option<Direction>; 2];
+
+/// Something player 1 did between two frames besides steering, which
+/// playback has to repeat to stay in step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameEvent {
+    /// Spent a bomb charge (`--bomb-radius`)
+    Bomb,
+    /// Spent a rewind charge (`--rewind`)
+    Rewind,
+}
+
+impl FrameEvent {
+    fn as_str(self) -> &'static str {
+        match self {
+            FrameEvent::Bomb => "bomb",
+            FrameEvent::Rewind => "rewind",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "bomb" => Some(FrameEvent::Bomb),
+            "rewind" => Some(FrameEvent::Rewind),
+            _ => None,
+        }
+    }
+}
 
 pub struct Recorder {
-    frames: Vec<Option<Direction>>,
+    frames: Vec<FrameInput>,
+    checkpoints: Vec<(usize, u64)>, // (frames played, state hash after them)
+    events: Vec<(usize, FrameEvent)>, // (frames played, event before the next)
+    from: RecordFrom,
+    lead_in: usize, // idle frames skipped before capture began
+    players: usize,
+    meta: Vec<(&'static str, String)>,
 }
 
 impl Recorder {
-    pub fn new() -> Self {
-        Recorder { frames: Vec::new() }
+    pub fn new(from: RecordFrom) -> Self {
+        Recorder { frames: Vec::new(), checkpoints: Vec::new(), events: Vec::new(), from, lead_in: 0, players: 1, meta: Vec::new() }
+    }
+
+    /// A recorder that also stores everything needed to rebuild the match:
+    /// the round's RNG `seed`, board size, player count and mode flags.
+    pub fn for_settings(settings: &Settings, seed: u64) -> Self {
+        let mut rec = Recorder::new(settings.record_from);
+        // An independent --two-headed second head is recorded as player 2
+        rec.players = if settings.multiplayer || settings.two_headed == Some(TwinMode::Independent) { 2 } else { 1 };
+        rec.meta = match_meta(settings, seed);
+        rec
+    }
+
+    pub fn record_frames(&mut self, input: FrameInput) {
+        if self.from == RecordFrom::FirstMove && self.frames.is_empty() && input == [None, None] {
+            self.lead_in += 1;
+            return;
+        }
+        self.frames.push(input);
     }
 
-    pub fn record_frame(&mut self, dir: Option<Direction>) {
-        self.frames.push(dir);
+    /// Note `event`, which happened after the latest frame.
+    pub fn record_event(&mut self, event: FrameEvent) {
+        self.events.push((self.frames.len(), event));
     }
 
-    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+    /// Final scores, saved so playback can show (and check) the result.
+    pub fn set_scores(&mut self, scores: &[usize]) {
+        self.meta.retain(|&(key, _)| key != "scores");
+        let scores: Vec<String> = scores.iter().map(usize::to_string).collect();
+        self.meta.push(("scores", scores.join(" ")));
+    }
+
+    /// Store the state hash after the latest frame every
+    /// `CHECKPOINT_INTERVAL` frames, so playback can spot divergence.
+    pub fn checkpoint(&mut self, state: &GameState) {
+        let frame = self.frames.len();
+        if frame > 0 && frame.is_multiple_of(CHECKPOINT_INTERVAL) && self.checkpoints.last().is_none_or(|&(f, _)| f != frame) {
+            self.checkpoints.push((frame, state.state_hash()));
+        }
+    }
+
+    /// One line per frame (a direction or '.', one character per player),
+    /// with `H <hash>` lines following the frame each checkpoint was taken
+    /// after and `E <event>` lines following the frame the event came
+    /// after. Skipped idle frames are written as a leading `L <count>` line,
+    /// then the match settings as `M <key> <value>` lines.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
         let mut f = fs::File::create(path)?;
-        for frame in &self.frames {
-            let ch = match frame {
-                Some(Direction::North) => 'N',
-                Some(Direction::South) => 'S',
-                Some(Direction::East) => 'E',
-                Some(Direction::West) => 'W',
-                None => '.',
-            };
-            writeln!(f, "{ch}")?;
+        if self.lead_in > 0 {
+            writeln!(f, "L {}", self.lead_in)?;
+        }
+        for (key, value) in &self.meta {
+            writeln!(f, "M {key} {value}")?;
+        }
+        let mut checkpoints = self.checkpoints.iter().peekable();
+        let mut events = self.events.iter().peekable();
+        // Events before the first frame still go after the settings
+        while let Some((_, event)) = events.next_if(|&&(at, _)| at == 0) {
+            writeln!(f, "E {}", event.as_str())?;
+        }
+        for (i, frame) in self.frames.iter().enumerate() {
+            let line: String = frame[..self.players].iter().map(|d| d.map_or('.', Direction::as_char)).collect();
+            writeln!(f, "{line}")?;
+            if let Some((_, hash)) = checkpoints.next_if(|&&(at, _)| at == i + 1) {
+                writeln!(f, "H {hash:016x}")?;
+            }
+            while let Some((_, event)) = events.next_if(|&&(at, _)| at == i + 1) {
+                writeln!(f, "E {}", event.as_str())?;
+            }
         }
         Ok(())
     }
 }
 
+pub fn value_name<T: ValueEnum>(value: &T) -> String {
+    value.to_possible_value().map(|v| v.get_name().to_string()).unwrap_or_default()
+}
+
+/// Settings that change how a match plays out, as replay header entries.
+pub fn match_meta(settings: &Settings, seed: u64) -> Vec<(&'static str, String)> {
+    let mut meta = vec![
+        ("seed", seed.to_string()),
+        ("rng", value_name(&settings.rng)),
+        ("width", settings.map_width.to_string()),
+        ("height", settings.map_height.to_string()),
+        ("players", if settings.multiplayer { "2" } else { "1" }.to_string()),
+        ("disable-borders", settings.disable_borders.to_string()),
+        ("edge-gaps", settings.edge_gaps.to_string()),
+        ("allow-reverse", settings.allow_reverse.to_string()),
+        ("assist", settings.assist.to_string()),
+        ("food-walls", settings.food_walls.to_string()),
+        ("deterministic-bonus", settings.deterministic_bonus.to_string()),
+        ("mirror-food", settings.mirror_food.to_string()),
+        ("obstacles", settings.obstacles.to_string()),
+        ("spawn-immunity", settings.spawn_immunity.to_string()),
+        ("warp-tiles", settings.warp_tiles.to_string()),
+        ("portals", settings.portals.to_string()),
+        ("food-count", settings.food_count.to_string()),
+        ("start-food", settings.start_food.to_string()),
+        ("auto-food", settings.auto_food.to_string()),
+        ("food-min-spread", settings.food_min_spread.to_string()),
+        ("food-values", settings.food_values.to_string()),
+        ("hungry", settings.hungry.to_string()),
+        ("max-length", settings.max_length.to_string()),
+        ("wrap-penalty", settings.wrap_penalty.to_string()),
+        ("wrap-limit", settings.wrap_limit.to_string()),
+        ("bomb-radius", settings.bomb_radius.to_string()),
+        ("rewind", settings.rewind.to_string()),
+        ("powerups", settings.powerups.to_string()),
+        ("coins", settings.coins.to_string()),
+        ("steal", settings.steal.to_string()),
+        ("growing-board", settings.growing_board.to_string()),
+        ("shrinking-border", settings.shrinking_border.to_string()),
+        ("style-points", settings.style_points.to_string()),
+        ("shape", value_name(&settings.shape)),
+        ("score-formula", settings.score_formula.to_string()),
+    ];
+    // Only written when set
+    let optional = [
+        ("start-pos", settings.start_pos.map(|p| p.to_string())),
+        ("p1-spawn", settings.p1_spawn.as_ref().map(value_name)),
+        ("p1-dir", settings.p1_dir.as_ref().map(value_name)),
+        ("p2-spawn", settings.p2_spawn.as_ref().map(value_name)),
+        ("p2-dir", settings.p2_dir.as_ref().map(value_name)),
+        ("p2-speed", settings.p2_speed.map(|ms| ms.to_string())),
+        ("two-headed", settings.two_headed.as_ref().map(value_name)),
+        // The map itself, rows split by '/', so the file plays anywhere
+        ("map", settings.map.as_ref().map(|map| map.to_text().lines().collect::<Vec<_>>().join("/"))),
+        // A --from-snapshot layout; a map file carries its own
+        ("layout", settings.layout.as_ref().filter(|_| settings.map.is_none()).map(Snapshot::layout_line)),
+        ("art", settings.art.as_ref().map(|art| art.cells.iter().map(|(r, c)| format!("{r},{c}")).collect::<Vec<_>>().join(" "))),
+        // Only a --p2-speed match depends on the pace; otherwise the viewer picks it
+        ("speed", settings.p2_speed.map(|_| settings.speed.to_string())),
+        ("progressive-speed", settings.p2_speed.map(|_| settings.progressive_speed.to_string())),
+        ("fill-speed", settings.p2_speed.map(|_| settings.fill_speed.to_string())),
+        ("speed-curve", settings.p2_speed.map(|_| value_name(&settings.speed_curve))),
+        ("speed-floor", settings.p2_speed.map(|_| settings.speed_floor.to_string())),
+    ];
+    meta.extend(optional.into_iter().filter_map(|(key, value)| Some((key, value?))));
+    meta
+}
+
+#[derive(Clone)]
 pub struct Player {
-    frames: Vec<Option<Direction>>,
+    frames: Vec<FrameInput>,
+    checkpoints: Vec<(usize, u64)>,
+    events: Vec<(usize, FrameEvent)>,
+    lead_in: usize,
+    meta: Vec<(String, String)>,
     index: usize,
 }
 
 impl Player {
-    pub fn load(path: &Path) -> std::io::Result<Self> {
-        let f = fs::File::open(path)?;
-        let reader = BufReader::new(f);
+    /// Load a recording written by `Recorder::save`. Empty, binary or
+    /// otherwise malformed files are rejected with `InvalidData`.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let bytes = fs::read(path)?;
+        Self::parse(&bytes)
+    }
+
+    fn parse(bytes: &[u8]) -> io::Result<Self> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+        let text = std::str::from_utf8(bytes)
+            .map_err(|_| invalid("replay file is not text (binary or corrupt data)".to_string()))?;
+
         let mut frames = Vec::new();
-        for line in reader.lines() {
-            let line = line?;
-            let dir = match line.trim() {
-                "N" => Some(Direction::North),
-                "S" => Some(Direction::South),
-                "E" => Some(Direction::East),
-                "W" => Some(Direction::West),
-                _ => None,
-            };
-            frames.push(dir);
+        let mut checkpoints = Vec::new();
+        let mut events = Vec::new();
+        let mut lead_in = 0;
+        let mut meta = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if let Some(count) = line.strip_prefix("L ") {
+                if i != 0 {
+                    return Err(invalid(format!("replay line {}: lead-in must be the first line", i + 1)));
+                }
+                lead_in = count
+                    .trim()
+                    .parse()
+                    .map_err(|_| invalid(format!("replay line 1: bad lead-in count '{count}'")))?;
+                continue;
+            }
+            if let Some(entry) = line.strip_prefix("M ") {
+                if !frames.is_empty() {
+                    return Err(invalid(format!("replay line {}: settings must come before the frames", i + 1)));
+                }
+                let (key, value) = entry.trim().split_once(' ').unwrap_or((entry.trim(), ""));
+                meta.push((key.to_string(), value.trim().to_string()));
+                continue;
+            }
+            if let Some(hex) = line.strip_prefix("H ") {
+                let hash = u64::from_str_radix(hex.trim(), 16)
+                    .map_err(|_| invalid(format!("replay line {}: bad checkpoint hash '{hex}'", i + 1)))?;
+                checkpoints.push((frames.len(), hash));
+                continue;
+            }
+            if let Some(name) = line.strip_prefix("E ") {
+                let event = FrameEvent::parse(name.trim())
+                    .ok_or_else(|| invalid(format!("replay line {}: unknown event '{}'", i + 1, name.trim())))?;
+                events.push((frames.len(), event));
+                continue;
+            }
+            let moves: Vec<Option<Option<Direction>>> = line
+                .chars()
+                .map(|ch| if ch == '.' { Some(None) } else { Direction::from_char(ch).map(Some) })
+                .collect();
+            match moves[..] {
+                [Some(p1)] => frames.push([p1, None]),
+                [Some(p1), Some(p2)] => frames.push([p1, p2]),
+                _ => return Err(invalid(format!("replay line {}: expected N, S, E, W or '.' per player, got '{line}'", i + 1))),
+            }
+        }
+        if frames.is_empty() {
+            return Err(invalid("replay file is empty".to_string()));
         }
-        Ok(Player { frames, index: 0 })
+        Ok(Player { frames, checkpoints, events, lead_in, meta, index: 0 })
     }
 
+    /// Player 1's input for the next frame.
     pub fn next_frame(&mut self) -> Option<Option<Direction>> {
-        if self.index < self.frames.len() {
-            let val = self.frames[self.index];
+        self.next_inputs().map(|[p1, _]| p1)
+    }
+
+    /// Both players' inputs for the next frame, or None once it's over.
+    pub fn next_inputs(&mut self) -> Option<FrameInput> {
+        let frame = self.frames.get(self.index).copied();
+        if frame.is_some() {
             self.index += 1;
-            Some(val)
-        } else {
-            None // replay finished
         }
+        frame
+    }
+
+    /// Jump so the next frame read is `frame`, clamped to the recording.
+    pub fn seek(&mut self, frame: usize) {
+        self.index = frame.min(self.frames.len());
+    }
+
+    pub fn total_frames(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Events recorded after `frame` frames, before the next one.
+    pub fn events_after(&self, frame: usize) -> Vec<FrameEvent> {
+        self.events.iter().filter(|&&(at, _)| at == frame).map(|&(_, event)| event).collect()
+    }
+
+    /// Recorded state hash after `frame` frames, if one was checkpointed.
+    pub fn checkpoint(&self, frame: usize) -> Option<u64> {
+        self.checkpoints.iter().find(|&&(at, _)| at == frame).map(|&(_, hash)| hash)
+    }
+
+    fn meta(&self, key: &str) -> Option<&str> {
+        self.meta.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    /// Caption for `frame`: the latest note added at or up to
+    /// `NOTE_CAPTION_FRAMES` frames before it.
+    pub fn caption(&self, frame: usize) -> Option<&str> {
+        self.meta
+            .iter()
+            .filter(|(k, _)| k == "note")
+            .filter_map(|(_, v)| {
+                let (at, text) = v.split_once(' ')?;
+                Some((at.parse::<usize>().ok()?, text))
+            })
+            .filter(|&(at, _)| at <= frame && frame < at + NOTE_CAPTION_FRAMES)
+            .max_by_key(|&(at, _)| at)
+            .map(|(_, text)| text)
+    }
+
+    /// Final scores the recording was saved with, player 1 first.
+    pub fn recorded_scores(&self) -> Option<Vec<usize>> {
+        self.meta("scores")?.split_whitespace().map(|s| s.parse().ok()).collect()
+    }
+
+    /// Overwrite the match settings stored in the recording, so playback
+    /// rebuilds the same board whatever flags the viewer passed. Older
+    /// recordings without them leave `settings` alone.
+    pub fn apply_meta(&self, settings: &mut Settings) {
+        apply_match_meta(self.meta.iter().map(|(k, v)| (k.as_str(), v.as_str())), settings);
+    }
+}
+
+/// Put every match setting back to its default, so a `--run-code` plays
+/// the shared setup rather than whatever else was passed alongside it.
+pub fn reset_match_settings(settings: &mut Settings) {
+    let defaults = Settings::parse_from(["snake-term"]);
+    let meta = match_meta(&defaults, defaults.seed);
+    apply_match_meta(meta.iter().map(|(k, v)| (*k, v.as_str())), settings);
+    // Entries only written when set, and what replays can't carry
+    settings.start_pos = None;
+    settings.p1_spawn = None;
+    settings.p1_dir = None;
+    settings.p2_spawn = None;
+    settings.p2_dir = None;
+    settings.p2_speed = None;
+    settings.two_headed = None;
+    settings.art = None;
+    settings.progressive_speed = defaults.progressive_speed;
+    settings.fill_speed = defaults.fill_speed;
+    settings.speed_curve = defaults.speed_curve;
+    settings.speed_floor = defaults.speed_floor;
+    settings.time_attack = defaults.time_attack;
+    settings.tie_breaker = defaults.tie_breaker;
+    settings.score_by_length = false;
+    settings.from_snapshot = None;
+    settings.map_file = None;
+    settings.art_target = None;
+}
+
+pub fn apply_match_meta<'a>(meta: impl IntoIterator<Item = (&'a str, &'a str)>, settings: &mut Settings) {
+    fn set<T: std::str::FromStr>(value: &str, field: &mut T) {
+        if let Ok(v) = value.parse() {
+            *field = v;
+        }
+    }
+    fn set_enum<T: ValueEnum>(value: &str, field: &mut T) {
+        if let Ok(v) = T::from_str(value, true) {
+            *field = v;
+        }
+    }
+    for (key, value) in meta {
+        match key {
+            "seed" => {
+                // The board comes from the recording, not the viewer's own files
+                settings.layout = None;
+                settings.map = None;
+                set(value, &mut settings.seed);
+            }
+            "rng" => set_enum(value, &mut settings.rng),
+            "width" => set(value, &mut settings.map_width),
+            "height" => set(value, &mut settings.map_height),
+            "players" => settings.multiplayer = value == "2",
+            "disable-borders" => set(value, &mut settings.disable_borders),
+            "edge-gaps" => set(value, &mut settings.edge_gaps),
+            "allow-reverse" => set(value, &mut settings.allow_reverse),
+            "assist" => set(value, &mut settings.assist),
+            "food-walls" => set(value, &mut settings.food_walls),
+            "deterministic-bonus" => set(value, &mut settings.deterministic_bonus),
+            "mirror-food" => set(value, &mut settings.mirror_food),
+            "obstacles" => set(value, &mut settings.obstacles),
+            "spawn-immunity" => set(value, &mut settings.spawn_immunity),
+            "warp-tiles" => set(value, &mut settings.warp_tiles),
+            "portals" => set(value, &mut settings.portals),
+            "food-count" => set(value, &mut settings.food_count),
+            "start-food" => set(value, &mut settings.start_food),
+            "auto-food" => set(value, &mut settings.auto_food),
+            "food-min-spread" => set(value, &mut settings.food_min_spread),
+            "food-values" => set(value, &mut settings.food_values),
+            "hungry" => set(value, &mut settings.hungry),
+            "max-length" => set(value, &mut settings.max_length),
+            "wrap-penalty" => set(value, &mut settings.wrap_penalty),
+            "wrap-limit" => set(value, &mut settings.wrap_limit),
+            "bomb-radius" => set(value, &mut settings.bomb_radius),
+            "rewind" => set(value, &mut settings.rewind),
+            "powerups" => set(value, &mut settings.powerups),
+            "coins" => set(value, &mut settings.coins),
+            "steal" => set(value, &mut settings.steal),
+            "growing-board" => set(value, &mut settings.growing_board),
+            "shrinking-border" => set(value, &mut settings.shrinking_border),
+            "style-points" => set(value, &mut settings.style_points),
+            "shape" => set_enum(value, &mut settings.shape),
+            "score-formula" => set(value, &mut settings.score_formula),
+            "start-pos" => settings.start_pos = value.parse().ok(),
+            "p1-spawn" => settings.p1_spawn = SpawnPoint::from_str(value, true).ok(),
+            "p1-dir" => settings.p1_dir = Direction::from_str(value, true).ok(),
+            "p2-spawn" => settings.p2_spawn = SpawnPoint::from_str(value, true).ok(),
+            "two-headed" => settings.two_headed = TwinMode::from_str(value, true).ok(),
+            "map" => {
+                if let Ok(map) = MapFile::parse(&value.replace('/', "\n")) {
+                    settings.use_map(map);
+                }
+            }
+            "layout" => {
+                if let Ok(snap) = Snapshot::parse_layout(settings.map_width, settings.map_height, value) {
+                    settings.use_snapshot(snap);
+                }
+            }
+            "art" => {
+                let cells = value.split_whitespace().map(|cell| {
+                    let (r, c) = cell.split_once(',')?;
+                    Some((r.parse().ok()?, c.parse().ok()?))
+                });
+                settings.art = cells.collect::<Option<Vec<_>>>().map(|cells| ArtTarget { cells });
+            }
+            "p2-dir" => settings.p2_dir = Direction::from_str(value, true).ok(),
+            "p2-speed" => settings.p2_speed = value.parse().ok(),
+            "speed" => set(value, &mut settings.speed),
+            "progressive-speed" => set(value, &mut settings.progressive_speed),
+            "fill-speed" => set(value, &mut settings.fill_speed),
+            "speed-curve" => set_enum(value, &mut settings.speed_curve),
+            "speed-floor" => set(value, &mut settings.speed_floor),
+            _ => {} // newer keys, "scores" or "note"
+        }
+    }
+}
+
+/// Add a note to the recording at `path`, captioned from `frame` during
+/// playback (`--annotate`). Notes are `M note <frame> <text>` settings
+/// lines, which loaders that don't know them skip.
+pub fn annotate(path: &Path, frame: usize, text: &str) -> io::Result<()> {
+    let bytes = fs::read(path)?;
+    let player = Player::parse(&bytes)?;
+    if frame > player.total_frames() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("frame {frame} is past the end of the recording ({} frames)", player.total_frames()),
+        ));
+    }
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "note text is empty"));
+    }
+    let contents = String::from_utf8_lossy(&bytes);
+    let lines: Vec<&str> = contents.lines().collect();
+    // Settings lines have to stay ahead of the first frame
+    let at = lines.iter().take_while(|l| l.starts_with("L ") || l.starts_with("M ")).count();
+    let note = format!("M note {frame} {text}");
+    let mut out: Vec<&str> = lines[..at].to_vec();
+    out.push(&note);
+    out.extend(&lines[at..]);
+    fs::write(path, out.join("\n") + "\n")
+}
+
+/// Headless re-simulation of a recorded game, shared by on-screen playback
+/// and exporters. Follows the live game's tick: both snakes move, heads
+/// colliding with the other body die, the border shrinks, then food,
+/// pickups, coins and art coverage resolve. Modes it can't rebuild are
+/// refused for `--record` in `Settings::validate`.
+pub struct ReplaySim {
+    pub snake: Snake,
+    pub snake2: Option<Snake>,
+    pub map: GameMap,
+    pub frame_count: usize,
+    rng: GameRng,
+    steps: [StepAccumulator; 2],
+    art_progress: [ArtProgress; 2],
+}
+
+impl ReplaySim {
+    /// Fresh board for `player`, already past any idle lead-in frames the
+    /// recorder skipped. Frame counts start after the lead-in.
+    pub fn start(settings: &Settings, player: &Player) -> Self {
+        let mut sim = ReplaySim::new(settings);
+        for _ in 0..player.lead_in {
+            if !sim.tick([None, None], settings) {
+                break;
+            }
+        }
+        sim.frame_count = 0;
+        sim
+    }
+
+    fn new(settings: &Settings) -> Self {
+        let w = settings.map_width;
+        let h = settings.map_height;
+        // Replays need deterministic food (and random start positions)
+        let mut rng = GameRng::seeded(settings.rng, if settings.seed != 0 { settings.seed } else { 42 });
+        let new_snake = || {
+            let mut snake = Snake::new(w, h);
+            snake.allow_reverse = settings.allow_reverse;
+            snake
+        };
+        let mut snake = new_snake();
+        let mut snake2 = settings.multiplayer.then(new_snake);
+        let mut map = GameMap::new(w, h);
+        let mut snakes: Vec<&mut Snake> = std::iter::once(&mut snake).chain(snake2.as_mut()).collect();
+        // The recorded game started, so its second head had room
+        let _ = map.start_round(&mut snakes, settings, &mut rng);
+        ReplaySim { snake, snake2, map, frame_count: 0, rng, steps: Default::default(), art_progress: Default::default() }
+    }
+
+    /// Every snake on the board, player 1 first.
+    pub fn snakes(&self) -> Vec<&Snake> {
+        std::iter::once(&self.snake).chain(self.snake2.as_ref()).collect()
+    }
+
+    /// Board fill for `--fill-speed`.
+    pub fn fill(&self) -> f64 {
+        self.map.fill_fraction(&self.snakes())
+    }
+
+    /// Scores in player order.
+    pub fn scores(&self) -> Vec<usize> {
+        self.snakes().iter().map(|s| s.score).collect()
+    }
+
+    /// Re-simulate from the start up to `frame` (clamped to where the
+    /// recording ends or a snake dies). The game can't be run backwards,
+    /// so this is how playback steps back.
+    pub fn at_frame(settings: &Settings, player: &mut Player, frame: usize) -> Self {
+        player.seek(0);
+        let mut sim = ReplaySim::start(settings, player);
+        while sim.frame_count < frame && sim.step(player, settings) {}
+        sim
+    }
+
+    /// `--loop-replay`: go back to frame 0 of `player`, rebuilding the board
+    /// from the recorded seed so every pass plays out the same.
+    pub fn restart(&mut self, settings: &Settings, player: &mut Player) {
+        *self = ReplaySim::at_frame(settings, player, 0);
+    }
+
+    /// Advance one frame of `player`. Returns false once the recording is
+    /// exhausted or a snake has died.
+    pub fn step(&mut self, player: &mut Player, settings: &Settings) -> bool {
+        if self.snakes().iter().any(|s| s.is_dead) {
+            return false;
+        }
+        let Some(input) = player.next_inputs() else {
+            return false; // replay ended
+        };
+        for event in player.events_after(self.frame_count) {
+            self.apply_event(event, settings);
+        }
+        self.tick(input, settings)
+    }
+
+    /// Repeat what player 1 did between two frames.
+    fn apply_event(&mut self, event: FrameEvent, settings: &Settings) {
+        match event {
+            FrameEvent::Bomb => {
+                self.map.detonate(&mut self.snake, settings.bomb_radius);
+            }
+            FrameEvent::Rewind => {
+                self.snake.rewind(&self.map.walls, self.map.border_min, self.map.border_max);
+            }
+        }
+    }
+
+    fn tick(&mut self, [p1, p2]: FrameInput, settings: &Settings) -> bool {
+        if let Some(dir) = p1 {
+            self.snake.queue_direction(dir);
+        }
+        if let Some(dir) = p2 {
+            match self.snake2.as_mut() {
+                Some(s2) => s2.queue_direction(dir),
+                None => self.snake.queue_twin_direction(dir),
+            }
+        }
+        self.frame_count += 1;
+
+        let periods = settings.player_speeds(self.snake.length, self.snake2.as_ref().map(|s| s.length), self.fill());
+        let tick_ms = periods[0].min(periods[1]);
+        let moves = [self.steps[0].advance(tick_ms, periods[0]), self.steps[1].advance(tick_ms, periods[1])];
+        let mut snakes: Vec<&mut Snake> = std::iter::once(&mut self.snake).chain(self.snake2.as_mut()).collect();
+        self.map.step_snakes(&mut snakes, moves, settings);
+        if let Some(ref mut s2) = self.snake2 {
+            snake::collide(&mut self.snake, s2, settings);
+        }
+        if self.snakes().iter().any(|s| s.is_dead) {
+            return false;
+        }
+
+        let mut snakes: Vec<&mut Snake> = std::iter::once(&mut self.snake).chain(self.snake2.as_mut()).collect();
+        self.map.warp_snakes(&mut snakes, &mut self.rng);
+        if settings.shrinking_border {
+            self.map.update_shrinking_border(&self.snake);
+            let mut snakes: Vec<&mut Snake> = std::iter::once(&mut self.snake).chain(self.snake2.as_mut()).collect();
+            self.map.rehome_food(&mut snakes, settings, &mut self.rng);
+            let (bmin, bmax) = (self.map.border_min, self.map.border_max);
+            let (r, c) = self.snake.head;
+            if r < bmin.0 || r >= bmax.0 || c < bmin.1 || c >= bmax.1 {
+                self.snake.is_dead = true;
+                return false;
+            }
+        }
+        let mut snakes: Vec<&mut Snake> = std::iter::once(&mut self.snake).chain(self.snake2.as_mut()).collect();
+        let food = self.map.resolve_food(&mut snakes, settings, &mut self.rng);
+        if settings.growing_board {
+            let snakes: Vec<&Snake> = std::iter::once(&self.snake).chain(self.snake2.as_ref()).collect();
+            self.map.update_growing_board(&snakes);
+        }
+        if food.board_full {
+            return false;
+        }
+
+        let snakes: Vec<&Snake> = std::iter::once(&self.snake).chain(self.snake2.as_ref()).collect();
+        self.map.maybe_spawn_bonus(&snakes, &mut self.rng);
+        self.map.tick_bonus();
+        if !self.map.check_bonus_eaten(&mut self.snake, settings) {
+            if let Some(ref mut s2) = self.snake2 {
+                self.map.check_bonus_eaten(s2, settings);
+            }
+        }
+        // Pickups keep the rng in step; spending them comes from the recording's events
+        let snakes: Vec<&Snake> = std::iter::once(&self.snake).chain(self.snake2.as_ref()).collect();
+        self.map.maybe_spawn_bomb(&snakes, settings, &mut self.rng);
+        self.map.maybe_spawn_rewind(&snakes, settings, &mut self.rng);
+        self.map.maybe_spawn_split(&snakes, settings, &mut self.rng);
+        self.map.check_bomb_collected(&mut self.snake);
+        self.map.check_rewind_collected(&mut self.snake);
+        self.map.check_split_collected(&mut self.snake);
+
+        for snake in std::iter::once(&mut self.snake).chain(self.snake2.as_mut()) {
+            self.map.collect_coins(snake, settings);
+        }
+        self.map.tick_coins();
+        let snakes: Vec<&Snake> = std::iter::once(&self.snake).chain(self.snake2.as_ref()).collect();
+        self.map.drop_coins(&snakes, settings, &mut self.rng);
+
+        if let Some(ref target) = settings.art {
+            let mut complete = false;
+            let snakes = std::iter::once(&mut self.snake).chain(self.snake2.as_mut());
+            for (snake, progress) in snakes.zip(self.art_progress.iter_mut()) {
+                let covered = art::overlap(&snake.parts, target);
+                snake.art_points += progress.award(covered);
+                snake.rescore(settings);
+                complete |= covered == target.cells.len();
+            }
+            if complete {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Written after every frame `render_frames` produces.
+pub const FRAME_SEPARATOR: &str = "\x1b[0m\r\n--- end of frame ---\r\n";
+
+/// Headless run for golden-file tests of the renderer (`--render-to`):
+/// writes the first `frames` rendered frames to `out`, starting with the
+/// untouched board, without touching the terminal. Player 1 follows
+/// `--input-script` when given and otherwise every snake is steered by
+/// the AI. Stops early if a snake dies; returns the frames written.
+pub fn render_frames(settings: &Settings, frames: usize, out: &mut impl Write) -> io::Result<usize> {
+    // Centering depends on the terminal size, which would make frames unstable
+    let mut settings = settings.clone();
+    settings.center_board = false;
+    let settings = &settings;
+    let mut script = settings.input_script.as_deref().map(Player::load).transpose()?;
+    let mut sim = ReplaySim::new(settings);
+    let mut written = 0;
+    while written < frames {
+        let snakes: Vec<&Snake> = std::iter::once(&sim.snake).chain(sim.snake2.as_ref()).collect();
+        let frame = sim.map.render(&snakes, settings, false, sim.frame_count, 0.0);
+        out.write_all(frame.as_bytes())?;
+        out.write_all(FRAME_SEPARATOR.as_bytes())?;
+        written += 1;
+        if written == frames {
+            break;
+        }
+        let input = headless_input(&sim, script.as_mut(), settings);
+        if !sim.tick(input, settings) {
+            break;
+        }
+    }
+    out.flush()?;
+    Ok(written)
+}
+
+/// Input for the next headless tick: the script's next frame, or the AI
+/// steering every snake when there's no script.
+fn headless_input(sim: &ReplaySim, script: Option<&mut Player>, settings: &Settings) -> FrameInput {
+    match script {
+        Some(player) => player.next_inputs().unwrap_or([None, None]),
+        None => {
+            let snakes = sim.snakes();
+            let ai = |player| (player < snakes.len()).then(|| ai::choose_direction(&snakes, player, &sim.map, settings)).flatten();
+            [ai(0), ai(1)]
+        }
+    }
+}
+
+/// First frame where two `--audit` runs disagreed, with each run's state
+/// hash there (None where that run had already ended).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    pub frame: usize,
+    pub first: Option<u64>,
+    pub second: Option<u64>,
+}
+
+/// State hash of each frame of a headless run, the untouched board first,
+/// for up to `frames` ticks. Input comes as in `render_frames`.
+/// `perturb` sees the simulation before every tick, so tests can inject
+/// the kind of drift the audit is there to catch.
+pub fn state_hashes(settings: &Settings, frames: usize, mut perturb: impl FnMut(&mut ReplaySim)) -> io::Result<Vec<u64>> {
+    let mut script = settings.input_script.as_deref().map(Player::load).transpose()?;
+    let mut sim = ReplaySim::new(settings);
+    let mut hashes = vec![GameState::new(&sim.snakes(), &sim.map).state_hash()];
+    while sim.frame_count < frames {
+        perturb(&mut sim);
+        let input = headless_input(&sim, script.as_mut(), settings);
+        let alive = sim.tick(input, settings);
+        hashes.push(GameState::new(&sim.snakes(), &sim.map).state_hash());
+        if !alive {
+            break;
+        }
+    }
+    Ok(hashes)
+}
+
+/// The first frame at which `first` and `second` differ, if any.
+pub fn first_divergence(first: &[u64], second: &[u64]) -> Option<Divergence> {
+    let frame = (0..first.len().max(second.len())).find(|&i| first.get(i) != second.get(i))?;
+    Some(Divergence { frame, first: first.get(frame).copied(), second: second.get(frame).copied() })
+}
+
+/// `--audit`: run the same game twice from `settings` and compare the
+/// state hashes frame by frame. Returns how many frames matched, or where
+/// the runs first drifted apart.
+pub fn audit(settings: &Settings, frames: usize) -> io::Result<Result<usize, Divergence>> {
+    let first = state_hashes(settings, frames, |_| {})?;
+    let second = state_hashes(settings, frames, |_| {})?;
+    Ok(match first_divergence(&first, &second) {
+        Some(divergence) => Err(divergence),
+        None => Ok(first.len()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    fn sim_state(sim: &ReplaySim) -> String {
+        let snake = &sim.snake;
+        format!("{:?} {:?} {:?} {} {}", snake.parts, snake.food, snake.direction, snake.score, sim.frame_count)
+    }
+
+    #[test]
+    fn test_load_round_trips_recording() {
+        let mut rec = Recorder::new(RecordFrom::Start);
+        rec.record_frames([Some(Direction::North), None]);
+        rec.record_frames([None, None]);
+        rec.record_frames([Some(Direction::West), None]);
+        rec.checkpoints.push((2, 0xDEAD_BEEF));
+        let path = std::env::temp_dir().join(format!("snake-term-rec-{}.rep", std::process::id()));
+        rec.save(&path).unwrap();
+
+        let mut player = Player::load(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(player.next_frame(), Some(Some(Direction::North)));
+        assert_eq!(player.next_frame(), Some(None));
+        assert_eq!(player.next_frame(), Some(Some(Direction::West)));
+        assert_eq!(player.next_frame(), None);
+        assert_eq!(player.checkpoint(2), Some(0xDEAD_BEEF));
+        assert_eq!(player.checkpoint(3), None);
+    }
+
+    #[test]
+    fn test_corrupt_replay_rejected() {
+        let err = Player::parse(b"").err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("empty"));
+
+        let random: Vec<u8> = (0..64u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8).collect();
+        assert_eq!(Player::parse(&random).err().unwrap().kind(), io::ErrorKind::InvalidData);
+
+        let err = Player::parse(b"N\nE\nxyz\n").err().unwrap();
+        assert!(err.to_string().contains("line 3"));
+    }
+
+    #[test]
+    fn test_step_forward_then_back_restores_frame() {
+        let mut settings = Settings::parse_from(["test", "--seed", "9"]).resolve();
+        settings.map_width = 20;
+        settings.map_height = 20;
+        let mut player = Player::parse(b"N\n.\nW\n.\nS\n.\n").unwrap();
+
+        let mut sim = ReplaySim::at_frame(&settings, &mut player, 3);
+        let before = sim_state(&sim);
+        assert_eq!(sim.frame_count, 3);
+        assert!(sim.step(&mut player, &settings));
+        assert_ne!(sim_state(&sim), before);
+
+        let back = ReplaySim::at_frame(&settings, &mut player, sim.frame_count - 1);
+        assert_eq!(sim_state(&back), before);
+
+        // Clamped at both ends
+        let start = ReplaySim::at_frame(&settings, &mut player, 0);
+        assert_eq!(start.frame_count, 0);
+        let mut end = ReplaySim::at_frame(&settings, &mut player, 100);
+        assert_eq!(end.frame_count, player.total_frames());
+        assert!(!end.step(&mut player, &settings));
+        assert_eq!(end.frame_count, player.total_frames());
+    }
+
+    #[test]
+    fn test_loop_replay_restarts_from_frame_zero() {
+        let settings = Settings::parse_from(["test", "--seed", "9", "--replay", "x.rep", "--loop-replay"]).resolve();
+        let settings = Settings { map_width: 20, map_height: 20, ..settings };
+        let mut player = Player::parse(b"N\n.\nW\n.\nS\n.\n").unwrap();
+
+        let mut sim = ReplaySim::start(&settings, &player);
+        let first = sim_state(&sim);
+        let mut pass = Vec::new();
+        while sim.step(&mut player, &settings) {
+            pass.push(sim_state(&sim));
+        }
+        assert_eq!(sim.frame_count, player.total_frames());
+
+        sim.restart(&settings, &mut player);
+        assert_eq!(sim.frame_count, 0);
+        assert_eq!(sim_state(&sim), first);
+        let mut again = Vec::new();
+        while sim.step(&mut player, &settings) {
+            again.push(sim_state(&sim));
+        }
+        assert_eq!(again, pass);
+    }
+
+    #[test]
+    fn test_record_from_first_move_skips_idle_frames() {
+        let mut rec = Recorder::new(RecordFrom::FirstMove);
+        rec.record_frames([None, None]);
+        rec.record_frames([None, None]);
+        rec.record_frames([Some(Direction::North), None]);
+        rec.record_frames([None, None]);
+        assert_eq!(rec.frames, vec![[Some(Direction::North), None], [None, None]]);
+        assert_eq!(rec.lead_in, 2);
+
+        let path = std::env::temp_dir().join(format!("snake-term-first-move-{}.rep", std::process::id()));
+        rec.save(&path).unwrap();
+        let mut player = Player::load(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(player.total_frames(), 2);
+        assert_eq!(player.next_frame(), Some(Some(Direction::North)));
+
+        // Playback starts where the full recording would be after the idle frames
+        let mut settings = Settings::parse_from(["test", "--seed", "9"]).resolve();
+        settings.map_width = 20;
+        settings.map_height = 20;
+        let mut full = Player::parse(b".\n.\nN\n.\n").unwrap();
+        let full_sim = ReplaySim::at_frame(&settings, &mut full, 4);
+        let skipped = ReplaySim::at_frame(&settings, &mut player, 2);
+        assert_eq!(skipped.snake.parts, full_sim.snake.parts);
+        assert_eq!(skipped.frame_count, 2);
+
+        assert!(Player::parse(b"N\nL 3\n").is_err());
+    }
+
+    #[test]
+    fn test_records_turns_typed_during_frame_delay() {
+        let mut settings = Settings::parse_from(["test", "--seed", "9"]).resolve();
+        settings.map_width = 20;
+        settings.map_height = 20;
+
+        // A live round: the frame's own poll sees nothing, but a key pressed
+        // during the previous frame's delay is already in the snake's queue
+        let mut live = ReplaySim::new(&settings);
+        let turn = match live.snake.direction {
+            Direction::North | Direction::South => Direction::East,
+            Direction::East | Direction::West => Direction::North,
+        };
+        let mut rec = Recorder::new(RecordFrom::Start);
+        let mut heads = Vec::new();
+        for frame in 0..5 {
+            if frame == 2 {
+                live.snake.queue_direction(turn);
+            }
+            let applied = live.map.step_snakes(&mut [&mut live.snake], [true, true], &settings);
+            rec.record_frames(applied);
+            heads.push(live.snake.head);
+        }
+        assert_eq!(rec.frames[2], [Some(turn), None]);
+
+        let path = std::env::temp_dir().join(format!("snake-term-delay-turn-{}.rep", std::process::id()));
+        rec.save(&path).unwrap();
+        let mut player = Player::load(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        let mut sim = ReplaySim::start(&settings, &player);
+        let mut replayed = Vec::new();
+        while sim.step(&mut player, &settings) {
+            replayed.push(sim.snake.head);
+        }
+        assert_eq!(replayed, heads);
+    }
+
+    #[test]
+    fn test_recorded_bomb_replays_its_blast() {
+        let mut settings = Settings::parse_from(["test", "--bomb-radius", "2", "--seed", "5"]).resolve();
+        settings.map_width = 20;
+        settings.map_height = 20;
+        let mut live = ReplaySim::new(&settings);
+        let (r, c) = live.snake.head;
+        // Same walls and a collected charge on both boards
+        let prep = |sim: &mut ReplaySim| {
+            sim.map.walls = vec![(r - 1, c + 2), (r + 6, c)];
+            sim.snake.bomb_charges = 1;
+        };
+        prep(&mut live);
+
+        let mut rec = Recorder::new(RecordFrom::Start);
+        for frame in 0..4 {
+            if frame == 2 {
+                assert_eq!(live.map.detonate(&mut live.snake, settings.bomb_radius), 1);
+                rec.record_event(FrameEvent::Bomb);
+            }
+            assert!(live.tick([None, None], &settings));
+            rec.record_frames([None, None]);
+        }
+
+        let path = std::env::temp_dir().join(format!("snake-term-bomb-{}.rep", std::process::id()));
+        rec.save(&path).unwrap();
+        let mut player = Player::load(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(player.events_after(2), vec![FrameEvent::Bomb]);
+        let mut sim = ReplaySim::start(&settings, &player);
+        prep(&mut sim);
+        while sim.step(&mut player, &settings) {}
+        assert_eq!(sim.frame_count, 4);
+        assert_eq!(sim.map.walls, live.map.walls);
+        assert_eq!(sim.snake.bomb_charges, 0);
+
+        assert!(Player::parse(b"N\nE launch\n").is_err());
+    }
+
+    #[test]
+    fn test_recorded_rewind_replays() {
+        let mut settings = Settings::parse_from(["test", "--rewind", "--seed", "8"]).resolve();
+        settings.map_width = 20;
+        settings.map_height = 20;
+        let mut live = ReplaySim::new(&settings);
+        live.snake.rewind_charges = 1;
+
+        let mut rec = Recorder::new(RecordFrom::Start);
+        for frame in 0..8 {
+            if frame == 6 {
+                assert!(live.snake.rewind(&live.map.walls, live.map.border_min, live.map.border_max));
+                rec.record_event(FrameEvent::Rewind);
+            }
+            assert!(live.tick([None, None], &settings));
+            rec.record_frames([None, None]);
+        }
+
+        let path = std::env::temp_dir().join(format!("snake-term-rewind-{}.rep", std::process::id()));
+        rec.save(&path).unwrap();
+        let mut player = Player::load(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        let mut sim = ReplaySim::start(&settings, &player);
+        sim.snake.rewind_charges = 1;
+        while sim.step(&mut player, &settings) {}
+        assert_eq!(sim.frame_count, 8);
+        assert_eq!(sim_state(&sim), sim_state(&live));
+        assert_eq!(sim.snake.rewind_charges, 0);
+    }
+
+    #[test]
+    fn test_multiplayer_recording_replays_identically() {
+        let mut settings = Settings::parse_from(["test", "--multiplayer", "--obstacles", "4", "--seed", "1234"]).resolve();
+        settings.map_width = 16;
+        settings.map_height = 16;
+
+        // Play a short match with both snakes steered by the AI
+        let mut rec = Recorder::for_settings(&settings, settings.seed);
+        let mut game = ReplaySim::new(&settings);
+        for _ in 0..80 {
+            let snakes = game.snakes();
+            let input = [
+                crate::ai::choose_direction(&snakes, 0, &game.map, &settings),
+                crate::ai::choose_direction(&snakes, 1, &game.map, &settings),
+            ];
+            rec.record_frames(input);
+            let alive = game.tick(input, &settings);
+            rec.checkpoint(&GameState::new(&game.snakes(), &game.map));
+            if !alive {
+                break;
+            }
+        }
+        rec.set_scores(&game.scores());
+        assert!(game.scores().iter().all(|&s| s > 0), "both players scored: {:?}", game.scores());
+
+        let path = std::env::temp_dir().join(format!("snake-term-match-{}.rep", std::process::id()));
+        rec.save(&path).unwrap();
+        let mut player = Player::load(&path).unwrap();
+        let _ = fs::remove_file(&path);
+
+        // The viewer's own flags don't matter: the file carries the match
+        let mut viewer = Settings::parse_from(["test", "--seed", "5"]).resolve();
+        viewer.map_width = 30;
+        viewer.map_height = 12;
+        player.apply_meta(&mut viewer);
+        assert!(viewer.multiplayer);
+        assert_eq!((viewer.seed, viewer.map_width, viewer.map_height, viewer.obstacles), (1234, 16, 16, 4));
+
+        let mut sim = ReplaySim::start(&viewer, &player);
+        assert_eq!(sim.map.walls, ReplaySim::new(&settings).map.walls);
+        while sim.step(&mut player, &viewer) {
+            if let Some(expected) = player.checkpoint(sim.frame_count) {
+                assert_eq!(GameState::new(&sim.snakes(), &sim.map).state_hash(), expected, "frame {}", sim.frame_count);
+            }
+        }
+        assert_eq!(sim.frame_count, player.total_frames());
+        assert_eq!(Some(sim.scores()), player.recorded_scores());
+        assert_eq!(sim.snake.parts, game.snake.parts);
+        assert_eq!(sim.snake2.as_ref().unwrap().parts, game.snake2.as_ref().unwrap().parts);
+    }
+
+    #[test]
+    fn test_recording_carries_shrinking_border_and_art() {
+        let mut settings = Settings::parse_from(["test", "--shrinking-border", "--seed", "3"]).resolve();
+        settings.art = Some(ArtTarget::parse("..#\n.##\n"));
+        let mut rec = Recorder::for_settings(&settings, settings.seed);
+        rec.record_frames([None, None]);
+        let path = std::env::temp_dir().join(format!("snake-term-art-{}.rep", std::process::id()));
+        rec.save(&path).unwrap();
+        let player = Player::load(&path).unwrap();
+        let _ = fs::remove_file(&path);
+
+        let mut viewer = Settings::parse_from(["test"]).resolve();
+        player.apply_meta(&mut viewer);
+        assert!(viewer.shrinking_border);
+        assert_eq!(viewer.art, settings.art);
+
+        // Modes playback can't rebuild aren't recorded at all
+        let rejected = |args: &[&str]| {
+            let args = ["test", "--record", "out.rep"].iter().chain(args).copied();
+            Settings::parse_from(args).resolve().validate().is_err()
+        };
+        assert!(rejected(&["--time-attack", "30"]));
+        assert!(!rejected(&["--from-snapshot", "board.txt"]));
+        assert!(!rejected(&["--map-file", "level.txt"]));
+        assert!(!rejected(&["--shrinking-border"]));
+    }
+
+    #[test]
+    fn test_map_file_recording_replays_on_its_map() {
+        let map = MapFile::parse("#......#\n........\n......#.\n...v....\n........\n........\n#......#\n").unwrap();
+        let mut settings = Settings::parse_from(["test", "--seed", "4"]).resolve();
+        settings.use_map(map.clone());
+        let mut rec = Recorder::for_settings(&settings, settings.seed);
+        rec.record_frames([None, None]);
+        let path = std::env::temp_dir().join(format!("snake-term-mapfile-{}.rep", std::process::id()));
+        rec.save(&path).unwrap();
+        let player = Player::load(&path).unwrap();
+        let _ = fs::remove_file(&path);
+
+        let mut viewer = Settings::parse_from(["test"]).resolve();
+        player.apply_meta(&mut viewer);
+        assert_eq!(viewer.map, Some(map.clone()));
+        let sim = ReplaySim::start(&viewer, &player);
+        assert_eq!((sim.map.width, sim.map.height), (8, 7));
+        assert_eq!(sim.map.walls, map.walls);
+        assert_eq!((sim.snake.head, sim.snake.direction), ((3, 3), Direction::South));
+    }
+
+    #[test]
+    fn test_snapshot_recording_replays_on_its_layout() {
+        let mut board = GameMap::new(10, 8);
+        board.walls = vec![(2, 2), (5, 7)];
+        board.border_min = (1, 1);
+        board.border_max = (6, 8);
+        let snap = Snapshot::capture(&Snake::new(10, 8), &board, 0);
+        let mut settings = Settings::parse_from(["test", "--seed", "6"]).resolve();
+        settings.use_snapshot(snap.clone());
+        let mut rec = Recorder::for_settings(&settings, settings.seed);
+        rec.record_frames([None, None]);
+        let path = std::env::temp_dir().join(format!("snake-term-layout-{}.rep", std::process::id()));
+        rec.save(&path).unwrap();
+        let player = Player::load(&path).unwrap();
+        let _ = fs::remove_file(&path);
+
+        let mut viewer = Settings::parse_from(["test"]).resolve();
+        player.apply_meta(&mut viewer);
+        assert_eq!(viewer.layout, Some(snap));
+        let sim = ReplaySim::start(&viewer, &player);
+        assert_eq!((sim.map.width, sim.map.height), (10, 8));
+        assert_eq!(sim.map.walls, vec![(2, 2), (5, 7)]);
+        assert_eq!((sim.map.border_min, sim.map.border_max), ((1, 1), (6, 8)));
+    }
+
+    #[test]
+    fn test_annotations_round_trip_and_caption() {
+        // Wrapping lets the snake coast straight through all 40 frames
+        let mut settings = Settings::parse_from(["test", "--seed", "3", "--disable-borders"]).resolve();
+        settings.map_width = 20;
+        settings.map_height = 20;
+        let mut rec = Recorder::for_settings(&settings, settings.seed);
+        for _ in 0..40 {
+            rec.record_frames([None, None]);
+        }
+        let path = std::env::temp_dir().join(format!("snake-term-notes-{}.rep", std::process::id()));
+        rec.save(&path).unwrap();
+
+        annotate(&path, 5, "watch the   tail\nhere").unwrap();
+        annotate(&path, 30, "too late").unwrap();
+        assert!(annotate(&path, 41, "past the end").is_err());
+        assert!(annotate(&path, 2, "  ").is_err());
+        let mut player = Player::load(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(player.total_frames(), 40);
+
+        // Notes don't disturb the match settings or the playback
+        let mut viewer = Settings::parse_from(["test"]).resolve();
+        player.apply_meta(&mut viewer);
+        assert_eq!(viewer.seed, 3);
+        let mut sim = ReplaySim::start(&viewer, &player);
+        let mut captions = Vec::new();
+        while sim.step(&mut player, &viewer) {
+            captions.push((sim.frame_count, player.caption(sim.frame_count).map(str::to_string)));
+        }
+        let shown = |frame: usize| captions.iter().find(|&&(f, _)| f == frame).and_then(|(_, c)| c.clone());
+        assert_eq!(shown(4), None);
+        assert_eq!(shown(5).as_deref(), Some("watch the tail here"));
+        assert_eq!(shown(5 + NOTE_CAPTION_FRAMES - 1).as_deref(), Some("watch the tail here"));
+        assert_eq!(shown(5 + NOTE_CAPTION_FRAMES), None);
+        assert_eq!(shown(30).as_deref(), Some("too late"));
+    }
+
+    #[test]
+    fn test_audit_matches_and_flags_injected_drift() {
+        let mut settings = Settings::parse_from(["test", "--seed", "7", "--obstacles", "4"]).resolve();
+        settings.map_width = 20;
+        settings.map_height = 12;
+        let frames = audit(&settings, 60).unwrap().expect("the engine is deterministic");
+        assert!(frames > 30, "ran {frames} frames");
+
+        // A wall that only the second run gets, dropped in before tick 20
+        let first = state_hashes(&settings, 60, |_| {}).unwrap();
+        let second = state_hashes(&settings, 60, |sim| {
+            if sim.frame_count == 20 {
+                sim.map.walls.push((0, 0));
+            }
+        })
+        .unwrap();
+        let divergence = first_divergence(&first, &second).unwrap();
+        assert_eq!(divergence.frame, 21);
+        assert_ne!(divergence.first, divergence.second);
+        assert_eq!(first_divergence(&first, &first), None);
+        assert_eq!(first_divergence(&first[..5], &first[..3]).map(|d| (d.frame, d.second)), Some((3, None)));
+    }
+
+    #[test]
+    fn test_render_frames_writes_separated_frames() {
+        let mut settings = Settings::parse_from(["test", "--seed", "9"]).resolve();
+        settings.map_width = 12;
+        settings.map_height = 10;
+
+        let mut out = Vec::new();
+        assert_eq!(render_frames(&settings, 5, &mut out).unwrap(), 5);
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(text.matches(FRAME_SEPARATOR).count(), 5);
+        assert!(text.contains(settings.food));
+
+        // Same settings, same frames: usable as a golden file
+        let mut again = Vec::new();
+        render_frames(&settings, 5, &mut again).unwrap();
+        assert_eq!(text.as_bytes(), &again[..]);
     }
 }
@@ -0,0 +1,221 @@
+use clap::ValueEnum;
+
+use crate::config::Settings;
+use crate::replay;
+use crate::rng::RngAlgorithm;
+
+const RUN_CODE_VERSION: u8 = 1;
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+const FLAG_MULTIPLAYER: u8 = 1;
+const FLAG_DISABLE_BORDERS: u8 = 1 << 1;
+const FLAG_ALLOW_REVERSE: u8 = 1 << 2;
+const FLAG_FOOD_WALLS: u8 = 1 << 3;
+const FLAG_MIRROR_FOOD: u8 = 1 << 4;
+const FLAG_COINS: u8 = 1 << 5;
+
+/// The setup a `--run-code` carries: a round's seed plus the board size and
+/// mode flags that decide how it plays out. Counts too large for their
+/// field are clamped; match settings outside the code play at their
+/// defaults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunCode {
+    pub seed: u64,
+    pub rng: RngAlgorithm,
+    pub width: u16,
+    pub height: u16,
+    pub flags: u8,
+    pub obstacles: u16,
+    pub warp_tiles: u8,
+    pub portals: u8,
+    pub food_count: u8,
+    pub speed: u16,
+}
+
+fn clamp<T: TryFrom<usize> + Copy>(value: usize, max: T) -> T {
+    T::try_from(value).unwrap_or(max)
+}
+
+impl RunCode {
+    /// The setup of a round played with `settings` from `seed`.
+    pub fn capture(settings: &Settings, seed: u64) -> RunCode {
+        let flags = [
+            (settings.multiplayer, FLAG_MULTIPLAYER),
+            (settings.disable_borders, FLAG_DISABLE_BORDERS),
+            (settings.allow_reverse, FLAG_ALLOW_REVERSE),
+            (settings.food_walls, FLAG_FOOD_WALLS),
+            (settings.mirror_food, FLAG_MIRROR_FOOD),
+            (settings.coins, FLAG_COINS),
+        ];
+        RunCode {
+            seed,
+            rng: settings.rng,
+            width: clamp(settings.map_width, u16::MAX),
+            height: clamp(settings.map_height, u16::MAX),
+            flags: flags.iter().filter(|&&(on, _)| on).fold(0, |acc, &(_, bit)| acc | bit),
+            obstacles: clamp(settings.obstacles, u16::MAX),
+            warp_tiles: clamp(settings.warp_tiles, u8::MAX),
+            portals: clamp(settings.portals, u8::MAX),
+            food_count: clamp(settings.food_count, u8::MAX),
+            speed: u16::try_from(settings.speed).unwrap_or(u16::MAX),
+        }
+    }
+
+    /// Overwrite the matching fields of `settings` with this setup, and
+    /// put the other match settings back to their defaults.
+    pub fn apply(&self, settings: &mut Settings) {
+        replay::reset_match_settings(settings);
+        settings.seed = self.seed;
+        settings.rng = self.rng;
+        settings.map_width = self.width.into();
+        settings.map_height = self.height.into();
+        settings.multiplayer = self.flags & FLAG_MULTIPLAYER != 0;
+        settings.disable_borders = self.flags & FLAG_DISABLE_BORDERS != 0;
+        settings.allow_reverse = self.flags & FLAG_ALLOW_REVERSE != 0;
+        settings.food_walls = self.flags & FLAG_FOOD_WALLS != 0;
+        settings.mirror_food = self.flags & FLAG_MIRROR_FOOD != 0;
+        settings.coins = self.flags & FLAG_COINS != 0;
+        settings.obstacles = self.obstacles.into();
+        settings.warp_tiles = self.warp_tiles.into();
+        settings.portals = self.portals.into();
+        settings.food_count = self.food_count.into();
+        settings.speed = self.speed.into();
+    }
+
+    fn to_bytes(self) -> Vec<u8> {
+        let rng = RngAlgorithm::value_variants().iter().position(|&r| r == self.rng).unwrap_or(0);
+        let mut bytes = vec![RUN_CODE_VERSION];
+        bytes.extend(self.seed.to_be_bytes());
+        bytes.push(rng as u8);
+        bytes.extend(self.width.to_be_bytes());
+        bytes.extend(self.height.to_be_bytes());
+        bytes.push(self.flags);
+        bytes.extend(self.obstacles.to_be_bytes());
+        bytes.extend([self.warp_tiles, self.portals, self.food_count]);
+        bytes.extend(self.speed.to_be_bytes());
+        bytes.push(checksum(&bytes));
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<RunCode, String> {
+        let Some((&sum, body)) = bytes.split_last() else {
+            return Err("run code is empty".to_string());
+        };
+        if checksum(body) != sum {
+            return Err("run code checksum doesn't match (mistyped?)".to_string());
+        }
+        if body.first() != Some(&RUN_CODE_VERSION) || body.len() != 22 {
+            return Err("run code is from an unknown version".to_string());
+        }
+        let u16_at = |i: usize| u16::from_be_bytes([body[i], body[i + 1]]);
+        let mut seed = [0; 8];
+        seed.copy_from_slice(&body[1..9]);
+        let rng = *RngAlgorithm::value_variants()
+            .get(usize::from(body[9]))
+            .ok_or("run code names an unknown RNG")?;
+        Ok(RunCode {
+            seed: u64::from_be_bytes(seed),
+            rng,
+            width: u16_at(10),
+            height: u16_at(12),
+            flags: body[14],
+            obstacles: u16_at(15),
+            warp_tiles: body[17],
+            portals: body[18],
+            food_count: body[19],
+            speed: u16_at(20),
+        })
+    }
+
+    /// The shareable text form: unpadded base32, upper case.
+    pub fn encode(&self) -> String {
+        base32_encode(&self.to_bytes())
+    }
+
+    /// Read a code from `encode`. Case, spaces and dashes are ignored so
+    /// codes survive being retyped.
+    pub fn decode(code: &str) -> Result<RunCode, String> {
+        let cleaned: String = code.chars().filter(|c| !c.is_whitespace() && *c != '-').collect();
+        let bytes = base32_decode(&cleaned).ok_or_else(|| format!("run code {code:?} isn't valid base32"))?;
+        RunCode::from_bytes(&bytes)
+    }
+}
+
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |acc, &b| acc.rotate_left(3) ^ b)
+}
+
+fn base32_encode(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    let (mut buffer, mut bits) = (0u32, 0);
+    for &byte in bytes {
+        buffer = (buffer << 8) | u32::from(byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32_ALPHABET[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    out
+}
+
+fn base32_decode(text: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    let (mut buffer, mut bits) = (0u32, 0);
+    for ch in text.chars() {
+        let value = BASE32_ALPHABET.iter().position(|&a| a as char == ch.to_ascii_uppercase())?;
+        buffer = (buffer << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn test_run_code_round_trips_settings() {
+        let played = Settings::parse_from([
+            "test", "--seed", "987654321", "--rng", "std", "--map-width", "31", "--map-height", "17",
+            "--multiplayer", "--disable-borders", "--coins", "--obstacles", "12", "--portals", "2",
+            "--food-count", "3", "--speed", "140",
+        ])
+        .resolve();
+        let code = RunCode::capture(&played, played.seed).encode();
+        assert!(code.chars().all(|c| BASE32_ALPHABET.contains(&(c as u8))));
+
+        // Pasted into a fresh setup, with the case and grouping mangled
+        let mut shared = Settings::parse_from(["test"]).resolve();
+        let pasted = format!("{}-{}", code[..10].to_lowercase(), &code[10..]);
+        RunCode::decode(&pasted).unwrap().apply(&mut shared);
+        assert_eq!((shared.seed, shared.rng), (987_654_321, RngAlgorithm::Std));
+        assert_eq!((shared.map_width, shared.map_height), (31, 17));
+        assert!(shared.multiplayer && shared.disable_borders && shared.coins);
+        assert!(!shared.allow_reverse && !shared.food_walls && !shared.mirror_food);
+        assert_eq!((shared.obstacles, shared.portals, shared.warp_tiles), (12, 2, 0));
+        assert_eq!((shared.food_count, shared.speed), (3, 140));
+        assert_eq!(RunCode::capture(&shared, shared.seed).encode(), code);
+
+        // Match settings the code doesn't carry don't leak in from the flags
+        let mut other = Settings::parse_from(["test", "--shape", "circle", "--hungry", "5", "--time-attack", "30", "--p1-dir", "north"]).resolve();
+        RunCode::decode(&code).unwrap().apply(&mut other);
+        let defaults = Settings::parse_from(["test"]).resolve();
+        assert_eq!((other.shape, other.hungry, other.time_attack, other.p1_dir), (defaults.shape, 0, 0, None));
+        assert_eq!((other.map_width, other.obstacles), (31, 12));
+
+        // A typo is caught rather than quietly giving a different game
+        let typo: String = code.chars().enumerate().map(|(i, c)| if i == 4 { if c == 'A' { 'B' } else { 'A' } } else { c }).collect();
+        assert!(RunCode::decode(&typo).is_err());
+        assert!(RunCode::decode("not base32!").is_err());
+        assert!(RunCode::decode("").is_err());
+    }
+}
@@ -0,0 +1,283 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use clap::ValueEnum;
+
+/// Counters describing a run, fed into `GameState::compute_score`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RunSummary {
+    pub food: usize,     // points earned from food and bonus food
+    pub growth: usize,   // segments grown beyond the initial length
+    pub time_secs: u64,  // game time survived (sum of frame delays)
+}
+
+/// Per-counter weights parsed from `--score-formula` (e.g. "food=1,length=2,time=0").
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScoreWeights {
+    pub food: usize,
+    pub length: usize,
+    pub time: usize,
+}
+
+impl Default for ScoreWeights {
+    // +1 per food, matching the classic scoring
+    fn default() -> Self {
+        ScoreWeights { food: 1, length: 0, time: 0 }
+    }
+}
+
+impl ScoreWeights {
+    /// `--score-by-length`: one point per segment grown, so bonus food
+    /// and starvation count by the length they add or take away.
+    pub const BY_LENGTH: ScoreWeights = ScoreWeights { food: 0, length: 1, time: 0 };
+}
+
+impl FromStr for ScoreWeights {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Weights not mentioned in the formula count as zero
+        let mut weights = ScoreWeights { food: 0, length: 0, time: 0 };
+        for term in s.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+            let (key, value) = term
+                .split_once('=')
+                .ok_or_else(|| format!("expected key=weight, got '{term}'"))?;
+            let value: usize = value
+                .trim()
+                .parse()
+                .map_err(|_| format!("invalid weight '{}' for '{}'", value.trim(), key.trim()))?;
+            match key.trim() {
+                "food" => weights.food = value,
+                "length" => weights.length = value,
+                "time" => weights.time = value,
+                other => return Err(format!("unknown score term '{other}' (expected food, length or time)")),
+            }
+        }
+        Ok(weights)
+    }
+}
+
+impl fmt::Display for ScoreWeights {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "food={},length={},time={}", self.food, self.length, self.time)
+    }
+}
+
+/// Counters collected over one round, shown on the game-over screen.
+#[derive(Debug, Clone, Default)]
+pub struct RunStats {
+    pub food_eaten: usize,
+    pub bonuses_eaten: usize,
+    pub final_length: usize,
+    pub score: usize,
+    pub elapsed: Duration,
+}
+
+impl RunStats {
+    pub fn score_per_minute(&self) -> f64 {
+        let minutes = self.elapsed.as_secs_f64() / 60.0;
+        if minutes > 0.0 {
+            self.score as f64 / minutes
+        } else {
+            0.0
+        }
+    }
+
+    /// Label/value rows for the game-over analytics block.
+    pub fn summary_rows(&self) -> Vec<(&'static str, String)> {
+        let secs = self.elapsed.as_secs();
+        vec![
+            ("Length", self.final_length.to_string()),
+            ("Food eaten", self.food_eaten.to_string()),
+            ("Bonuses", self.bonuses_eaten.to_string()),
+            ("Time", format!("{}:{:02}", secs / 60, secs % 60)),
+            ("Score/min", format!("{:.1}", self.score_per_minute())),
+        ]
+    }
+}
+
+/// Score milestones for `--milestone-pause`: every multiple of `every`.
+/// Each one fires once per round, even if the score dips back below it and
+/// climbs past it again.
+#[derive(Debug, Clone)]
+pub struct Milestones {
+    every: usize,
+    reached: usize,
+}
+
+impl Milestones {
+    pub fn new(every: usize) -> Option<Self> {
+        if every == 0 {
+            return None;
+        }
+        Some(Milestones { every, reached: 0 })
+    }
+
+    /// The milestone `score` has just passed, if it's past a new one. A
+    /// jump over several at once reports only the highest.
+    pub fn crossed(&mut self, score: usize) -> Option<usize> {
+        let milestone = score / self.every * self.every;
+        if milestone <= self.reached {
+            return None;
+        }
+        self.reached = milestone;
+        Some(milestone)
+    }
+}
+
+/// In-memory results for the current session (never persisted).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Session {
+    pub rounds: usize,
+    pub best: usize,
+    pub last: Option<usize>,
+    /// Consecutive rounds that beat the round before them.
+    pub streak: usize,
+}
+
+impl Session {
+    pub fn record_round(&mut self, score: usize) {
+        self.streak = match self.last {
+            Some(last) if score > last => self.streak + 1,
+            _ => 0,
+        };
+        self.last = Some(score);
+        self.best = self.best.max(score);
+        self.rounds += 1;
+    }
+}
+
+/// How a multiplayer draw on score is broken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TieBreaker {
+    /// The longer snake wins
+    Length,
+    /// The snake that died last wins
+    Survival,
+}
+
+/// One player's standing at the end of a multiplayer round.
+#[derive(Debug, Clone, Copy)]
+pub struct PlayerResult {
+    pub score: usize,
+    pub length: usize,
+    pub survived_frames: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Player1,
+    Player2,
+    Draw,
+}
+
+impl Outcome {
+    pub fn message(self) -> &'static str {
+        match self {
+            Outcome::Player1 => "Player 1 wins!",
+            Outcome::Player2 => "Player 2 wins!",
+            Outcome::Draw => "Draw!",
+        }
+    }
+}
+
+pub fn decide_winner(p1: &PlayerResult, p2: &PlayerResult, tie_breaker: TieBreaker) -> Outcome {
+    let key = |p: &PlayerResult| {
+        let tie = match tie_breaker {
+            TieBreaker::Length => p.length,
+            TieBreaker::Survival => p.survived_frames,
+        };
+        (p.score, tie)
+    };
+    match key(p1).cmp(&key(p2)) {
+        std::cmp::Ordering::Greater => Outcome::Player1,
+        std::cmp::Ordering::Less => Outcome::Player2,
+        std::cmp::Ordering::Equal => Outcome::Draw,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_stats_score_per_minute() {
+        let stats = RunStats {
+            food_eaten: 10,
+            bonuses_eaten: 2,
+            final_length: 15,
+            score: 16,
+            elapsed: Duration::from_secs(120),
+        };
+        assert!((stats.score_per_minute() - 8.0).abs() < f64::EPSILON);
+
+        let instant = RunStats { score: 5, ..RunStats::default() };
+        assert_eq!(instant.score_per_minute(), 0.0);
+
+        let rows = stats.summary_rows();
+        assert_eq!(rows[3], ("Time", "2:00".to_string()));
+        assert_eq!(rows[4], ("Score/min", "8.0".to_string()));
+    }
+
+    #[test]
+    fn test_decide_winner() {
+        let result = |score, length, survived_frames| PlayerResult { score, length, survived_frames };
+
+        // Higher score wins regardless of tie-breaker
+        let (a, b) = (result(5, 4, 10), result(3, 9, 20));
+        assert_eq!(decide_winner(&a, &b, TieBreaker::Length), Outcome::Player1);
+        assert_eq!(decide_winner(&b, &a, TieBreaker::Survival), Outcome::Player2);
+
+        // Tied score: longer snake, or the one that died last
+        let (a, b) = (result(4, 8, 30), result(4, 6, 31));
+        assert_eq!(decide_winner(&a, &b, TieBreaker::Length), Outcome::Player1);
+        assert_eq!(decide_winner(&a, &b, TieBreaker::Survival), Outcome::Player2);
+
+        // Exact draw
+        let (a, b) = (result(4, 6, 31), result(4, 6, 31));
+        assert_eq!(decide_winner(&a, &b, TieBreaker::Length), Outcome::Draw);
+        assert_eq!(decide_winner(&a, &b, TieBreaker::Survival), Outcome::Draw);
+    }
+
+    #[test]
+    fn test_score_formula_parse_errors() {
+        assert!("food".parse::<ScoreWeights>().is_err());
+        assert!("food=x".parse::<ScoreWeights>().is_err());
+        assert!("speed=1".parse::<ScoreWeights>().is_err());
+        let w: ScoreWeights = "food=3,time=2".parse().unwrap();
+        assert_eq!(w.to_string().parse::<ScoreWeights>().unwrap(), w);
+    }
+
+    #[test]
+    fn test_session_streak() {
+        let mut session = Session::default();
+        session.record_round(5);
+        assert_eq!((session.streak, session.best), (0, 5));
+        session.record_round(8);
+        session.record_round(12);
+        assert_eq!((session.streak, session.best), (2, 12));
+        // Matching the previous score isn't an improvement
+        session.record_round(12);
+        assert_eq!(session.streak, 0);
+        session.record_round(3);
+        assert_eq!((session.streak, session.best), (0, 12));
+        session.record_round(4);
+        assert_eq!((session.streak, session.last, session.rounds), (1, Some(4), 6));
+    }
+
+    #[test]
+    fn test_milestones_fire_once_per_threshold() {
+        assert!(Milestones::new(0).is_none());
+        let mut milestones = Milestones::new(10).unwrap();
+        let fired: Vec<Option<usize>> = [0, 4, 9, 10, 11, 10, 19, 20, 20].iter().map(|&s| milestones.crossed(s)).collect();
+        assert_eq!(fired, [None, None, None, Some(10), None, None, None, Some(20), None]);
+
+        // A penalty drops the score below 20; climbing back doesn't repeat it
+        assert_eq!(milestones.crossed(17), None);
+        assert_eq!(milestones.crossed(22), None);
+        // A big jump past several reports the highest, once
+        assert_eq!(milestones.crossed(45), Some(40));
+        assert_eq!(milestones.crossed(49), None);
+    }
+}
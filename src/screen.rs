@@ -0,0 +1,82 @@
+use std::io::{self, Write};
+
+use crossterm::{cursor, event, queue, terminal};
+
+/// Whether a terminal with this `TERM` can be trusted with the alternate
+/// screen. Dumb and unset terminals (pipes, many CI runners) can't.
+pub fn supports_alt_screen(term: Option<&str>) -> bool {
+    !matches!(term.map(str::trim), None | Some("") | Some("dumb"))
+}
+
+/// How the game takes over the terminal: on the alternate screen, or
+/// inline in the normal buffer for terminals without one. Bracketed paste
+/// is switched on too, so a paste arrives as one event the game can ignore
+/// instead of a burst of key presses.
+pub struct Screen {
+    alt: bool,
+    paste: bool,
+}
+
+impl Screen {
+    pub fn new(no_alt_screen: bool, no_bracketed_paste: bool) -> Self {
+        Screen { alt: !no_alt_screen, paste: !no_bracketed_paste }
+    }
+
+    pub fn setup(&self, out: &mut impl Write) -> io::Result<()> {
+        if self.alt {
+            queue!(out, terminal::EnterAlternateScreen)?;
+        } else {
+            queue!(out, terminal::Clear(terminal::ClearType::All), cursor::MoveTo(0, 0))?;
+        }
+        if self.paste {
+            queue!(out, event::EnableBracketedPaste)?;
+        }
+        queue!(out, cursor::Hide)?;
+        out.flush()
+    }
+
+    /// Undo `setup`. Inline mode wipes the board it drew and leaves the
+    /// cursor at the top for the shell prompt.
+    pub fn teardown(&self, out: &mut impl Write) -> io::Result<()> {
+        queue!(out, cursor::Show)?;
+        if self.paste {
+            queue!(out, event::DisableBracketedPaste)?;
+        }
+        if self.alt {
+            queue!(out, terminal::LeaveAlternateScreen)?;
+        } else {
+            queue!(out, terminal::Clear(terminal::ClearType::All), cursor::MoveTo(0, 0))?;
+        }
+        out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEAVE_ALT: &str = "\x1b[?1049l";
+
+    fn teardown_bytes(screen: &Screen) -> String {
+        let mut out = Vec::new();
+        screen.teardown(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_inline_teardown_skips_alt_screen() {
+        let inline = teardown_bytes(&Screen::new(true, false));
+        assert!(!inline.contains(LEAVE_ALT));
+        assert!(inline.contains("\x1b[?25h"), "cursor is shown again");
+
+        assert!(teardown_bytes(&Screen::new(false, false)).contains(LEAVE_ALT));
+
+        let mut setup = Vec::new();
+        Screen::new(true, false).setup(&mut setup).unwrap();
+        assert!(!String::from_utf8(setup).unwrap().contains("\x1b[?1049h"));
+
+        assert!(supports_alt_screen(Some("xterm-256color")));
+        assert!(!supports_alt_screen(Some("dumb")));
+        assert!(!supports_alt_screen(None));
+    }
+}
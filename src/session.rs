@@ -0,0 +1,318 @@
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+
+use crate::config::Settings;
+use crate::replay;
+
+const SESSION_HEADER: &str = "# snake-term session v1";
+
+/// Named keys in session logs; any other key is written as `c:<char>`.
+const KEY_NAMES: [(KeyCode, &str); 8] = [
+    (KeyCode::Up, "up"),
+    (KeyCode::Down, "down"),
+    (KeyCode::Left, "left"),
+    (KeyCode::Right, "right"),
+    (KeyCode::Enter, "enter"),
+    (KeyCode::Esc, "esc"),
+    (KeyCode::Tab, "tab"),
+    (KeyCode::Backspace, "backspace"),
+];
+
+const MODIFIER_NAMES: [(KeyModifiers, &str); 3] = [
+    (KeyModifiers::CONTROL, "ctrl"),
+    (KeyModifiers::ALT, "alt"),
+    (KeyModifiers::SHIFT, "shift"),
+];
+
+fn key_token(code: KeyCode) -> Option<String> {
+    if let KeyCode::Char(ch) = code {
+        return Some(format!("c:{ch}"));
+    }
+    KEY_NAMES.iter().find(|&&(key, _)| key == code).map(|&(_, name)| name.to_string())
+}
+
+fn parse_key_token(token: &str) -> Option<KeyCode> {
+    if let Some(ch) = token.strip_prefix("c:") {
+        let mut chars = ch.chars();
+        return match (chars.next(), chars.next()) {
+            (Some(ch), None) => Some(KeyCode::Char(ch)),
+            _ => None,
+        };
+    }
+    KEY_NAMES.iter().find(|&&(_, name)| name == token).map(|&(key, _)| key)
+}
+
+fn modifier_token(modifiers: KeyModifiers) -> String {
+    let names: Vec<&str> = MODIFIER_NAMES.iter().filter(|&&(m, _)| modifiers.contains(m)).map(|&(_, name)| name).collect();
+    if names.is_empty() { "-".to_string() } else { names.join("+") }
+}
+
+fn parse_modifier_token(token: &str) -> Option<KeyModifiers> {
+    if token == "-" {
+        return Some(KeyModifiers::NONE);
+    }
+    token.split('+').try_fold(KeyModifiers::NONE, |mods, name| {
+        MODIFIER_NAMES.iter().find(|&&(_, n)| n == name).map(|&(m, _)| mods | m)
+    })
+}
+
+/// The seed and match settings a session was played with, plus the pace
+/// the key timings depend on.
+fn session_meta(settings: &Settings) -> Vec<(&'static str, String)> {
+    let mut meta = replay::match_meta(settings, settings.seed);
+    let pace = [
+        ("speed", settings.speed.to_string()),
+        ("progressive-speed", settings.progressive_speed.to_string()),
+        ("fill-speed", settings.fill_speed.to_string()),
+        ("speed-curve", replay::value_name(&settings.speed_curve)),
+        ("speed-floor", settings.speed_floor.to_string()),
+    ];
+    for (key, value) in pace {
+        if !meta.iter().any(|&(k, _)| k == key) {
+            meta.push((key, value));
+        }
+    }
+    meta
+}
+
+/// `--session-log`: a header of `# <key> <value>` lines holding the seed
+/// and match settings, then every key press from the menu on, one line
+/// each as `<ms since start> <modifiers> <key>`. Lines are flushed as
+/// they're written so a crash still leaves a usable log.
+pub struct SessionRecorder<W: Write> {
+    out: W,
+    start: Instant,
+}
+
+impl<W: Write> SessionRecorder<W> {
+    pub fn new(mut out: W, settings: &Settings) -> io::Result<Self> {
+        writeln!(out, "{SESSION_HEADER}")?;
+        for (key, value) in session_meta(settings) {
+            writeln!(out, "# {key} {value}")?;
+        }
+        out.flush()?;
+        Ok(SessionRecorder { out, start: Instant::now() })
+    }
+
+    pub fn record(&mut self, code: KeyCode, modifiers: KeyModifiers) -> io::Result<()> {
+        let Some(key) = key_token(code) else {
+            return Ok(()); // keys the game never reacts to
+        };
+        let ms = self.start.elapsed().as_millis();
+        writeln!(self.out, "{ms} {} {key}", modifier_token(modifiers))?;
+        self.out.flush()
+    }
+}
+
+/// `--session-play`: a session log fed back key by key at its original
+/// timing, in place of the keyboard.
+pub struct SessionPlayer {
+    events: VecDeque<(Duration, KeyCode, KeyModifiers)>,
+    meta: Vec<(String, String)>,
+    start: Instant,
+}
+
+impl SessionPlayer {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut events = VecDeque::new();
+        let mut meta = Vec::new();
+        for (n, line) in text.lines().enumerate() {
+            if line == SESSION_HEADER {
+                continue;
+            }
+            if let Some(entry) = line.strip_prefix("# ") {
+                if let Some((key, value)) = entry.split_once(' ') {
+                    meta.push((key.to_string(), value.to_string()));
+                }
+                continue;
+            }
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let bad = || format!("bad session log line {}: {line:?}", n + 1);
+            let mut fields = line.splitn(3, ' ');
+            let ms: u64 = fields.next().and_then(|f| f.parse().ok()).ok_or_else(bad)?;
+            let modifiers = fields.next().and_then(parse_modifier_token).ok_or_else(bad)?;
+            let code = fields.next().and_then(parse_key_token).ok_or_else(bad)?;
+            events.push_back((Duration::from_millis(ms), code, modifiers));
+        }
+        Ok(SessionPlayer { events, meta, start: Instant::now() })
+    }
+
+    /// Play with the seed and match settings the session was logged with.
+    pub fn apply_meta(&self, settings: &mut Settings) {
+        replay::apply_match_meta(self.meta.iter().map(|(k, v)| (k.as_str(), v.as_str())), settings);
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        SessionPlayer::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// The next key if it was pressed by `elapsed` into the session.
+    pub fn pop_due(&mut self, elapsed: Duration) -> Option<(KeyCode, KeyModifiers)> {
+        let &(at, code, modifiers) = self.events.front()?;
+        if at > elapsed {
+            return None;
+        }
+        self.events.pop_front();
+        Some((code, modifiers))
+    }
+
+    /// Wait up to `timeout` for the next key, as a keyboard poll would.
+    fn wait_key(&mut self, timeout: Duration) -> Option<(KeyCode, KeyModifiers)> {
+        let elapsed = self.start.elapsed();
+        let due = self.events.front().map(|&(at, ..)| at)?;
+        std::thread::sleep(due.saturating_sub(elapsed).min(timeout));
+        self.pop_due(self.start.elapsed())
+    }
+}
+
+enum Active {
+    Record(SessionRecorder<BufWriter<File>>),
+    Play(SessionPlayer),
+}
+
+static ACTIVE: Mutex<Option<Active>> = Mutex::new(None);
+
+fn set_active(active: Active) {
+    if let Ok(mut slot) = ACTIVE.lock() {
+        *slot = Some(active);
+    }
+}
+
+/// Start writing every key press to `path`, after a header of `settings`.
+pub fn record_to(path: &Path, settings: &Settings) -> io::Result<()> {
+    let recorder = SessionRecorder::new(BufWriter::new(File::create(path)?), settings)?;
+    set_active(Active::Record(recorder));
+    Ok(())
+}
+
+/// Take key presses from the log at `path` instead of the keyboard until
+/// it runs out, switching `settings` to the ones it was logged with.
+pub fn play_from(path: &Path, settings: &mut Settings) -> io::Result<()> {
+    let player = SessionPlayer::load(path)?;
+    player.apply_meta(settings);
+    set_active(Active::Play(player));
+    Ok(())
+}
+
+/// The one place keys are read: waits up to `timeout` for a key press from
+/// the keyboard (recording it with `--session-log`) or from a
+/// `--session-play` log. Ctrl+C on the real keyboard still gets through
+/// during playback.
+pub fn read_key(timeout: Duration) -> Option<(KeyCode, KeyModifiers)> {
+    let Ok(mut active) = ACTIVE.lock() else {
+        return keyboard_key(timeout);
+    };
+    if let Some(Active::Play(player)) = active.as_mut() {
+        if let Some((code, modifiers)) = keyboard_key(Duration::ZERO) {
+            if modifiers.contains(KeyModifiers::CONTROL) && code == KeyCode::Char('c') {
+                return Some((code, modifiers));
+            }
+        }
+        let key = player.wait_key(timeout);
+        if player.is_finished() {
+            *active = None; // back to the keyboard
+        }
+        return key;
+    }
+
+    let key = keyboard_key(timeout)?;
+    if let Some(Active::Record(recorder)) = active.as_mut() {
+        let _ = recorder.record(key.0, key.1);
+    }
+    Some(key)
+}
+
+fn keyboard_key(timeout: Duration) -> Option<(KeyCode, KeyModifiers)> {
+    if !event::poll(timeout).unwrap_or(false) {
+        return None;
+    }
+    event::read().ok().as_ref().and_then(key_of)
+}
+
+/// The key press in a terminal event, if it is one. Pastes never count:
+/// with bracketed paste on, pasted text arrives as a single `Paste` event
+/// and is dropped here instead of steering the snake.
+pub fn key_of(event: &Event) -> Option<(KeyCode, KeyModifiers)> {
+    match *event {
+        Event::Key(KeyEvent { code, modifiers, .. }) => Some((code, modifiers)),
+        Event::Paste(_) => None,
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{map_key, map_menu_key};
+    use clap::Parser;
+
+    #[test]
+    fn test_session_log_replays_same_inputs() {
+        let settings = Settings::parse_from(["test"]).resolve();
+        let keys = [
+            (KeyCode::Down, KeyModifiers::NONE),
+            (KeyCode::Up, KeyModifiers::NONE),
+            (KeyCode::Enter, KeyModifiers::NONE),
+            (KeyCode::Char('d'), KeyModifiers::NONE),
+            (KeyCode::Char('S'), KeyModifiers::SHIFT),
+            (KeyCode::Char(' '), KeyModifiers::NONE),
+            (KeyCode::Left, KeyModifiers::NONE),
+            (KeyCode::F(5), KeyModifiers::NONE), // never matters, not logged
+            (KeyCode::Char('c'), KeyModifiers::CONTROL),
+        ];
+        let mut log = Vec::new();
+        let mut recorder = SessionRecorder::new(&mut log, &settings).unwrap();
+        for (code, modifiers) in keys {
+            recorder.record(code, modifiers).unwrap();
+        }
+        let text = String::from_utf8(log).unwrap();
+        assert!(text.starts_with(SESSION_HEADER));
+
+        let mut player = SessionPlayer::parse(&text).unwrap();
+        let logged: Vec<_> = keys.iter().copied().filter(|&(code, _)| code != KeyCode::F(5)).collect();
+        let mut played = Vec::new();
+        while let Some(key) = player.pop_due(Duration::MAX) {
+            played.push(key);
+        }
+        assert!(player.is_finished());
+        assert_eq!(played, logged);
+
+        // The first three drove the menu, the rest the game
+        let menu = |keys: &[(KeyCode, KeyModifiers)]| keys[..3].iter().map(|&(c, m)| map_menu_key(c, m)).collect::<Vec<_>>();
+        let game = |keys: &[(KeyCode, KeyModifiers)]| keys[3..].iter().map(|&(c, m)| map_key(&settings, c, m)).collect::<Vec<_>>();
+        assert_eq!(menu(&played), menu(&logged));
+        assert_eq!(game(&played), game(&logged));
+
+        // Keys only come out once their time has come
+        let mut later = SessionPlayer::parse("500 - c:w\n").unwrap();
+        assert_eq!(later.pop_due(Duration::from_millis(499)), None);
+        assert_eq!(later.pop_due(Duration::from_millis(500)), Some((KeyCode::Char('w'), KeyModifiers::NONE)));
+
+        // The header carries the seed and settings back
+        let logged_with = Settings { seed: 42, map_width: 30, obstacles: 7, speed: 80, ..settings.clone() };
+        let mut log = Vec::new();
+        SessionRecorder::new(&mut log, &logged_with).unwrap();
+        let mut played_with = Settings::parse_from(["test", "--seed", "9"]).resolve();
+        SessionPlayer::parse(&String::from_utf8(log).unwrap()).unwrap().apply_meta(&mut played_with);
+        assert_eq!(
+            (played_with.seed, played_with.map_width, played_with.obstacles, played_with.speed),
+            (42, 30, 7, 80)
+        );
+
+        assert!(SessionPlayer::parse("12 - nope\n").is_err());
+        assert!(SessionPlayer::parse("x - c:w\n").is_err());
+    }
+}
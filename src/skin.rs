@@ -0,0 +1,121 @@
+use std::fmt;
+use std::str::FromStr;
+
+use clap::ValueEnum;
+
+/// Body/head glyph sets, unlocked as the all-time high score grows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Skin {
+    Classic,
+    Beads,
+    Rope,
+    Arrows,
+    Phantom,
+}
+
+/// High score each skin requires unless `--skin-unlocks` says otherwise.
+pub const DEFAULT_SKIN_UNLOCKS: [(Skin, usize); 4] = [(Skin::Beads, 25), (Skin::Rope, 50), (Skin::Arrows, 100), (Skin::Phantom, 200)];
+
+/// `--skin-unlocks`: the high score each skin needs, as `skin=score` pairs
+/// separated by commas. Skins left out are free.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkinUnlocks(pub Vec<(Skin, usize)>);
+
+impl Default for SkinUnlocks {
+    fn default() -> Self {
+        SkinUnlocks(DEFAULT_SKIN_UNLOCKS.to_vec())
+    }
+}
+
+impl FromStr for SkinUnlocks {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let pairs = s.split(',').map(str::trim).filter(|pair| !pair.is_empty()).map(|pair| {
+            let bad = || format!("invalid skin unlock '{pair}' (expected skin=score)");
+            let (skin, score) = pair.split_once('=').ok_or_else(bad)?;
+            let skin = Skin::from_str(skin.trim(), true).map_err(|_| format!("unknown skin '{}'", skin.trim()))?;
+            Ok((skin, score.trim().parse().map_err(|_| bad())?))
+        });
+        pairs.collect::<Result<_, String>>().map(SkinUnlocks)
+    }
+}
+
+impl fmt::Display for SkinUnlocks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pairs: Vec<String> = self.0.iter().map(|&(skin, score)| format!("{}={score}", skin.name())).collect();
+        write!(f, "{}", pairs.join(","))
+    }
+}
+
+impl Skin {
+    /// The `--skin` value naming this skin.
+    pub fn name(self) -> String {
+        self.to_possible_value().map_or_else(String::new, |v| v.get_name().to_string())
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Skin::Classic => "Classic",
+            Skin::Beads => "Beads",
+            Skin::Rope => "Rope",
+            Skin::Arrows => "Arrows",
+            Skin::Phantom => "Phantom",
+        }
+    }
+
+    /// Body glyph and head glyphs in WNES order.
+    pub fn glyphs(self) -> (char, [char; 4]) {
+        match self {
+            Skin::Classic => ('@', ['<', '^', '>', 'v']),
+            Skin::Beads => ('o', ['O', 'O', 'O', 'O']),
+            Skin::Rope => ('=', ['<', '^', '>', 'v']),
+            Skin::Arrows => ('~', ['←', '↑', '→', '↓']),
+            Skin::Phantom => ('░', ['◄', '▲', '►', '▼']),
+        }
+    }
+}
+
+/// High score `skin` needs according to `table`; unlisted skins are free.
+pub fn unlock_threshold(skin: Skin, table: &[(Skin, usize)]) -> usize {
+    table.iter().find(|(s, _)| *s == skin).map_or(0, |&(_, t)| t)
+}
+
+pub fn is_unlocked(skin: Skin, high_score: usize, table: &[(Skin, usize)]) -> bool {
+    high_score >= unlock_threshold(skin, table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skin_unlocks() {
+        let table = SkinUnlocks::default().0;
+        let unlocked = |high| -> Vec<Skin> {
+            Skin::value_variants().iter().copied().filter(|&s| is_unlocked(s, high, &table)).collect()
+        };
+        assert_eq!(unlocked(0), vec![Skin::Classic]);
+        assert_eq!(unlocked(24), vec![Skin::Classic]);
+        assert_eq!(unlocked(25), vec![Skin::Classic, Skin::Beads]);
+        assert_eq!(unlocked(199).len(), 4);
+        assert_eq!(unlocked(10_000).len(), Skin::value_variants().len());
+
+        // Custom tables, including skins missing from the table
+        let table = [(Skin::Rope, 10), (Skin::Phantom, 5)];
+        assert!(is_unlocked(Skin::Phantom, 5, &table));
+        assert!(!is_unlocked(Skin::Rope, 9, &table));
+        assert!(is_unlocked(Skin::Arrows, 0, &table));
+        assert_eq!(unlock_threshold(Skin::Rope, &table), 10);
+
+        // --skin-unlocks round-trips and rejects junk
+        let parsed: SkinUnlocks = "rope=10, Phantom = 5".parse().unwrap();
+        assert_eq!(parsed.0, table);
+        assert_eq!(parsed.to_string(), "rope=10,phantom=5");
+        assert_eq!(SkinUnlocks::default().to_string().parse::<SkinUnlocks>().unwrap(), SkinUnlocks::default());
+        assert_eq!("".parse::<SkinUnlocks>().unwrap().0, []);
+        assert!("rope".parse::<SkinUnlocks>().is_err());
+        assert!("rope=lots".parse::<SkinUnlocks>().is_err());
+        assert!("scales=5".parse::<SkinUnlocks>().is_err());
+    }
+}
@@ -1,12 +1,44 @@
 use std::collections::VecDeque;
 
 use crate::config::*;
+use crate::score::RunSummary;
+use crate::state::GameState;
+
+/// The snake as it stood before one move, kept for `--rewind`.
+#[derive(Debug, Clone)]
+pub struct SnakeSnapshot {
+    pub parts: VecDeque<(usize, usize)>,
+    pub direction: Direction,
+    pub length: usize,
+    pub score: usize,
+    pub points: usize,
+    pub survived_ms: u64,
+    pub penalty: usize,
+    pub style_points: usize,
+    pub art_points: usize,
+    pub coin_points: usize,
+    pub wraps_used: usize,
+    pub ticks_since_food: usize,
+}
+
+/// The second head of a `--two-headed` snake, or the split-off half of a
+/// `--powerups` one, and the strand of body behind it. It shares the
+/// snake's length, food and score.
+pub struct Twin {
+    pub parts: VecDeque<(usize, usize)>,
+    pub head: (usize, usize),
+    pub direction: Direction,
+    pub input_queue: VecDeque<Direction>,
+    pub mode: TwinMode,
+    pub share: Option<usize>, // cells of `length` this split-off half holds; None shares all of it
+}
 
 pub struct Snake {
     pub parts: VecDeque<(usize, usize)>,
     pub head: (usize, usize),
     pub food: (usize, usize),
     pub food_eaten: bool,
+    pub food_value: usize, // points the primary pellet is worth (--food-values)
     pub is_dead: bool,
     pub length: usize,
     pub direction: Direction,
@@ -15,6 +47,23 @@ pub struct Snake {
     pub map_width: usize,
     pub map_height: usize,
     pub score: usize,
+    pub points: usize,     // raw points from food/bonus, weighted into `score`
+    pub survived_ms: u64,  // game time survived, summed from frame delays
+    pub penalty: usize,    // score deducted so far (e.g. --wrap-penalty)
+    pub style_points: usize, // --style-points near-miss bonus so far
+    pub art_points: usize, // --art-target coverage bonus so far
+    pub coin_points: usize, // --coins collected so far
+    pub near_miss_flash: usize, // frames left of the NEAR MISS indicator
+    pub wrapped: bool,     // the last move crossed an edge in wrap-around mode
+    pub wraps_used: usize, // for --wrap-limit
+    pub bomb_charges: usize, // collected --bomb-radius bombs
+    pub rewind_charges: usize, // collected --rewind pickups
+    pub history: VecDeque<SnakeSnapshot>, // the last REWIND_TICKS moves, oldest first
+    pub vacated: Option<(usize, usize)>, // cell the tail left on the last move
+    pub has_key: bool, // carrying the key that opens the gate once
+    pub twin: Option<Twin>, // --two-headed second head
+    pub ticks_since_food: usize, // for --hungry
+    pub allow_reverse: bool,
 }
 
 impl Snake {
@@ -24,6 +73,7 @@ impl Snake {
             head: (0, 0),
             food: (0, 0),
             food_eaten: false,
+            food_value: 1,
             is_dead: false,
             length: INITIAL_SNAKE_LENGTH,
             direction: Direction::East,
@@ -32,6 +82,23 @@ impl Snake {
             map_width,
             map_height,
             score: 0,
+            points: 0,
+            survived_ms: 0,
+            penalty: 0,
+            style_points: 0,
+            art_points: 0,
+            coin_points: 0,
+            near_miss_flash: 0,
+            wrapped: false,
+            wraps_used: 0,
+            bomb_charges: 0,
+            rewind_charges: 0,
+            history: VecDeque::new(),
+            vacated: None,
+            has_key: false,
+            twin: None,
+            ticks_since_food: 0,
+            allow_reverse: false,
         };
         snake.initialize();
         snake
@@ -44,6 +111,22 @@ impl Snake {
         self.is_dead = false;
         self.length = INITIAL_SNAKE_LENGTH;
         self.score = 0;
+        self.points = 0;
+        self.survived_ms = 0;
+        self.penalty = 0;
+        self.style_points = 0;
+        self.art_points = 0;
+        self.coin_points = 0;
+        self.near_miss_flash = 0;
+        self.wrapped = false;
+        self.wraps_used = 0;
+        self.bomb_charges = 0;
+        self.rewind_charges = 0;
+        self.history.clear();
+        self.vacated = None;
+        self.has_key = false;
+        self.twin = None;
+        self.ticks_since_food = 0;
         self.parts.clear();
         for row in self.world.iter_mut() {
             row.fill(0);
@@ -51,6 +134,21 @@ impl Snake {
         self.initialize();
     }
 
+    /// Put the snake back at `spawn` after a death, keeping its score counters.
+    pub fn respawn<T, F: FnOnce(&mut Snake) -> T>(&mut self, settings: &Settings, spawn: F) -> T {
+        let (points, survived_ms, penalty, style, art, coins) =
+            (self.points, self.survived_ms, self.penalty, self.style_points, self.art_points, self.coin_points);
+        let spawned = spawn(self);
+        self.points = points;
+        self.survived_ms = survived_ms;
+        self.penalty = penalty;
+        self.style_points = style;
+        self.art_points = art;
+        self.coin_points = coins;
+        self.rescore(settings);
+        spawned
+    }
+
     fn initialize(&mut self) {
         let row = self.map_height / 2;
         let start_col = self.map_width / 2 - INITIAL_SNAKE_LENGTH / 2;
@@ -62,68 +160,364 @@ impl Snake {
         self.head = *self.parts.back().unwrap();
     }
 
-    pub fn init_at(&mut self, row: usize, start_col: usize, dir: Direction, reverse: bool) {
-        self.parts.clear();
-        for r in self.world.iter_mut() { r.fill(0); }
+    /// Reset to a fresh body with its head at `head`, plus the
+    /// `--two-headed` second head. Fails if that head has no room.
+    pub fn spawn_fresh(&mut self, head: (usize, usize), dir: Direction, settings: &Settings) -> Result<(), String> {
+        self.reset();
+        self.spawn_at(head, dir, INITIAL_SNAKE_LENGTH);
+        if let Some(mode) = settings.two_headed {
+            if !self.add_twin(mode) {
+                return Err("the second head has no room opposite player 1's spawn".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Lay a fresh `length`-cell body with its head at `head` facing `dir`,
+    /// the tail trailing straight behind. Returns false, leaving the snake
+    /// untouched, if the body wouldn't fit on the board.
+    pub fn spawn_at(&mut self, head: (usize, usize), dir: Direction, length: usize) -> bool {
+        let Some(cells) = spawn_cells(head, dir, length, self.map_width, self.map_height) else {
+            return false;
+        };
+        self.twin = None;
+        self.set_body(cells);
         self.direction = dir;
+        self.input_queue.clear();
+        self.history.clear();
+        self.length = length;
+        self.ticks_since_food = 0;
+        self.wraps_used = 0;
+        self.has_key = false;
         self.score = 0;
-        self.length = INITIAL_SNAKE_LENGTH;
-        for i in 0..INITIAL_SNAKE_LENGTH {
-            let pos = if reverse {
-                (row, start_col - i)
-            } else {
-                (row, start_col + i)
-            };
+        self.points = 0;
+        self.survived_ms = 0;
+        self.penalty = 0;
+        self.style_points = 0;
+        self.art_points = 0;
+        self.coin_points = 0;
+        self.near_miss_flash = 0;
+        true
+    }
+
+    /// Replace the body with `parts` (tail first), rebuilding the occupancy grid.
+    pub fn set_body<I: IntoIterator<Item = (usize, usize)>>(&mut self, parts: I) {
+        self.parts.clear();
+        for r in self.world.iter_mut() { r.fill(0); }
+        for pos in parts {
             self.parts.push_back(pos);
-            self.world[pos.0][pos.1] = 1;
+            self.world[pos.0][pos.1] += 1;
+        }
+        for &(r, c) in self.twin.iter().flat_map(|t| &t.parts) {
+            self.world[r][c] += 1;
+        }
+        if let Some(&head) = self.parts.back() {
+            self.head = head;
+        }
+    }
+
+    /// `--two-headed`: grow a second head of the snake's length at
+    /// `twin_spawn`. Returns false, leaving the snake single, if it
+    /// wouldn't fit or would overlap the body.
+    pub fn add_twin(&mut self, mode: TwinMode) -> bool {
+        let (head, dir) = twin_spawn(self.head, self.direction, self.map_width, self.map_height);
+        let Some(cells) = spawn_cells(head, dir, self.length, self.map_width, self.map_height) else {
+            return false;
+        };
+        if cells.iter().any(|&(r, c)| self.world[r][c] > 0) {
+            return false;
+        }
+        for &(r, c) in &cells {
+            self.world[r][c] += 1;
+        }
+        self.twin = Some(Twin { parts: cells.into(), head, direction: dir, input_queue: VecDeque::new(), mode, share: None });
+        true
+    }
+
+    /// `--powerups`: divide the body in two. The front half keeps the head;
+    /// the tail half turns into an independently steered strand whose head
+    /// is the old tail end, heading away from the rest. `length` stays the
+    /// total, so the score doesn't change. Refused while split already or
+    /// shorter than `SPLIT_MIN_LENGTH`.
+    pub fn split(&mut self) -> bool {
+        if self.twin.is_some() || self.parts.len() < SPLIT_MIN_LENGTH {
+            return false;
+        }
+        let direction = direction_between(self.parts[1], self.parts[0]);
+        let half = self.parts.len() / 2;
+        let parts: VecDeque<(usize, usize)> = self.parts.drain(..half).rev().collect();
+        let head = parts[parts.len() - 1];
+        let share = Some(parts.len());
+        self.twin = Some(Twin { parts, head, direction, input_queue: VecDeque::new(), mode: TwinMode::Independent, share });
+        true
+    }
+
+    /// Join a split-off strand back on once one head touches the other
+    /// strand's tail end, making a single snake of every cell. Returns
+    /// whether it did.
+    pub fn merge(&mut self) -> bool {
+        let Some(twin) = self.twin.take_if(|t| t.share.is_some()) else {
+            return false;
+        };
+        let touching = |a: (usize, usize), b: (usize, usize)| a.0.abs_diff(b.0) + a.1.abs_diff(b.1) == 1;
+        let tail = self.parts.front().copied();
+        let twin_tail = twin.parts.front().copied();
+        if tail.is_some_and(|tail| touching(twin.head, tail)) {
+            // The twin leads into this strand's tail
+            let mut parts = twin.parts;
+            parts.append(&mut self.parts);
+            self.parts = parts;
+        } else if twin_tail.is_some_and(|tail| touching(self.head, tail)) {
+            // This head leads into the twin's tail, whose head takes over
+            let mut parts = twin.parts;
+            self.parts.append(&mut parts);
+            self.head = twin.head;
+            self.direction = twin.direction;
+            self.input_queue = twin.input_queue;
+        } else {
+            self.twin = Some(twin);
+            return false;
+        }
+        true
+    }
+
+    /// Cells of `length` the main strand holds: all of it, less a
+    /// split-off half's share.
+    fn front_length(&self) -> usize {
+        self.length.saturating_sub(self.twin.as_ref().and_then(|t| t.share).unwrap_or(0))
+    }
+
+    /// Whether any part of the snake, either strand with `--two-headed`, is on `pos`.
+    pub fn occupies(&self, pos: (usize, usize)) -> bool {
+        self.parts.contains(&pos) || self.twin.as_ref().is_some_and(|t| t.parts.contains(&pos))
+    }
+
+    /// Move the head, having just stepped onto a portal, out of its
+    /// partner at `exit`. Running into its own body there is fatal.
+    pub fn teleport_head(&mut self, exit: (usize, usize)) {
+        let (r, c) = self.head;
+        self.world[r][c] = self.world[r][c].saturating_sub(1);
+        if let Some(head) = self.parts.back_mut() {
+            *head = exit;
+        }
+        self.head = exit;
+        self.world[exit.0][exit.1] += 1;
+        if self.world[exit.0][exit.1] > 1 {
+            self.is_dead = true;
         }
-        self.head = *self.parts.back().unwrap();
+    }
+
+    /// Send the second head through a portal to `exit`, like `teleport_head`.
+    pub fn teleport_twin(&mut self, exit: (usize, usize)) {
+        let Some(twin) = self.twin.as_mut() else {
+            return;
+        };
+        let (r, c) = twin.head;
+        self.world[r][c] = self.world[r][c].saturating_sub(1);
+        if let Some(head) = twin.parts.back_mut() {
+            *head = exit;
+        }
+        twin.head = exit;
+        self.world[exit.0][exit.1] += 1;
+        if self.world[exit.0][exit.1] > 1 {
+            self.is_dead = true;
+        }
+    }
+
+    /// Where the second head is, if the snake has one.
+    pub fn twin_head(&self) -> Option<(usize, usize)> {
+        self.twin.as_ref().map(|t| t.head)
+    }
+
+    /// Every head the snake has: the main one, then the second.
+    pub fn heads(&self) -> impl Iterator<Item = (usize, usize)> {
+        std::iter::once(self.head).chain(self.twin_head())
     }
 
     pub fn queue_direction(&mut self, dir: Direction) {
         // Buffer up to 3 inputs for smooth turning
         if self.input_queue.len() < 3 {
-            // Check against the last queued direction (or current) to avoid reversals
+            // Check against the last queued direction (or current) to avoid
+            // reversals; a lone head has no body to turn back into
             let last = self.input_queue.back().copied().unwrap_or(self.direction);
-            if dir != last.opposite() && dir != last {
+            if dir != last && (dir != last.opposite() || self.allow_reverse || self.parts.len() == 1) {
                 self.input_queue.push_back(dir);
             }
         }
     }
 
-    pub fn apply_queued_input(&mut self) {
-        if let Some(next) = self.input_queue.pop_front() {
-            if next != self.direction.opposite() {
-                self.direction = next;
+    /// Queue a turn for an independently steered second head.
+    pub fn queue_twin_direction(&mut self, dir: Direction) {
+        let Some(twin) = self.twin.as_mut().filter(|t| t.mode == TwinMode::Independent) else {
+            return;
+        };
+        let last = twin.input_queue.back().copied().unwrap_or(twin.direction);
+        if twin.input_queue.len() < 3 && dir != last && dir != last.opposite() {
+            twin.input_queue.push_back(dir);
+        }
+    }
+
+    /// Take the second head's next queued turn, returning it if it was applied.
+    pub fn apply_twin_input(&mut self) -> Option<Direction> {
+        let twin = self.twin.as_mut()?;
+        let next = twin.input_queue.pop_front()?;
+        if next == twin.direction.opposite() {
+            return None;
+        }
+        twin.direction = next;
+        Some(next)
+    }
+
+    /// Take the next queued turn, returning it if it was applied.
+    pub fn apply_queued_input(&mut self) -> Option<Direction> {
+        let next = self.input_queue.pop_front()?;
+        if next != self.direction.opposite() || self.parts.len() == 1 {
+            self.direction = next;
+        } else if self.allow_reverse {
+            self.reverse();
+        } else {
+            return None;
+        }
+        Some(next)
+    }
+
+    /// U-turn: the tail becomes the head and the snake heads away from its body.
+    /// Occupied cells don't change, so the world grid stays consistent.
+    pub fn reverse(&mut self) {
+        let reversed: VecDeque<(usize, usize)> = self.parts.iter().rev().copied().collect();
+        self.parts = reversed;
+        self.head = *self.parts.back().unwrap();
+        // Grown tails repeat a cell, so find the first segment behind the new head
+        match self.parts.iter().rev().find(|&&p| p != self.head) {
+            Some(&neck) => self.direction = direction_between(neck, self.head),
+            None => self.direction = self.direction.opposite(),
+        }
+    }
+
+    pub fn run_summary(&self) -> RunSummary {
+        RunSummary {
+            food: self.points,
+            growth: self.length.saturating_sub(INITIAL_SNAKE_LENGTH),
+            time_secs: self.survived_ms / 1000,
+        }
+    }
+
+    /// Recompute `score` from the run counters using the configured weights,
+    /// plus `--style-points`, art and coin bonuses, less any accumulated
+    /// penalty.
+    pub fn rescore(&mut self, settings: &Settings) {
+        let earned = GameState::compute_score(&settings.score_formula, &self.run_summary()) + self.style_points + self.art_points + self.coin_points;
+        self.score = earned.saturating_sub(self.penalty);
+    }
+
+    /// Body segments right beside the head, other than the one it just came
+    /// from: each is a near miss.
+    pub fn near_misses(&self) -> usize {
+        let neck = self.parts.len().checked_sub(2).map(|i| self.parts[i]);
+        let (r, c) = self.head;
+        let around = [
+            r.checked_sub(1).map(|r| (r, c)),
+            (r + 1 < self.map_height).then_some((r + 1, c)),
+            c.checked_sub(1).map(|c| (r, c)),
+            (c + 1 < self.map_width).then_some((r, c + 1)),
+        ];
+        around.into_iter().flatten().filter(|&pos| Some(pos) != neck && self.world[pos.0][pos.1] > 0).count()
+    }
+
+    /// The next `cells` cells straight ahead of the head, stopping early at
+    /// a border.
+    pub fn path_ahead(&self, cells: usize, settings: &Settings, border_min: (usize, usize), border_max: (usize, usize)) -> Vec<(usize, usize)> {
+        let mut path = Vec::with_capacity(cells);
+        let mut pos = self.head;
+        for _ in 0..cells {
+            match Snake::step(pos, self.direction, settings, border_min, border_max) {
+                Some(next) if !path.contains(&next) => {
+                    path.push(next);
+                    pos = next;
+                }
+                _ => break,
             }
         }
+        path
     }
 
-    pub fn update_movement(&mut self, settings: &Settings, walls: &[(usize, usize)], border_min: (usize, usize), border_max: (usize, usize)) {
-        let (dr, dc) = self.direction.delta();
-        let new_row = self.head.0 as i32 + dr;
-        let new_col = self.head.1 as i32 + dc;
+    /// Cell entered by moving from `from` in `dir`, or `None` if that leaves the borders.
+    pub fn step(from: (usize, usize), dir: Direction, settings: &Settings, border_min: (usize, usize), border_max: (usize, usize)) -> Option<(usize, usize)> {
+        let (dr, dc) = dir.delta();
+        let new_row = from.0 as i32 + dr;
+        let new_col = from.1 as i32 + dc;
 
         let (bmin_r, bmin_c) = border_min;
         let (bmax_r, bmax_c) = border_max;
         let eff_h = bmax_r - bmin_r;
         let eff_w = bmax_c - bmin_c;
+        let wrapped = (
+            (((new_row - bmin_r as i32) % eff_h as i32 + eff_h as i32) as usize % eff_h) + bmin_r,
+            (((new_col - bmin_c as i32) % eff_w as i32 + eff_w as i32) as usize % eff_w) + bmin_c,
+        );
 
-        let (new_row, new_col) = if settings.disable_borders {
-            (
-                (((new_row - bmin_r as i32) % eff_h as i32 + eff_h as i32) as usize % eff_h) + bmin_r,
-                (((new_col - bmin_c as i32) % eff_w as i32 + eff_w as i32) as usize % eff_w) + bmin_c,
-            )
+        if settings.disable_borders {
+            Some(wrapped)
+        } else if new_row < bmin_r as i32
+            || new_row >= bmax_r as i32
+            || new_col < bmin_c as i32
+            || new_col >= bmax_c as i32
+        {
+            // With --edge-gaps, leaving through an opening wraps
+            let through_gap = match dir {
+                Direction::North | Direction::South => is_edge_gap(from.1.saturating_sub(bmin_c), eff_w, settings.edge_gaps),
+                Direction::East | Direction::West => is_edge_gap(from.0.saturating_sub(bmin_r), eff_h, settings.edge_gaps),
+            };
+            through_gap.then_some(wrapped)
         } else {
-            if new_row < bmin_r as i32
-                || new_row >= bmax_r as i32
-                || new_col < bmin_c as i32
-                || new_col >= bmax_c as i32
-            {
-                self.is_dead = true;
-                return;
+            Some((new_row as usize, new_col as usize))
+        }
+    }
+
+    /// Rail assist: pick a perpendicular direction that keeps the snake alive,
+    /// preferring the side with more open cells ahead.
+    fn assist_turn(&self, settings: &Settings, walls: &[(usize, usize)], border_min: (usize, usize), border_max: (usize, usize)) -> Option<Direction> {
+        let turns = match self.direction {
+            Direction::East | Direction::West => [Direction::North, Direction::South],
+            Direction::North | Direction::South => [Direction::West, Direction::East],
+        };
+        let room = |dir: Direction| {
+            let mut pos = self.head;
+            let mut free = 0;
+            while let Some(cell) = Self::step(pos, dir, settings, border_min, border_max) {
+                if walls.contains(&cell) || self.world[cell.0][cell.1] > 0 {
+                    break;
+                }
+                pos = cell;
+                free += 1;
             }
-            (new_row as usize, new_col as usize)
+            free
+        };
+        turns
+            .into_iter()
+            .map(|dir| (dir, room(dir)))
+            .filter(|&(_, free)| free > 0)
+            .max_by_key(|&(_, free)| free)
+            .map(|(dir, _)| dir)
+    }
+
+    pub fn update_movement(&mut self, settings: &Settings, walls: &[(usize, usize)], border_min: (usize, usize), border_max: (usize, usize)) {
+        if settings.rewind {
+            self.remember();
+        }
+        self.wrapped = false;
+        self.vacated = None;
+        let mut next = Self::step(self.head, self.direction, settings, border_min, border_max);
+        if next.is_none() && settings.assist {
+            if let Some(dir) = self.assist_turn(settings, walls, border_min, border_max) {
+                self.direction = dir;
+                self.input_queue.clear();
+                next = Self::step(self.head, dir, settings, border_min, border_max);
+            }
+        }
+        let Some((new_row, new_col)) = next else {
+            self.is_dead = true;
+            return;
         };
 
         // Check wall collision
@@ -132,16 +526,34 @@ impl Snake {
             return;
         }
 
+        let (dr, dc) = self.direction.delta();
+        self.wrapped = (new_row as i32, new_col as i32) != (self.head.0 as i32 + dr, self.head.1 as i32 + dc);
+        if self.wrapped && settings.wrap_limit > 0 {
+            self.wraps_used += 1;
+            if self.wraps_used > settings.wrap_limit {
+                self.is_dead = true;
+                return;
+            }
+        }
         self.head = (new_row, new_col);
         self.parts.push_back(self.head);
+        // Only this snake's body is known here; close enough for the clock
+        let fill = board_fill(walls.len() + self.parts.len(), border_min, border_max);
+        self.survived_ms += settings.effective_speed(self.length, fill);
 
         self.food_eaten = self.head == self.food;
         if self.food_eaten {
-            self.length += 1;
-            self.score += 1;
-        } else {
+            self.points += self.food_value;
+            self.ticks_since_food = 0;
+        }
+        // At the --max-length cap food still scores, but the tail moves on.
+        // A strand the second head grew last move catches up instead.
+        let grew = self.food_eaten && self.grow(settings.max_length);
+        let short = self.twin.is_some() && self.parts.len() <= self.front_length();
+        if !grew && !short {
             if let Some(tail) = self.parts.pop_front() {
                 self.world[tail.0][tail.1] = self.world[tail.0][tail.1].saturating_sub(1);
+                self.vacated = Some(tail);
             }
         }
 
@@ -149,9 +561,297 @@ impl Snake {
         if self.world[self.head.0][self.head.1] > 1 {
             self.is_dead = true;
         }
+        if !self.is_dead {
+            self.advance_twin(settings, walls, border_min, border_max);
+        }
+        if !self.is_dead {
+            self.merge();
+        }
+        self.near_miss_flash = self.near_miss_flash.saturating_sub(1);
+        if settings.style_points && !self.is_dead {
+            let bonus = self.near_misses().min(STYLE_POINTS_PER_TICK);
+            if bonus > 0 {
+                self.style_points += bonus;
+                self.near_miss_flash = NEAR_MISS_FLASH_FRAMES;
+            }
+        }
+        self.rescore(settings);
+
+        if self.wrapped && settings.wrap_penalty > 0 {
+            // Saturates at zero: a wrap never takes more than the current score
+            self.penalty += self.score.min(settings.wrap_penalty);
+            self.rescore(settings);
+        }
+
+        if !self.is_dead && !self.food_eaten {
+            self.starve(settings.hungry);
+            self.rescore(settings);
+        }
+    }
+
+    fn remember(&mut self) {
+        if self.history.len() == REWIND_TICKS {
+            self.history.pop_front();
+        }
+        self.history.push_back(SnakeSnapshot {
+            parts: self.parts.clone(),
+            direction: self.direction,
+            length: self.length,
+            score: self.score,
+            points: self.points,
+            survived_ms: self.survived_ms,
+            penalty: self.penalty,
+            style_points: self.style_points,
+            art_points: self.art_points,
+            coin_points: self.coin_points,
+            wraps_used: self.wraps_used,
+            ticks_since_food: self.ticks_since_food,
+        });
+    }
+
+    /// Spend a rewind charge to put the snake back where it was
+    /// `REWIND_TICKS` moves ago, score included. Refused without a charge,
+    /// before that many moves have been made, or when the board has changed
+    /// under the old body since (a wall on it, or the border shrunk past
+    /// it). Returns whether it rewound.
+    pub fn rewind(&mut self, walls: &[(usize, usize)], border_min: (usize, usize), border_max: (usize, usize)) -> bool {
+        if self.rewind_charges == 0 || self.history.len() < REWIND_TICKS {
+            return false;
+        }
+        let inside = |&(r, c): &(usize, usize)| r >= border_min.0 && r < border_max.0 && c >= border_min.1 && c < border_max.1;
+        let blocked = |pos: &(usize, usize)| !inside(pos) || walls.contains(pos);
+        if self.history.front().is_none_or(|snap| snap.parts.iter().any(blocked)) {
+            return false;
+        }
+        let Some(snap) = self.history.pop_front() else {
+            return false;
+        };
+        self.history.clear();
+        self.rewind_charges -= 1;
+        self.set_body(snap.parts);
+        self.direction = snap.direction;
+        self.length = snap.length;
+        self.score = snap.score;
+        self.points = snap.points;
+        self.survived_ms = snap.survived_ms;
+        self.penalty = snap.penalty;
+        self.style_points = snap.style_points;
+        self.art_points = snap.art_points;
+        self.coin_points = snap.coin_points;
+        self.wraps_used = snap.wraps_used;
+        self.ticks_since_food = snap.ticks_since_food;
+        self.input_queue.clear();
+        self.food_eaten = false;
+        self.wrapped = false;
+        self.vacated = None;
+        true
+    }
+
+    /// Move the `--two-headed` second head one cell. It eats the shared
+    /// food too, and running it into a wall, the edge or any body is fatal.
+    fn advance_twin(&mut self, settings: &Settings, walls: &[(usize, usize)], border_min: (usize, usize), border_max: (usize, usize)) {
+        let Some(mut twin) = self.twin.take() else {
+            return;
+        };
+        if twin.mode == TwinMode::Mirrored {
+            twin.direction = self.direction.opposite();
+        }
+        match Self::step(twin.head, twin.direction, settings, border_min, border_max) {
+            Some(next) if !walls.contains(&next) => {
+                twin.head = next;
+                twin.parts.push_back(next);
+                if next == self.food && !self.food_eaten {
+                    self.food_eaten = true;
+                    self.points += self.food_value;
+                    self.ticks_since_food = 0;
+                    // A split-off half keeps what it eats
+                    if self.grow(settings.max_length) {
+                        if let Some(share) = twin.share.as_mut() {
+                            *share += 1;
+                        }
+                    }
+                }
+                while twin.parts.len() > twin.share.unwrap_or(self.length) {
+                    if let Some((r, c)) = twin.parts.pop_front() {
+                        self.world[r][c] = self.world[r][c].saturating_sub(1);
+                    }
+                }
+                self.world[next.0][next.1] += 1;
+                if self.world[next.0][next.1] > 1 {
+                    self.is_dead = true;
+                }
+            }
+            _ => self.is_dead = true,
+        }
+        self.twin = Some(twin);
+    }
+
+    /// Sit out a tick (`--p2-speed`): the snake didn't move, so it
+    /// neither ate nor wrapped.
+    pub fn rest(&mut self) {
+        self.food_eaten = false;
+        self.wrapped = false;
+        self.vacated = None;
+    }
+
+    /// `--hungry`: lose the tail segment every `interval` ticks without
+    /// food, dying once that would leave fewer than `HUNGRY_MIN_LENGTH`.
+    pub fn starve(&mut self, interval: usize) {
+        if interval == 0 {
+            return;
+        }
+        self.ticks_since_food += 1;
+        if self.ticks_since_food < interval {
+            return;
+        }
+        self.ticks_since_food = 0;
+        if self.length <= HUNGRY_MIN_LENGTH {
+            self.is_dead = true;
+            return;
+        }
+        self.length -= 1;
+        if self.parts.len() > 1 {
+            if let Some(tail) = self.parts.pop_front() {
+                self.world[tail.0][tail.1] = self.world[tail.0][tail.1].saturating_sub(1);
+            }
+        }
+    }
+
+    /// Add a segment to `length` unless that would pass `max_length`
+    /// (0 = no cap). Returns whether it grew.
+    pub fn grow(&mut self, max_length: usize) -> bool {
+        if self.at_length_cap(max_length) {
+            return false;
+        }
+        self.length += 1;
+        true
+    }
+
+    /// Grow for food the second head ate; a split-off half keeps the
+    /// extra segment.
+    pub fn grow_twin(&mut self, max_length: usize) -> bool {
+        let grew = self.grow(max_length);
+        if let Some(share) = self.twin.as_mut().and_then(|t| t.share.as_mut()).filter(|_| grew) {
+            *share += 1;
+        }
+        grew
+    }
+
+    /// `--steal`: the opponent ate, costing this snake a point. The score
+    /// never drops below zero.
+    pub fn lose_point(&mut self) {
+        let taken = self.score.min(1);
+        self.penalty += taken;
+        self.score -= taken;
+    }
+
+    /// `--steal`: an opponent's head crossed this body at `pos`, so everything
+    /// from the tail up to and including that cell falls away. The head
+    /// can't be cut. Returns the segments lost.
+    pub fn cut_at(&mut self, pos: (usize, usize), settings: &Settings) -> usize {
+        let Some(i) = self.parts.iter().position(|&p| p == pos) else {
+            return 0;
+        };
+        if i + 1 >= self.parts.len() {
+            return 0;
+        }
+        for (r, c) in self.parts.drain(..=i) {
+            self.world[r][c] = self.world[r][c].saturating_sub(1);
+        }
+        self.length = self.length.saturating_sub(i + 1).max(1);
+        self.rescore(settings);
+        i + 1
+    }
+
+    /// Wraps left before the next one is fatal; None without `--wrap-limit`.
+    pub fn wraps_left(&self, wrap_limit: usize) -> Option<usize> {
+        (wrap_limit > 0).then(|| wrap_limit.saturating_sub(self.wraps_used))
+    }
+
+    pub fn at_length_cap(&self, max_length: usize) -> bool {
+        max_length > 0 && self.length >= max_length
+    }
+
+    /// One more starvation step would kill the snake.
+    pub fn starving(&self) -> bool {
+        self.length <= HUNGRY_MIN_LENGTH
+    }
+
+    /// How well fed the snake is under `--hungry`: 1.0 right after eating,
+    /// falling toward 0 as the next segment loss nears, and 0 once it is
+    /// starving. None when hunger is off.
+    pub fn health(&self, hungry: usize) -> Option<f32> {
+        if hungry == 0 {
+            return None;
+        }
+        if self.starving() {
+            return Some(0.0);
+        }
+        Some(1.0 - self.ticks_since_food.min(hungry) as f32 / hungry as f32)
     }
 }
 
+/// Direction of a single step from `from` to the adjacent cell `to`,
+/// including steps that wrapped around an edge.
+fn direction_between(from: (usize, usize), to: (usize, usize)) -> Direction {
+    if from.0 == to.0 {
+        if to.1 == from.1 + 1 || from.1 > to.1 + 1 {
+            Direction::East
+        } else {
+            Direction::West
+        }
+    } else if to.0 == from.0 + 1 || from.0 > to.0 + 1 {
+        Direction::South
+    } else {
+        Direction::North
+    }
+}
+
+/// Settle a tick's contact between the two players. A head on the other
+/// snake's body is fatal, unless `--steal` lets it cut that body instead;
+/// heads meeting always kill both.
+pub fn collide(p1: &mut Snake, p2: &mut Snake, settings: &Settings) {
+    if settings.steal && p1.head != p2.head {
+        p2.cut_at(p1.head, settings);
+        p1.cut_at(p2.head, settings);
+    }
+    if p1.parts.contains(&p2.head) {
+        p2.is_dead = true;
+    }
+    if p2.parts.contains(&p1.head) {
+        p1.is_dead = true;
+    }
+}
+
+/// Whether cell `offset` along a border side `len` cells long is one of
+/// the `gaps` evenly spaced `--edge-gaps` openings. Opposite sides share
+/// the same offsets, so an opening always leads into another.
+pub fn is_edge_gap(offset: usize, len: usize, gaps: usize) -> bool {
+    (1..=gaps).any(|i| i * len / (gaps + 1) == offset)
+}
+
+/// Where a `--two-headed` second head starts for a snake spawned at
+/// `head` facing `dir`: two rows off (below if there's room), mirrored
+/// left to right and facing the other way.
+pub fn twin_spawn(head: (usize, usize), dir: Direction, width: usize, height: usize) -> ((usize, usize), Direction) {
+    let row = if head.0 + 2 < height { head.0 + 2 } else { head.0.saturating_sub(2) };
+    ((row, width.saturating_sub(1 + head.1)), dir.opposite())
+}
+
+/// Cells (tail first) of a `length`-cell snake with its head at `head`
+/// facing `dir`, or None if any of them falls off a `width`x`height` board.
+pub fn spawn_cells(head: (usize, usize), dir: Direction, length: usize, width: usize, height: usize) -> Option<Vec<(usize, usize)>> {
+    let (dr, dc) = dir.delta();
+    (0..length.max(1))
+        .rev()
+        .map(|i| {
+            let r = head.0 as i64 - dr as i64 * i as i64;
+            let c = head.1 as i64 - dc as i64 * i as i64;
+            (r >= 0 && c >= 0 && (r as usize) < height && (c as usize) < width).then_some((r as usize, c as usize))
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -236,7 +936,7 @@ mod tests {
 
     #[test]
     fn test_snake_wrap_around() {
-        let settings = Settings::parse_from(&["test", "--disable-borders"]);
+        let settings = Settings::parse_from(["test", "--disable-borders"]);
         let mut settings = settings.resolve();
         settings.map_width = 20;
         settings.map_height = 20;
@@ -249,6 +949,307 @@ mod tests {
         assert!(!snake.is_dead);
     }
 
+    #[test]
+    fn test_edge_gaps_wrap_only_through_openings() {
+        let settings = Settings::parse_from(["test", "--edge-gaps", "1"]).resolve();
+        // One opening per side, halfway along: row 10 and column 10 on 20x20
+        assert!(is_edge_gap(10, 20, 1) && !is_edge_gap(9, 20, 1));
+
+        let mut snake = Snake::new(20, 20);
+        snake.spawn_at((10, 17), Direction::East, INITIAL_SNAKE_LENGTH);
+        for _ in 0..2 {
+            snake.update_movement(&settings, &[], (0, 0), (20, 20));
+        }
+        assert_eq!(snake.head, (10, 19));
+        snake.update_movement(&settings, &[], (0, 0), (20, 20));
+        assert!(!snake.is_dead && snake.wrapped);
+        assert_eq!(snake.head, (10, 0));
+
+        // One row over the border is solid
+        snake.spawn_at((9, 18), Direction::East, INITIAL_SNAKE_LENGTH);
+        snake.update_movement(&settings, &[], (0, 0), (20, 20));
+        assert!(!snake.is_dead);
+        snake.update_movement(&settings, &[], (0, 0), (20, 20));
+        assert!(snake.is_dead);
+
+        // Top and bottom openings line up too
+        assert_eq!(Snake::step((0, 10), Direction::North, &settings, (0, 0), (20, 20)), Some((19, 10)));
+        assert_eq!(Snake::step((0, 11), Direction::North, &settings, (0, 0), (20, 20)), None);
+    }
+
+    #[test]
+    fn test_snake_wrap_penalty() {
+        let mut settings = Settings::parse_from(["test", "--disable-borders", "--wrap-penalty", "2"]).resolve();
+        settings.map_width = 20;
+        settings.map_height = 20;
+        let mut snake = Snake::new(20, 20);
+        snake.points = 5;
+        snake.rescore(&settings);
+
+        // Head starts at column 11 heading East: 8 plain moves, then a wrap
+        for _ in 0..8 {
+            snake.update_movement(&settings, &[], (0, 0), (20, 20));
+            assert!(!snake.wrapped);
+            assert_eq!(snake.score, 5);
+        }
+        snake.update_movement(&settings, &[], (0, 0), (20, 20));
+        assert!(snake.wrapped);
+        assert_eq!(snake.head.1, 0);
+        assert_eq!(snake.score, 3);
+
+        snake.update_movement(&settings, &[], (0, 0), (20, 20));
+        assert!(!snake.wrapped);
+        assert_eq!(snake.score, 3);
+    }
+
+    #[test]
+    fn test_style_points_for_near_misses() {
+        let mut settings = Settings::parse_from(["test", "--style-points"]).resolve();
+        settings.map_width = 20;
+        settings.map_height = 20;
+        let mut snake = Snake::new(20, 20);
+        snake.spawn_at((5, 6), Direction::East, 5);
+
+        // Heading straight on and turning away: nothing beside the head
+        snake.update_movement(&settings, &[], (0, 0), (20, 20));
+        snake.queue_direction(Direction::North);
+        snake.apply_queued_input();
+        snake.update_movement(&settings, &[], (0, 0), (20, 20));
+        assert_eq!((snake.style_points, snake.near_miss_flash), (0, 0));
+
+        // A tight U-turn runs the head right alongside the body
+        snake.queue_direction(Direction::West);
+        snake.apply_queued_input();
+        snake.update_movement(&settings, &[], (0, 0), (20, 20));
+        assert_eq!(snake.head, (4, 6));
+        assert_eq!(snake.style_points, 1);
+        assert_eq!(snake.near_miss_flash, NEAR_MISS_FLASH_FRAMES);
+        let formula = GameState::compute_score(&settings.score_formula, &snake.run_summary());
+        assert_eq!(snake.score, formula + 1);
+
+        // Into a pocket with body on three sides: capped per move
+        snake.set_body([(0, 0), (9, 5), (11, 5), (10, 4), (10, 6)]);
+        snake.direction = Direction::West;
+        snake.update_movement(&settings, &[], (0, 0), (20, 20));
+        assert!(!snake.is_dead);
+        assert_eq!(snake.style_points, 1 + STYLE_POINTS_PER_TICK);
+
+        // Off by default
+        let plain = Settings::parse_from(["test"]).resolve();
+        snake.set_body([(0, 0), (9, 5), (11, 5), (10, 4), (10, 6)]);
+        snake.style_points = 0;
+        snake.update_movement(&plain, &[], (0, 0), (20, 20));
+        assert_eq!(snake.style_points, 0);
+    }
+
+    #[test]
+    fn test_rewind_restores_earlier_head_and_score() {
+        let settings = Settings::parse_from(["test", "--rewind"]).resolve();
+        let mut snake = Snake::new(20, 20);
+        snake.spawn_at((5, 6), Direction::East, 3);
+        snake.rewind_charges = 1;
+        snake.food = (5, 8);
+
+        // Too early: not enough moves behind it yet
+        snake.update_movement(&settings, &[], (0, 0), (20, 20));
+        assert!(!snake.rewind(&[], (0, 0), (20, 20)));
+        assert_eq!(snake.rewind_charges, 1);
+
+        let (head, score, length) = (snake.head, snake.score, snake.length);
+        for _ in 0..REWIND_TICKS {
+            snake.update_movement(&settings, &[], (0, 0), (20, 20));
+        }
+        assert!(snake.score > score && snake.head != head);
+
+        // Not onto a wall that went up since, nor past a border that shrank
+        assert!(!snake.rewind(&[head], (0, 0), (20, 20)));
+        assert!(!snake.rewind(&[], (0, 0), (20, head.1)));
+        assert_eq!(snake.rewind_charges, 1);
+
+        assert!(snake.rewind(&[], (0, 0), (20, 20)));
+        assert_eq!((snake.head, snake.score, snake.length), (head, score, length));
+        assert_eq!(snake.rewind_charges, 0);
+        // The charge is spent and the history starts over
+        assert!(!snake.rewind(&[], (0, 0), (20, 20)));
+    }
+
+    #[test]
+    fn test_two_heads_advance_and_share_growth() {
+        let settings = Settings::parse_from(["test", "--two-headed", "mirrored"]).resolve();
+        let mut snake = Snake::new(20, 20);
+        snake.spawn_at((10, 8), Direction::East, 3);
+        assert!(snake.add_twin(TwinMode::Mirrored));
+        let twin_head = |s: &Snake| s.twin.as_ref().unwrap().head;
+        assert_eq!(twin_head(&snake), (12, 11));
+
+        // Both heads move each tick, the second one the other way
+        snake.food = (0, 0);
+        snake.update_movement(&settings, &[], (0, 0), (20, 20));
+        assert_eq!((snake.head, twin_head(&snake)), ((10, 9), (12, 10)));
+
+        // Food eaten by the second head grows both strands
+        snake.food = (12, 9);
+        snake.update_movement(&settings, &[], (0, 0), (20, 20));
+        assert!(snake.food_eaten && !snake.is_dead);
+        assert_eq!((snake.points, snake.length), (1, 4));
+        snake.food = (0, 0);
+        snake.update_movement(&settings, &[], (0, 0), (20, 20));
+        assert_eq!(snake.parts.len(), 4);
+        assert_eq!(snake.twin.as_ref().unwrap().parts.len(), 4);
+
+        // ...and so does food eaten by the first
+        snake.food = (10, 12);
+        snake.update_movement(&settings, &[], (0, 0), (20, 20));
+        assert_eq!((snake.points, snake.length, snake.parts.len()), (2, 5, 5));
+        assert_eq!(snake.twin.as_ref().unwrap().parts.len(), 5);
+        assert!(snake.occupies(twin_head(&snake)));
+
+        // Independent: the second head turns on its own queue
+        let mut snake = Snake::new(20, 20);
+        snake.spawn_at((10, 8), Direction::East, 3);
+        snake.add_twin(TwinMode::Independent);
+        snake.queue_twin_direction(Direction::South);
+        assert_eq!(snake.apply_twin_input(), Some(Direction::South));
+        snake.update_movement(&settings, &[], (0, 0), (20, 20));
+        assert_eq!((snake.head, twin_head(&snake)), ((10, 9), (13, 11)));
+
+        // The second head is as lethal as the first: straight into the wall
+        snake.update_movement(&settings, &[(14, 11)], (0, 0), (20, 20));
+        assert!(snake.is_dead);
+    }
+
+    #[test]
+    fn test_snake_wrap_limit() {
+        let mut settings = Settings::parse_from(["test", "--disable-borders", "--wrap-limit", "2"]).resolve();
+        settings.map_width = 6;
+        settings.map_height = 6;
+        let mut snake = Snake::new(6, 6);
+        assert!(snake.spawn_at((2, 4), Direction::East, INITIAL_SNAKE_LENGTH));
+        assert_eq!(snake.wraps_left(settings.wrap_limit), Some(2));
+
+        // Each lap of the 6-wide board is one wrap
+        for wraps in 1..=2 {
+            for _ in 0..6 {
+                snake.update_movement(&settings, &[], (0, 0), (6, 6));
+            }
+            assert!(!snake.is_dead, "survives wrap {wraps}");
+            assert_eq!(snake.wraps_used, wraps);
+        }
+        assert_eq!(snake.wraps_left(settings.wrap_limit), Some(0));
+        snake.update_movement(&settings, &[], (0, 0), (6, 6));
+        assert!(!snake.is_dead);
+        snake.update_movement(&settings, &[], (0, 0), (6, 6));
+        assert!(snake.is_dead, "the third wrap is over the limit");
+        assert_eq!(snake.head, (2, 5));
+
+        assert_eq!(Snake::new(6, 6).wraps_left(0), None);
+    }
+
+    #[test]
+    fn test_snake_assist_turns_along_border() {
+        let settings = Settings::parse_from(["test", "--assist"]);
+        let mut settings = settings.resolve();
+        settings.map_width = 20;
+        settings.map_height = 20;
+        let mut snake = Snake::new(20, 20);
+        // Heading East with no input: the assist should slide along the right edge
+        for _ in 0..12 {
+            snake.update_movement(&settings, &[], (0, 0), (20, 20));
+            assert!(!snake.is_dead);
+        }
+        assert_eq!(snake.head.1, 19);
+        assert!(matches!(snake.direction, Direction::North | Direction::South));
+    }
+
+    #[test]
+    fn test_snake_allow_reverse() {
+        let settings = Settings::parse_from(["test", "--allow-reverse"]);
+        let mut settings = settings.resolve();
+        settings.map_width = 20;
+        settings.map_height = 20;
+        let mut snake = Snake::new(20, 20);
+        snake.allow_reverse = true;
+        let tail = *snake.parts.front().unwrap();
+        let head = snake.head;
+
+        snake.queue_direction(Direction::West);
+        snake.apply_queued_input();
+        assert_eq!(snake.head, tail);
+        assert_eq!(*snake.parts.front().unwrap(), head);
+        assert_eq!(snake.direction, Direction::West);
+
+        snake.update_movement(&settings, &[], (0, 0), (20, 20));
+        assert!(!snake.is_dead);
+        assert_eq!(snake.head, (tail.0, tail.1 - 1));
+    }
+
+    #[test]
+    fn test_split_divides_and_merge_rejoins() {
+        let settings = Settings::parse_from(["test", "--powerups", "--score-formula", "food=1,length=1"]).resolve();
+        let mut snake = Snake::new(20, 20);
+        snake.set_body([(10, 10), (10, 11), (11, 11), (12, 11), (12, 10), (13, 10)]);
+        snake.direction = Direction::South;
+        snake.length = 6;
+        snake.food = (0, 0);
+        snake.rescore(&settings);
+        assert_eq!(snake.score, 3);
+
+        // The tail half breaks off, its head the old tail end facing away;
+        // the length, and so the score, is still the whole snake's
+        assert!(snake.split());
+        let twin = snake.twin.as_ref().unwrap();
+        assert_eq!((twin.head, twin.direction, twin.share), ((10, 10), Direction::West, Some(3)));
+        assert_eq!(snake.parts.len() + twin.parts.len(), 6);
+        assert_eq!((snake.head, snake.length), ((13, 10), 6));
+        snake.rescore(&settings);
+        assert_eq!(snake.score, 3);
+        assert!(!snake.split(), "only one split at a time");
+        assert!(!snake.merge(), "the halves aren't touching yet");
+
+        // The split-off head eats on its way into the other half's tail:
+        // only that half grows, then the two rejoin
+        snake.food = (11, 10);
+        snake.queue_twin_direction(Direction::South);
+        assert_eq!(snake.apply_twin_input(), Some(Direction::South));
+        snake.update_movement(&settings, &[], (0, 0), (20, 20));
+        assert!(!snake.is_dead);
+        assert!(snake.twin.is_none());
+        assert_eq!((snake.head, snake.length), ((14, 10), 7));
+        assert_eq!(snake.parts, [(11, 11), (10, 11), (10, 10), (11, 10), (12, 10), (13, 10), (14, 10)]);
+        assert_eq!(snake.score, 5);
+
+        // An odd length splits unevenly, and neither half gains a cell
+        snake.spawn_at((5, 10), Direction::East, 5);
+        snake.food = (0, 0);
+        assert!(snake.split());
+        snake.update_movement(&settings, &[], (0, 0), (20, 20));
+        let twin = snake.twin.as_ref().unwrap();
+        assert_eq!((snake.parts.len(), twin.parts.len(), snake.length), (3, 2, 5));
+
+        // Too short to split
+        snake.spawn_at((5, 5), Direction::East, 3);
+        assert!(!snake.split());
+    }
+
+    #[test]
+    fn test_lone_head_can_reverse() {
+        let settings = Settings::parse_from(["test"]).resolve();
+        let mut snake = Snake::new(20, 20);
+        snake.spawn_at((10, 10), Direction::East, 1);
+        assert_eq!(snake.parts.len(), 1);
+        snake.queue_direction(Direction::West);
+        assert_eq!(snake.apply_queued_input(), Some(Direction::West));
+        snake.update_movement(&settings, &[], (0, 0), (20, 20));
+        assert!(!snake.is_dead);
+        assert_eq!(snake.head, (10, 9));
+
+        // With a body behind the head the U-turn is still refused
+        snake.spawn_at((10, 10), Direction::East, INITIAL_SNAKE_LENGTH);
+        snake.queue_direction(Direction::West);
+        assert_eq!(snake.apply_queued_input(), None);
+        assert_eq!(snake.direction, Direction::East);
+    }
+
     #[test]
     fn test_snake_food_eating() {
         let settings = Settings::parse_from::<[&str; 0], &str>([]);
@@ -264,4 +1265,136 @@ mod tests {
         assert_eq!(snake.length, old_length + 1);
         assert_eq!(snake.score, 1);
     }
+
+    #[test]
+    fn test_max_length_caps_growth() {
+        let mut settings = Settings::parse_from(["test", "--max-length", "4"]).resolve();
+        settings.map_width = 20;
+        settings.map_height = 20;
+        let mut snake = Snake::new(20, 20);
+        snake.spawn_at((5, 5), Direction::East, 3);
+
+        // Below the cap food grows the snake as usual
+        snake.food = (5, 6);
+        snake.update_movement(&settings, &[], (0, 0), (20, 20));
+        assert_eq!((snake.length, snake.parts.len(), snake.score), (4, 4, 1));
+        assert!(snake.at_length_cap(settings.max_length));
+
+        // At the cap it still scores, but the tail moves on
+        let tail = *snake.parts.front().unwrap();
+        snake.food = (5, 7);
+        snake.update_movement(&settings, &[], (0, 0), (20, 20));
+        assert!(snake.food_eaten);
+        assert_eq!((snake.length, snake.parts.len(), snake.score), (4, 4, 2));
+        assert!(!snake.parts.contains(&tail));
+        assert!(!snake.grow(settings.max_length));
+        assert!(!snake.at_length_cap(0));
+    }
+
+    #[test]
+    fn test_spawn_at() {
+        let mut snake = Snake::new(20, 20);
+        assert!(snake.spawn_at((5, 5), Direction::South, 4));
+        assert_eq!(snake.parts, VecDeque::from(vec![(2, 5), (3, 5), (4, 5), (5, 5)]));
+        assert_eq!(snake.head, (5, 5));
+        assert_eq!(snake.direction, Direction::South);
+        assert_eq!(snake.length, 4);
+
+        assert!(snake.spawn_at((10, 0), Direction::West, 3));
+        assert_eq!(snake.parts, VecDeque::from(vec![(10, 2), (10, 1), (10, 0)]));
+
+        // Moves off the head the way it faces
+        let settings = Settings::parse_from(["test"]).resolve();
+        snake.update_movement(&settings, &[], (0, 0), (20, 20));
+        assert!(snake.is_dead);
+
+        // A body that would hang off the board is refused
+        let mut fresh = Snake::new(20, 20);
+        let before = fresh.parts.clone();
+        assert!(!fresh.spawn_at((1, 5), Direction::South, 4));
+        assert!(!fresh.spawn_at((5, 18), Direction::West, 3));
+        assert_eq!(fresh.parts, before);
+    }
+
+    #[test]
+    fn test_start_pos_options() {
+        use crate::rng::GameRng;
+
+        let spawned = |pos: &str, seed: u64| {
+            let mut settings = Settings::parse_from(["test", "--start-pos", pos]).resolve();
+            settings.map_width = 12;
+            settings.map_height = 10;
+            assert!(settings.validate().is_ok(), "{pos}");
+            let mut rng = GameRng::seeded(settings.rng, seed);
+            let (head, dir) = settings.round_spawn(0, 12, 10, &mut rng);
+            let mut snake = Snake::new(12, 10);
+            assert!(snake.spawn_at(head, dir, INITIAL_SNAKE_LENGTH), "{pos} fits");
+            assert!(snake.parts.iter().all(|&(r, c)| r < 10 && c < 12));
+            snake.parts
+        };
+        for pos in ["center", "top-left", "bottom-right", "random", "4,7"] {
+            spawned(pos, 1);
+        }
+        assert_eq!(spawned("4,7", 1).back(), Some(&(4, 7)));
+
+        // Random placement follows the seed
+        assert_eq!(spawned("random", 7), spawned("random", 7));
+        assert!((1..20).any(|seed| spawned("random", seed) != spawned("random", 0)));
+
+        // On a loaded board, only cells inside its border and off its walls
+        let mut settings = Settings::parse_from(["test", "--start-pos", "random"]).resolve();
+        let walls: Vec<String> = (2..8).map(|r| format!("{r},5")).collect();
+        settings.use_snapshot(crate::snapshot::Snapshot::parse_layout(12, 10, &format!("2,1 8,11 {}", walls.join(" "))).unwrap());
+        for seed in 0..50 {
+            let (head, dir) = settings.round_spawn(0, 12, 10, &mut GameRng::seeded(settings.rng, seed));
+            let cells = spawn_cells(head, dir, INITIAL_SNAKE_LENGTH, 12, 10).unwrap();
+            assert!(cells.iter().all(|&(r, c)| (2..8).contains(&r) && (1..11).contains(&c) && c != 5), "seed {seed}: {cells:?}");
+        }
+
+        // An explicit cell whose body would hang off the board is rejected
+        let mut settings = Settings::parse_from(["test", "--start-pos", "4,0"]).resolve();
+        settings.map_width = 12;
+        settings.map_height = 10;
+        assert!(settings.validate().is_err());
+        assert!("4;7".parse::<StartPos>().is_err());
+    }
+
+    #[test]
+    fn test_hungry_snake_starves() {
+        let mut settings = Settings::parse_from(["test", "--hungry", "5", "--disable-borders"]).resolve();
+        settings.map_width = 20;
+        settings.map_height = 20;
+        let mut snake = Snake::new(20, 20);
+        let tick = |snake: &mut Snake| snake.update_movement(&settings, &[], (0, 0), (20, 20));
+
+        for _ in 0..4 {
+            tick(&mut snake);
+        }
+        assert_eq!((snake.length, snake.parts.len()), (3, 3));
+        tick(&mut snake);
+        assert_eq!((snake.length, snake.parts.len()), (2, 2));
+        assert!(snake.starving());
+
+        // Eating resets the countdown
+        for _ in 0..3 {
+            tick(&mut snake);
+        }
+        snake.food = Snake::step(snake.head, snake.direction, &settings, (0, 0), (20, 20)).unwrap();
+        tick(&mut snake);
+        assert_eq!(snake.length, 3);
+        for _ in 0..4 {
+            tick(&mut snake);
+        }
+        assert_eq!(snake.length, 3);
+        tick(&mut snake);
+        assert_eq!(snake.length, 2);
+
+        // At the floor the next starvation step is fatal
+        for _ in 0..4 {
+            tick(&mut snake);
+            assert!(!snake.is_dead);
+        }
+        tick(&mut snake);
+        assert!(snake.is_dead);
+    }
 }
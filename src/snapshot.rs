@@ -0,0 +1,351 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::config::{Direction, Settings};
+use crate::game_map::GameMap;
+use crate::highscore::data_path;
+use crate::snake::Snake;
+
+const SNAPSHOT_MAGIC: &str = "snake-term-snapshot 1";
+
+/// Serializable state of a singleplayer run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub width: usize,
+    pub height: usize,
+    pub parts: Vec<(usize, usize)>,
+    pub direction: Direction,
+    pub length: usize,
+    pub points: usize,
+    pub survived_ms: u64,
+    pub penalty: usize,
+    pub coin_points: usize,
+    pub food: (usize, usize),
+    pub walls: Vec<(usize, usize)>,
+    pub border_min: (usize, usize),
+    pub border_max: (usize, usize),
+    pub shrink_timer: usize,
+    pub frame_count: usize,
+}
+
+impl Snapshot {
+    pub fn capture(snake: &Snake, map: &GameMap, frame_count: usize) -> Self {
+        Snapshot {
+            width: map.width,
+            height: map.height,
+            parts: snake.parts.iter().copied().collect(),
+            direction: snake.direction,
+            length: snake.length,
+            points: snake.points,
+            survived_ms: snake.survived_ms,
+            penalty: snake.penalty,
+            coin_points: snake.coin_points,
+            food: snake.food,
+            walls: map.walls.clone(),
+            border_min: map.border_min,
+            border_max: map.border_max,
+            shrink_timer: map.shrink_timer,
+            frame_count,
+        }
+    }
+
+    pub fn restore_snake(&self, snake: &mut Snake, settings: &Settings) {
+        snake.reset();
+        snake.set_body(self.parts.iter().copied());
+        snake.direction = self.direction;
+        snake.length = self.length;
+        snake.points = self.points;
+        snake.survived_ms = self.survived_ms;
+        snake.penalty = self.penalty;
+        snake.coin_points = self.coin_points;
+        snake.food = self.food;
+        snake.rescore(settings);
+    }
+
+    pub fn restore_map(&self, map: &mut GameMap) {
+        self.restore_layout(map);
+        map.shrink_timer = self.shrink_timer;
+    }
+
+    /// Apply just the board layout (walls and borders), clearing any food,
+    /// for `--from-snapshot` runs that start a fresh snake on it.
+    pub fn restore_layout(&self, map: &mut GameMap) {
+        map.walls = self.walls.clone();
+        map.border_min = self.border_min;
+        map.border_max = self.border_max;
+        map.shrink_timer = 0;
+        map.bonus_food = None;
+        map.extra_food.clear();
+    }
+
+    /// The layout on one line, `<border min> <border max> <walls...>`, for
+    /// replay headers.
+    pub fn layout_line(&self) -> String {
+        format!("{} {} {}", fmt_pos(self.border_min), fmt_pos(self.border_max), fmt_positions(&self.walls))
+    }
+
+    /// Rebuild a `layout_line` on a `width` x `height` board.
+    pub fn parse_layout(width: usize, height: usize, line: &str) -> Result<Self, String> {
+        let mut fields = line.split_whitespace();
+        let mut map = GameMap::new(width, height);
+        map.border_min = parse_pos(fields.next())?;
+        map.border_max = parse_pos(fields.next())?;
+        map.walls = fields.map(|f| parse_pos(Some(f))).collect::<Result<_, _>>()?;
+        let in_bounds = |&(r, c): &(usize, usize)| r < height && c < width;
+        if !map.walls.iter().chain([&map.border_min, &map.border_max]).all(in_bounds) {
+            return Err("layout positions are out of bounds".to_string());
+        }
+        Ok(Snapshot::capture(&Snake::new(width, height), &map, 0))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut f = fs::File::create(path)?;
+        writeln!(f, "{SNAPSHOT_MAGIC}")?;
+        writeln!(f, "size {} {}", self.width, self.height)?;
+        writeln!(f, "direction {}", self.direction.as_char())?;
+        writeln!(f, "length {}", self.length)?;
+        writeln!(f, "points {}", self.points)?;
+        writeln!(f, "survived_ms {}", self.survived_ms)?;
+        writeln!(f, "penalty {}", self.penalty)?;
+        writeln!(f, "coins {}", self.coin_points)?;
+        writeln!(f, "food {}", fmt_pos(self.food))?;
+        writeln!(f, "border {} {}", fmt_pos(self.border_min), fmt_pos(self.border_max))?;
+        writeln!(f, "shrink_timer {}", self.shrink_timer)?;
+        writeln!(f, "frame {}", self.frame_count)?;
+        writeln!(f, "parts {}", fmt_positions(&self.parts))?;
+        writeln!(f, "walls {}", fmt_positions(&self.walls))?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        Self::parse(&contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    fn parse(contents: &str) -> Result<Self, String> {
+        let mut lines = contents.lines();
+        if lines.next().map(str::trim) != Some(SNAPSHOT_MAGIC) {
+            return Err("not a snake-term snapshot".to_string());
+        }
+
+        let mut snap = Snapshot {
+            width: 0,
+            height: 0,
+            parts: Vec::new(),
+            direction: Direction::East,
+            length: 0,
+            points: 0,
+            survived_ms: 0,
+            penalty: 0,
+            coin_points: 0,
+            food: (0, 0),
+            walls: Vec::new(),
+            border_min: (0, 0),
+            border_max: (0, 0),
+            shrink_timer: 0,
+            frame_count: 0,
+        };
+
+        for line in lines {
+            let (key, rest) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+            let mut fields = rest.split_whitespace();
+            match key {
+                "size" => {
+                    snap.width = parse_num(fields.next())?;
+                    snap.height = parse_num(fields.next())?;
+                }
+                "direction" => {
+                    snap.direction = rest
+                        .trim()
+                        .chars()
+                        .next()
+                        .and_then(Direction::from_char)
+                        .ok_or_else(|| format!("bad direction '{rest}'"))?;
+                }
+                "length" => snap.length = parse_num(fields.next())?,
+                "points" => snap.points = parse_num(fields.next())?,
+                "survived_ms" => snap.survived_ms = parse_num(fields.next())?,
+                "penalty" => snap.penalty = parse_num(fields.next())?,
+                "coins" => snap.coin_points = parse_num(fields.next())?,
+                "food" => snap.food = parse_pos(fields.next())?,
+                "border" => {
+                    snap.border_min = parse_pos(fields.next())?;
+                    snap.border_max = parse_pos(fields.next())?;
+                }
+                "shrink_timer" => snap.shrink_timer = parse_num(fields.next())?,
+                "frame" => snap.frame_count = parse_num(fields.next())?,
+                "parts" => snap.parts = fields.map(|f| parse_pos(Some(f))).collect::<Result<_, _>>()?,
+                "walls" => snap.walls = fields.map(|f| parse_pos(Some(f))).collect::<Result<_, _>>()?,
+                "" => {}
+                other => return Err(format!("unknown snapshot field '{other}'")),
+            }
+        }
+
+        let in_bounds = |&(r, c): &(usize, usize)| r < snap.height && c < snap.width;
+        if snap.parts.is_empty()
+            || !snap.parts.iter().all(in_bounds)
+            || !snap.walls.iter().all(in_bounds)
+            || !in_bounds(&snap.food)
+        {
+            return Err("snapshot positions are missing or out of bounds".to_string());
+        }
+        Ok(snap)
+    }
+}
+
+fn fmt_pos((r, c): (usize, usize)) -> String {
+    format!("{r},{c}")
+}
+
+fn fmt_positions(positions: &[(usize, usize)]) -> String {
+    positions.iter().map(|&p| fmt_pos(p)).collect::<Vec<_>>().join(" ")
+}
+
+fn parse_num<T: std::str::FromStr>(field: Option<&str>) -> Result<T, String> {
+    let field = field.ok_or("missing value")?;
+    field.parse().map_err(|_| format!("invalid number '{field}'"))
+}
+
+fn parse_pos(field: Option<&str>) -> Result<(usize, usize), String> {
+    let field = field.ok_or("missing position")?;
+    let (r, c) = field.split_once(',').ok_or_else(|| format!("invalid position '{field}'"))?;
+    Ok((parse_num(Some(r))?, parse_num(Some(c))?))
+}
+
+pub fn recovery_path() -> PathBuf {
+    data_path("recovery.txt")
+}
+
+/// Remove the recovery file after a run ends cleanly. A missing file is fine.
+pub fn clear_recovery(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Settle the recovery file once a run is over: a clean end has nothing
+/// left to recover, but one that failed (a terminal write error, say)
+/// keeps it for the next start.
+pub fn finish_run<T>(path: &Path, result: &io::Result<T>) {
+    if result.is_ok() {
+        let _ = clear_recovery(path);
+    }
+}
+
+/// Tracks when the next periodic autosave is due.
+pub struct Autosave {
+    interval: Duration,
+    last: Instant,
+}
+
+impl Autosave {
+    pub fn new(interval_secs: u64, now: Instant) -> Option<Self> {
+        if interval_secs == 0 {
+            return None;
+        }
+        Some(Autosave { interval: Duration::from_secs(interval_secs), last: now })
+    }
+
+    /// Returns true (and restarts the interval) once per elapsed interval.
+    pub fn due(&mut self, now: Instant) -> bool {
+        if now.duration_since(self.last) >= self.interval {
+            self.last = now;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    fn temp_file(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("snake-term-test-{}-{name}", std::process::id()))
+    }
+
+    #[test]
+    fn test_autosave_cadence() {
+        let start = Instant::now();
+        let mut autosave = Autosave::new(5, start).unwrap();
+        let saves = (1..=20)
+            .filter(|&s| autosave.due(start + Duration::from_secs(s)))
+            .count();
+        assert_eq!(saves, 4);
+        assert!(Autosave::new(0, start).is_none());
+    }
+
+    #[test]
+    fn test_clear_recovery_removes_file() {
+        let path = temp_file("recovery.txt");
+        let settings = Settings::parse_from(["test"]).resolve();
+        let mut snake = Snake::new(20, 20);
+        let mut map = GameMap::new(20, 20);
+        map.walls.push((1, 1));
+        snake.food = (4, 4);
+        snake.coin_points = 5;
+
+        let snap = Snapshot::capture(&snake, &map, 12);
+        snap.save(&path).unwrap();
+        assert!(path.exists());
+        assert_eq!(Snapshot::load(&path).unwrap(), snap);
+
+        let mut restored = Snake::new(20, 20);
+        snap.restore_snake(&mut restored, &settings);
+        assert_eq!(restored.parts, snake.parts);
+        assert_eq!((restored.coin_points, restored.score), (5, 5));
+
+        // A run that ended in an error is exactly what the file is for
+        finish_run(&path, &Err::<(), _>(io::Error::other("terminal gone")));
+        assert!(path.exists());
+        finish_run(&path, &Ok(()));
+        assert!(!path.exists());
+        // Clearing again is not an error
+        clear_recovery(&path).unwrap();
+    }
+
+    #[test]
+    fn test_layout_from_snapshot() {
+        let path = temp_file("layout.txt");
+        let mut old_snake = Snake::new(20, 20);
+        old_snake.length = 9;
+        old_snake.points = 6;
+        let mut map = GameMap::new(20, 20);
+        map.walls = vec![(2, 2), (2, 3), (17, 8)];
+        map.border_min = (1, 1);
+        map.border_max = (19, 18);
+        map.shrink_timer = 120;
+        Snapshot::capture(&old_snake, &map, 300).save(&path).unwrap();
+
+        let snap = Snapshot::load(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        let mut fresh_map = GameMap::new(snap.width, snap.height);
+        let fresh_snake = Snake::new(snap.width, snap.height);
+        snap.restore_layout(&mut fresh_map);
+
+        assert_eq!(fresh_map.walls, map.walls);
+        assert_eq!((fresh_map.border_min, fresh_map.border_max), ((1, 1), (19, 18)));
+        assert_eq!(fresh_map.shrink_timer, 0);
+        assert_eq!(fresh_snake.length, crate::config::INITIAL_SNAKE_LENGTH);
+        assert_eq!(fresh_snake.points, 0);
+
+        // Spawns are checked against the snapshot's board, not the flags'
+        let mut small = Snapshot::capture(&old_snake, &GameMap::new(8, 6), 0);
+        let mut settings = Settings::parse_from(["test", "--start-pos", "15,15"]).resolve();
+        settings.use_snapshot(small.clone());
+        assert_eq!((settings.map_width, settings.map_height), (8, 6));
+        assert!(settings.validate().is_err());
+        let mut settings = Settings::parse_from(["test"]).resolve();
+        settings.use_snapshot(small.clone());
+        assert!(settings.validate().is_ok());
+        let (head, _) = settings.spawn(0, 8, 6);
+        small.walls.push(head);
+        settings.use_snapshot(small);
+        assert!(settings.validate().is_err());
+    }
+}
@@ -0,0 +1,125 @@
+use crate::game_map::GameMap;
+use crate::score::{RunSummary, ScoreWeights};
+use crate::snake::Snake;
+
+/// Borrowed view of the board for hashing, plus the scoring function.
+pub struct GameState<'a> {
+    pub snakes: &'a [&'a Snake],
+    pub map: &'a GameMap,
+}
+
+impl<'a> GameState<'a> {
+    pub fn new(snakes: &'a [&'a Snake], map: &'a GameMap) -> Self {
+        GameState { snakes, map }
+    }
+
+    /// Score for a run under the `--score-formula` weights. Every score
+    /// shown or compared goes through here.
+    pub fn compute_score(weights: &ScoreWeights, run: &RunSummary) -> usize {
+        weights.food * run.food + weights.length * run.growth + weights.time * run.time_secs as usize
+    }
+
+    /// FNV-1a over snake bodies (both strands), food, walls, borders and scores. Unlike
+    /// `DefaultHasher` the value never changes between runs or Rust versions,
+    /// so it can be stored in replay files.
+    pub fn state_hash(&self) -> u64 {
+        let mut h = Fnv::new();
+        for snake in self.snakes {
+            h.write_usize(snake.parts.len());
+            for &pos in &snake.parts {
+                h.write_pos(pos);
+            }
+            // Only a snake with a second strand hashes it, so others keep their values
+            if let Some(ref twin) = snake.twin {
+                h.write_usize(twin.parts.len());
+                for &pos in &twin.parts {
+                    h.write_pos(pos);
+                }
+            }
+            h.write_pos(snake.food);
+            h.write_usize(snake.score);
+        }
+        let map = self.map;
+        h.write_usize(map.extra_food.len());
+        for &pos in &map.extra_food {
+            h.write_pos(pos);
+        }
+        h.write_usize(map.walls.len());
+        for &pos in &map.walls {
+            h.write_pos(pos);
+        }
+        h.write_pos(map.border_min);
+        h.write_pos(map.border_max);
+        h.0
+    }
+}
+
+struct Fnv(u64);
+
+impl Fnv {
+    fn new() -> Self {
+        Fnv(0xCBF2_9CE4_8422_2325)
+    }
+
+    fn write_usize(&mut self, v: usize) {
+        for byte in (v as u64).to_le_bytes() {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01B3);
+        }
+    }
+
+    fn write_pos(&mut self, (r, c): (usize, usize)) {
+        self.write_usize(r);
+        self.write_usize(c);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Direction, INITIAL_SNAKE_LENGTH};
+
+    fn build() -> (Snake, GameMap) {
+        let mut snake = Snake::new(20, 20);
+        snake.spawn_at((5, 7), Direction::East, INITIAL_SNAKE_LENGTH);
+        snake.food = (9, 9);
+        let mut map = GameMap::new(20, 20);
+        map.walls = vec![(1, 1), (2, 3)];
+        (snake, map)
+    }
+
+    #[test]
+    fn test_compute_score_weights() {
+        let run = RunSummary { food: 5, growth: 7, time_secs: 30 };
+
+        assert_eq!(GameState::compute_score(&ScoreWeights::default(), &run), 5);
+
+        let by_length: ScoreWeights = "length=1".parse().unwrap();
+        assert_eq!(GameState::compute_score(&by_length, &run), 7);
+
+        let mixed: ScoreWeights = "food=2, length=1, time=1".parse().unwrap();
+        assert_eq!(GameState::compute_score(&mixed, &run), 2 * 5 + 7 + 30);
+    }
+
+    #[test]
+    fn test_state_hash_stable_and_distinct() {
+        let (a_snake, a_map) = build();
+        let (b_snake, b_map) = build();
+        let a = GameState::new(&[&a_snake], &a_map).state_hash();
+        assert_eq!(a, GameState::new(&[&b_snake], &b_map).state_hash());
+
+        let (mut moved, map) = build();
+        moved.head = (5, 8);
+        moved.parts.pop_front();
+        moved.parts.push_back((5, 8));
+        assert_ne!(a, GameState::new(&[&moved], &map).state_hash());
+
+        let (snake, mut walled) = build();
+        walled.walls.push((4, 4));
+        assert_ne!(a, GameState::new(&[&snake], &walled).state_hash());
+
+        let (mut scored, map) = build();
+        scored.score = 1;
+        assert_ne!(a, GameState::new(&[&scored], &map).state_hash());
+    }
+}
@@ -0,0 +1,149 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::highscore::read_data_path;
+
+/// Heat-map resolution. Deaths are scaled onto this grid so boards of any
+/// size share the same buckets.
+pub const HEATMAP_ROWS: usize = 10;
+pub const HEATMAP_COLS: usize = 20;
+
+/// Shades from coldest to hottest.
+const HEAT_RAMP: [char; 10] = [' ', '.', ':', '-', '=', '+', '*', '#', '%', '@'];
+
+pub const STATS_FILE: &str = "stats.txt";
+
+pub fn stats_path() -> PathBuf {
+    read_data_path(STATS_FILE)
+}
+
+/// Lifetime statistics kept across runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stats {
+    pub deaths: [[u32; HEATMAP_COLS]; HEATMAP_ROWS],
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Stats { deaths: [[0; HEATMAP_COLS]; HEATMAP_ROWS] }
+    }
+}
+
+impl Stats {
+    /// Load the stats file; a missing or unreadable file starts from zero
+    /// and malformed lines are skipped.
+    pub fn load(path: &Path) -> Stats {
+        let mut stats = Stats::default();
+        let Ok(text) = fs::read_to_string(path) else {
+            return stats;
+        };
+        let mut row = 0;
+        for line in text.lines() {
+            let mut words = line.split_whitespace();
+            if words.next() != Some("deaths") || row >= HEATMAP_ROWS {
+                continue;
+            }
+            for (cell, word) in stats.deaths[row].iter_mut().zip(words) {
+                *cell = word.parse().unwrap_or(0);
+            }
+            row += 1;
+        }
+        stats
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut out = String::new();
+        for row in &self.deaths {
+            let counts: Vec<String> = row.iter().map(|c| c.to_string()).collect();
+            out.push_str(&format!("deaths {}\n", counts.join(" ")));
+        }
+        fs::write(path, out)
+    }
+
+    /// Count a death at board cell `pos` on a `width`x`height` map.
+    pub fn record_death(&mut self, pos: (usize, usize), width: usize, height: usize) {
+        let (r, c) = heatmap_bucket(pos, width, height);
+        self.deaths[r][c] = self.deaths[r][c].saturating_add(1);
+    }
+
+    pub fn total_deaths(&self) -> u32 {
+        self.deaths.iter().flatten().sum()
+    }
+}
+
+/// Heat-map bucket of board cell `pos` on a `width`x`height` map.
+pub fn heatmap_bucket((row, col): (usize, usize), width: usize, height: usize) -> (usize, usize) {
+    let r = row * HEATMAP_ROWS / height.max(1);
+    let c = col * HEATMAP_COLS / width.max(1);
+    (r.min(HEATMAP_ROWS - 1), c.min(HEATMAP_COLS - 1))
+}
+
+/// Draw the death heat map as framed lines, shading each bucket relative to
+/// the deadliest one. Each bucket is two characters wide so the grid looks
+/// roughly square in a terminal.
+pub fn render_heatmap(stats: &Stats) -> Vec<String> {
+    let max = stats.deaths.iter().flatten().copied().max().unwrap_or(0);
+    let edge = format!("+{}+", "-".repeat(HEATMAP_COLS * 2));
+    let mut lines = vec![edge.clone()];
+    for row in &stats.deaths {
+        let mut line = String::from("|");
+        for &count in row {
+            let shade = if count == 0 || max == 0 {
+                HEAT_RAMP[0]
+            } else {
+                // Any death is at least faintly visible
+                let level = (count as usize * (HEAT_RAMP.len() - 1)).div_ceil(max as usize);
+                HEAT_RAMP[level.max(1)]
+            };
+            line.push(shade);
+            line.push(shade);
+        }
+        line.push('|');
+        lines.push(line);
+    }
+    lines.push(edge);
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_death_buckets() {
+        let mut stats = Stats::default();
+        // Corners of a 40x20 board and the same spot on a board twice the size
+        stats.record_death((0, 0), 40, 20);
+        stats.record_death((19, 39), 40, 20);
+        stats.record_death((10, 20), 40, 20);
+        stats.record_death((20, 40), 80, 40);
+        // A 5x5 board spreads its cells over every other bucket
+        stats.record_death((4, 4), 5, 5);
+
+        assert_eq!(stats.deaths[0][0], 1);
+        assert_eq!(stats.deaths[HEATMAP_ROWS - 1][HEATMAP_COLS - 1], 1);
+        assert_eq!(stats.deaths[5][10], 2);
+        assert_eq!(stats.deaths[8][16], 1);
+        assert_eq!(stats.total_deaths(), 5);
+
+        let lines = render_heatmap(&stats);
+        assert_eq!(lines.len(), HEATMAP_ROWS + 2);
+        assert_eq!(&lines[6][21..23], "@@");
+        assert_eq!(&lines[1][1..3], "++");
+        assert_eq!(&lines[2][1..3], "  ");
+    }
+
+    #[test]
+    fn test_stats_round_trip() {
+        let path = std::env::temp_dir().join(format!("snake_stats_{}.txt", std::process::id()));
+        let mut stats = Stats::default();
+        stats.record_death((3, 7), 20, 10);
+        stats.record_death((3, 7), 20, 10);
+        stats.save(&path).unwrap();
+        let loaded = Stats::load(&path);
+        let _ = fs::remove_file(&path);
+        assert_eq!(loaded, stats);
+        assert_eq!(Stats::load(&path), Stats::default());
+    }
+}